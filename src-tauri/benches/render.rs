@@ -0,0 +1,131 @@
+//! Cost of the work the render cache saves, on synthetic square meshes. Renders are fetched
+//! through the same paths as the app, with the cache warm and cold.
+//!
+//! Usage: `cargo bench --bench render`
+
+use std::{env, fmt::Write, fs, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use manycore_parser::ManycoreSystem;
+use manycore_svg::SVG;
+use manycore_visualiser::benchmark::LoadedSystem;
+
+static SIDES: &[usize] = &[16, 32, 64];
+static DIRECTIONS: &[&str] = &["North", "East", "South", "West"];
+
+/// ManyCore XML for a `side` x `side` mesh with a chain of tasks, one per core.
+fn synthetic_xml(side: usize) -> String {
+    let core_count = side * side;
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ManycoreSystem xmlns=\"https://www.york.ac.uk/physics-engineering-technology/ManycoreSystems\" rows=\"{side}\" columns=\"{side}\" routingAlgo=\"RowFirst\"><TaskGraph>"
+    );
+
+    for task in 0..core_count {
+        let _ = write!(
+            xml,
+            "<Task id=\"{task}\" computationCost=\"{}\"/>",
+            10 + task % 190
+        );
+    }
+    for task in 1..core_count {
+        let _ = write!(
+            xml,
+            "<Edge from=\"{}\" to=\"{task}\" communicationCost=\"{}\"/>",
+            task - 1,
+            10 * (1 + task % 10)
+        );
+    }
+    xml.push_str("</TaskGraph><Cores>");
+
+    for core in 0..core_count {
+        let _ = write!(
+            xml,
+            "<Core id=\"{core}\" allocatedTask=\"{core}\" temperature=\"{}\"><Router age=\"{}\"/><Channels>",
+            20 + core % 60,
+            core % 100
+        );
+        for direction in DIRECTIONS {
+            let _ = write!(
+                xml,
+                "<Channel direction=\"{direction}\" actualComCost=\"{}\" bandwidth=\"100\"/>",
+                core % 90
+            );
+        }
+        xml.push_str("</Channels></Core>");
+    }
+    xml.push_str("</Cores></ManycoreSystem>");
+
+    xml
+}
+
+fn synthetic_system(side: usize) -> ManycoreSystem {
+    let file_path = env::temp_dir().join(format!("manycore_bench_{side}.xml"));
+    fs::write(&file_path, synthetic_xml(side)).expect("Could not write synthetic system");
+
+    let manycore = ManycoreSystem::parse_file(file_path.to_string_lossy().to_string().as_str())
+        .expect("Could not parse synthetic system");
+    let _ = fs::remove_file(file_path);
+
+    manycore
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+
+    for side in SIDES {
+        let manycore = synthetic_system(*side);
+        group.bench_with_input(BenchmarkId::new("build", side), &manycore, |b, manycore| {
+            b.iter(|| SVG::try_from(manycore))
+        });
+
+        let mut system = LoadedSystem::new(manycore).expect("Could not render synthetic system");
+
+        // get_svg after the system changed: build, serialise and derive overlays
+        group.bench_function(BenchmarkId::new("get_svg_cold", side), |b| {
+            b.iter(|| {
+                system.invalidate();
+                system.get_svg()
+            })
+        });
+        // get_svg with nothing changed since the last render
+        group.bench_function(BenchmarkId::new("get_svg_warm", side), |b| {
+            b.iter(|| system.get_svg())
+        });
+        // Whole render export after a configuration was applied
+        group.bench_function(BenchmarkId::new("serialise_render_cold", side), |b| {
+            b.iter(|| {
+                system.invalidate_svg();
+                system.serialise_render()
+            })
+        });
+        // Whole render export with nothing changed since the last one
+        group.bench_function(BenchmarkId::new("serialise_render_warm", side), |b| {
+            b.iter(|| system.serialise_render())
+        });
+    }
+
+    group.finish();
+}
+
+fn attributes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("attributes");
+
+    for side in SIDES {
+        let manycore = synthetic_system(*side);
+        let cached = Arc::new(manycore.configurable_attributes().clone());
+
+        // What get_attributes paid before the cache
+        group.bench_with_input(BenchmarkId::new("clone", side), &manycore, |b, manycore| {
+            b.iter(|| manycore.configurable_attributes().clone())
+        });
+        group.bench_with_input(BenchmarkId::new("cached", side), &cached, |b, cached| {
+            b.iter(|| Arc::clone(cached))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, render, attributes);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Stand-in for a simulator pushing state to the live server.
+//!
+//! Usage: `cargo run --example live_client -- <token> <file> [port]`
+//!
+//! The token is shown by the app when the live server starts.
+//!
+//! `.xml` files are pushed as whole systems, anything else as a JSON update message, e.g.
+//! `{"channel:0:East": {"actualComCost": 42}}`.
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::TcpStream,
+    process::ExitCode,
+};
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let (Some(token), Some(file_path)) = (args.next(), args.next()) else {
+        eprintln!("Usage: live_client <token> <file> [port]");
+        return ExitCode::FAILURE;
+    };
+    let port = args.next().unwrap_or_else(|| String::from("7878"));
+
+    let body = match fs::read_to_string(&file_path) {
+        Ok(body) => body,
+        Err(e) => {
+            eprintln!("Could not read {file_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let path = if file_path.ends_with(".xml") {
+        "/system"
+    } else {
+        "/update"
+    };
+
+    let mut stream = match TcpStream::connect(format!("127.0.0.1:{port}")) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Could not connect to the live server: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let request = format!(
+        "POST {path} HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nAuthorization: Bearer {token}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let mut response = String::new();
+    if let Err(e) = stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.read_to_string(&mut response))
+    {
+        eprintln!("Request failed: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    println!("{response}");
+
+    ExitCode::SUCCESS
+}
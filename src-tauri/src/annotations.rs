@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use chrono::Utc;
+use serde::Deserialize;
+use sha1_smol::Sha1;
+
+use crate::{
+    document::Document,
+    overlay::{escape, push_text, Decoration, GridGeometry, Overlays, Placement},
+    system::serialise,
+};
+
+static TITLE: &'static str = "export_title";
+static CAPTION: &'static str = "export_caption";
+static METADATA_NAMESPACE: &'static str =
+    "https://github.com/ManyCore-Visualiser/manycore_visualiser";
+static SVG_OPENING_TAG: &'static str = "<svg";
+
+/// Optional annotations added to an exported render.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportAnnotations {
+    pub title: Option<String>,
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub include_source: bool,
+    #[serde(default)]
+    pub include_timestamp: bool,
+    /// Include hashes of the system XML and configuration.
+    #[serde(default)]
+    pub include_hash: bool,
+    /// Draw the annotations in the render. If false, they are only written as metadata.
+    #[serde(default)]
+    pub render: bool,
+}
+
+/// Git style object hash: SHA-1 of a blob header followed by the content.
+fn git_hash(content: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content.as_bytes());
+
+    hasher.digest().to_string()
+}
+
+/// Resolved annotation values, as (keyword, value) pairs.
+/// Keywords follow the PNG predefined text keywords where one exists.
+pub(crate) struct ExportStamp {
+    render: bool,
+    pub entries: Vec<(&'static str, String)>,
+}
+
+impl ExportStamp {
+    pub fn new(annotations: ExportAnnotations, document: &Document) -> Result<Self, String> {
+        let mut entries = Vec::new();
+
+        if let Some(title) = annotations.title.filter(|title| !title.is_empty()) {
+            entries.push(("Title", title));
+        }
+
+        if let Some(caption) = annotations.caption.filter(|caption| !caption.is_empty()) {
+            entries.push(("Description", caption));
+        }
+
+        if annotations.include_source {
+            if let Some(source) = &document.source_path {
+                let file_name = Path::new(source)
+                    .file_name()
+                    .map_or(source.clone(), |name| name.to_string_lossy().to_string());
+                entries.push(("Source", file_name));
+            }
+        }
+
+        if annotations.include_timestamp {
+            entries.push(("Creation Time", Utc::now().to_rfc3339()));
+        }
+
+        if annotations.include_hash {
+            if let Some(manycore) = &document.manycore {
+                entries.push(("XML Hash", git_hash(serialise(manycore)?.as_str())));
+            }
+
+            if let Some(configuration) = &document.configuration {
+                let configuration =
+                    serde_json::to_string(configuration).map_err(|e| e.to_string())?;
+                entries.push(("Configuration Hash", git_hash(configuration.as_str())));
+            }
+        }
+
+        entries.push((
+            "Software",
+            format!("ManyCore Visualiser {}", env!("CARGO_PKG_VERSION")),
+        ));
+
+        Ok(Self {
+            render: annotations.render,
+            entries,
+        })
+    }
+
+    fn get(&self, keyword: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .find(|(key, _)| *key == keyword)
+            .map(|(_, value)| value)
+    }
+
+    /// Adds title and caption decorations to `overlays`.
+    /// The caption block also lists source, timestamp and hashes.
+    pub fn decorate(&self, overlays: &mut Overlays) {
+        let Some(geometry) = overlays.geometry else {
+            return;
+        };
+
+        if !self.render {
+            return;
+        }
+
+        if let Some(title) = self.get("Title") {
+            let font_size = geometry.font_size() * 2.0;
+            let mut markup = String::from("<g id=\"exportTitle\">");
+            push_text(&mut markup, 0.0, font_size, font_size, "black", title);
+            markup.push_str("</g>");
+
+            overlays.set_decoration(
+                TITLE,
+                Decoration {
+                    placement: Placement::Top,
+                    width: font_size * 0.6 * title.chars().count() as f32,
+                    height: font_size * 1.3,
+                    markup,
+                },
+            );
+        }
+
+        let lines = self
+            .entries
+            .iter()
+            .filter_map(|(keyword, value)| match *keyword {
+                "Title" | "Software" => None,
+                "Description" => Some(value.clone()),
+                _ => Some(format!("{keyword}: {value}")),
+            })
+            .collect::<Vec<String>>();
+
+        if !lines.is_empty() {
+            overlays.set_decoration(CAPTION, caption(&geometry, &lines));
+        }
+    }
+
+    /// Inserts a `<metadata>` element right after the root opening tag.
+    pub fn add_metadata(&self, mut svg_string: String) -> String {
+        let attributes = self
+            .entries
+            .iter()
+            .map(|(keyword, value)| {
+                format!(
+                    " {}=\"{}\"",
+                    keyword.to_lowercase().replace(' ', "-"),
+                    escape(value)
+                )
+            })
+            .collect::<String>();
+
+        let metadata = format!(
+            "<metadata><mcv:export xmlns:mcv=\"{METADATA_NAMESPACE}\"{attributes}/></metadata>"
+        );
+
+        if let Some(root_start) = svg_string.find(SVG_OPENING_TAG) {
+            if let Some(root_end) = svg_string[root_start..].find('>') {
+                svg_string.insert_str(root_start + root_end + 1, metadata.as_str());
+            }
+        }
+
+        svg_string
+    }
+}
+
+fn caption(geometry: &GridGeometry, lines: &[String]) -> Decoration {
+    let font_size = geometry.font_size();
+    let line_height = font_size * 1.4;
+    let mut markup = String::from("<g id=\"exportCaption\">");
+    let mut width: f32 = 0.0;
+
+    for (i, line) in lines.iter().enumerate() {
+        push_text(
+            &mut markup,
+            0.0,
+            line_height * (i + 1) as f32,
+            font_size,
+            "black",
+            line,
+        );
+        width = width.max(font_size * 0.6 * line.chars().count() as f32);
+    }
+    markup.push_str("</g>");
+
+    Decoration {
+        placement: Placement::Bottom,
+        width,
+        height: line_height * lines.len() as f32 + font_size * 0.4,
+        markup,
+    }
+}
@@ -0,0 +1,58 @@
+//! Entry points for the benchmarks in `benches/`, which only see the public items of the crate.
+
+use manycore_parser::ManycoreSystem;
+
+use crate::{
+    export::serialise_render,
+    result_status::ResultStatus,
+    svg::{display_svg, SVGResult},
+    State,
+};
+
+/// A system loaded and rendered as the app holds it.
+pub struct LoadedSystem {
+    state: State,
+}
+
+impl LoadedSystem {
+    /// Loads `manycore` and renders it once.
+    pub fn new(manycore: ManycoreSystem) -> Result<Self, String> {
+        let mut ret = Self {
+            state: State::new(),
+        };
+        let _ = ret.state.write().manycore.insert(manycore);
+        ret.get_svg()?;
+
+        Ok(ret)
+    }
+
+    /// Drops the render cache, as loading a system or applying updates does.
+    pub fn invalidate(&mut self) {
+        self.state.write().cache.invalidate();
+    }
+
+    /// Drops the serialised render, as applying a configuration does.
+    pub fn invalidate_svg(&mut self) {
+        self.state.write().cache.invalidate_svg();
+    }
+
+    /// What [`get_svg`](crate::svg::get_svg) sends to the app.
+    pub fn get_svg(&mut self) -> Result<String, String> {
+        let mut ret = SVGResult {
+            status: ResultStatus::Error,
+            message: String::new(),
+            svg: None,
+        };
+        display_svg(&mut ret, &self.state, None);
+
+        match (ret.status, ret.svg) {
+            (ResultStatus::Ok, Some(svg)) => Ok(svg.content),
+            _ => Err(ret.message),
+        }
+    }
+
+    /// The whole render as exports serialise it.
+    pub fn serialise_render(&mut self) -> Result<String, String> {
+        serialise_render(&mut self.state.write(), None, None, None, None)
+    }
+}
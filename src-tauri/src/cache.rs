@@ -0,0 +1,64 @@
+use std::sync::{Arc, OnceLock};
+
+use manycore_parser::{ConfigurableAttributes, ManycoreSystem};
+use manycore_svg::SVG;
+
+/// Data derived from the loaded system and its render, reused until they change.
+/// Entries are dropped when what they are derived from changes and rebuilt on next use.
+#[derive(Default)]
+pub(crate) struct RenderCache {
+    /// Serialised [`SVG`], without overlays.
+    svg_string: Option<String>,
+    /// Filled on first use, readers can share it.
+    attributes: OnceLock<Arc<ConfigurableAttributes>>,
+    /// Bumped whenever the system changes.
+    revision: u64,
+}
+
+impl RenderCache {
+    /// Serialised render, if it is up to date.
+    pub fn svg_string(&self) -> Option<&String> {
+        self.svg_string.as_ref()
+    }
+
+    /// Serialised render, serialising `svg` only if it changed since it was last serialised.
+    pub fn serialise(&mut self, svg: &SVG) -> Result<String, String> {
+        if let Some(svg_string) = &self.svg_string {
+            return Ok(svg_string.clone());
+        }
+
+        let svg_string = String::try_from(svg).map_err(|e| e.to_string())?;
+        self.svg_string = Some(svg_string.clone());
+
+        Ok(svg_string)
+    }
+
+    /// Tells apart versions of the loaded system, for work done outside the document lock.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Stores a freshly serialised render.
+    pub fn store_svg_string(&mut self, svg_string: String) {
+        self.svg_string = Some(svg_string);
+    }
+
+    /// Configurable attributes of `manycore`, cloned only once per system.
+    pub fn attributes(&self, manycore: &ManycoreSystem) -> Arc<ConfigurableAttributes> {
+        self.attributes
+            .get_or_init(|| Arc::new(manycore.configurable_attributes().clone()))
+            .clone()
+    }
+
+    /// The render changed, e.g. a configuration was applied.
+    pub fn invalidate_svg(&mut self) {
+        self.svg_string = None;
+    }
+
+    /// The system changed, everything derived from it is stale.
+    pub fn invalidate(&mut self) {
+        self.svg_string = None;
+        self.attributes = OnceLock::new();
+        self.revision += 1;
+    }
+}
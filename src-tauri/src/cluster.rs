@@ -0,0 +1,378 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    export::{serialise_render, Region},
+    heatmap::ColourMap,
+    overlay::{escape, push_text, GridGeometry},
+    result_status::ResultStatus,
+    svg::SVGObject,
+    system::{ElementId, ElementKind, SystemSnapshot},
+    topology::Topology,
+    State,
+};
+
+// Side of a cluster in the clustered render, in SVG user space
+static CLUSTER_SIZE: f32 = 100.0;
+static CLUSTER_GAP: f32 = 10.0;
+static EMPTY_CLUSTER_COLOUR: &'static str = "#e0e0e0";
+
+/// Which summary of a cluster its colour represents.
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+pub enum ClusterStatistic {
+    #[default]
+    Mean,
+    Max,
+}
+
+/// How cores are grouped in clusters, and which attribute is summarised.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfiguration {
+    /// Rows of cores per cluster.
+    cluster_rows: usize,
+    /// Columns of cores per cluster.
+    cluster_columns: usize,
+    element: ElementKind,
+    attribute: String,
+    #[serde(default)]
+    statistic: ClusterStatistic,
+    #[serde(default)]
+    colour_map: ColourMap,
+}
+
+/// Summary of the elements of a block of cores.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSummary {
+    id: usize,
+    row: usize,
+    column: usize,
+    cores: Vec<usize>,
+    /// Number of elements with a numeric value for the attribute.
+    sample_count: usize,
+    mean: Option<f64>,
+    max: Option<f64>,
+}
+
+/// Clusters laid out on a grid, in row order.
+pub(crate) struct Clustering {
+    cluster_rows: usize,
+    cluster_columns: usize,
+    rows: usize,
+    columns: usize,
+}
+
+impl Clustering {
+    pub fn new(
+        snapshot: &SystemSnapshot,
+        cluster_rows: usize,
+        cluster_columns: usize,
+    ) -> Result<Self, String> {
+        if cluster_rows == 0 || cluster_columns == 0 {
+            return Err(String::from("Clusters must span at least one core."));
+        }
+
+        Ok(Self {
+            cluster_rows,
+            cluster_columns,
+            rows: snapshot.rows.div_ceil(cluster_rows),
+            columns: snapshot.columns.div_ceil(cluster_columns),
+        })
+    }
+
+    pub fn cluster_count(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// Cluster a core belongs to.
+    fn cluster_of(&self, topology: &Topology, core: usize) -> usize {
+        let (row, column) = topology.position(core);
+
+        (row / self.cluster_rows) * self.columns + column / self.cluster_columns
+    }
+
+    /// Span of a cluster in core rows and columns, as (first row, first column, rows, columns).
+    /// Clusters on the bottom and right edges may be smaller.
+    pub fn span(&self, topology: &Topology, cluster: usize) -> (usize, usize, usize, usize) {
+        let row = (cluster / self.columns) * self.cluster_rows;
+        let column = (cluster % self.columns) * self.cluster_columns;
+
+        (
+            row,
+            column,
+            self.cluster_rows.min(topology.rows - row),
+            self.cluster_columns.min(topology.columns - column),
+        )
+    }
+
+    /// Area covered by a cluster in the render, as (x, y, width, height).
+    pub fn rect(
+        &self,
+        topology: &Topology,
+        geometry: &GridGeometry,
+        cluster: usize,
+    ) -> (f32, f32, f32, f32) {
+        let (row, column, rows, columns) = self.span(topology, cluster);
+
+        (
+            geometry.x + column as f32 * geometry.cell_width,
+            geometry.y + row as f32 * geometry.cell_height,
+            columns as f32 * geometry.cell_width,
+            rows as f32 * geometry.cell_height,
+        )
+    }
+
+    fn cores(&self, topology: &Topology, cluster: usize) -> Vec<usize> {
+        let (row, column, rows, columns) = self.span(topology, cluster);
+
+        (row..row + rows)
+            .flat_map(|row| (column..column + columns).map(move |column| (row, column)))
+            .map(|(row, column)| topology.core_at(row, column))
+            .collect()
+    }
+}
+
+fn element_core(element: &ElementId) -> usize {
+    match element {
+        ElementId::Core(core) | ElementId::Router(core) => *core,
+        ElementId::Channel(channel) => channel.core,
+    }
+}
+
+/// Groups cores in clusters and summarises the attribute over each of them.
+/// Channels belong to the cluster of the router they leave.
+fn summarise(
+    snapshot: &SystemSnapshot,
+    configuration: &ClusterConfiguration,
+) -> Result<Vec<ClusterSummary>, String> {
+    let topology = Topology::from_snapshot(snapshot);
+    let clustering = Clustering::new(
+        snapshot,
+        configuration.cluster_rows,
+        configuration.cluster_columns,
+    )?;
+
+    let mut samples = vec![Vec::new(); clustering.cluster_count()];
+    for (element, value) in
+        snapshot.numeric_values(configuration.element, configuration.attribute.as_str())
+    {
+        samples[clustering.cluster_of(&topology, element_core(&element))].push(value);
+    }
+
+    Ok(samples
+        .into_iter()
+        .enumerate()
+        .map(|(id, values)| {
+            let mean =
+                (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+            let max = values.iter().copied().reduce(f64::max);
+
+            ClusterSummary {
+                id,
+                row: id / clustering.columns,
+                column: id % clustering.columns,
+                cores: clustering.cores(&topology, id),
+                sample_count: values.len(),
+                mean,
+                max,
+            }
+        })
+        .collect())
+}
+
+fn format_value(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => String::from("-"),
+    }
+}
+
+/// Draws one tile per cluster, coloured by the chosen statistic and labelled with its mean and max.
+/// Tiles carry a `data-cluster` attribute for the frontend to drill into.
+fn clustered_svg(
+    clusters: &[ClusterSummary],
+    configuration: &ClusterConfiguration,
+    rows: usize,
+    columns: usize,
+) -> String {
+    let value = |cluster: &ClusterSummary| match configuration.statistic {
+        ClusterStatistic::Mean => cluster.mean,
+        ClusterStatistic::Max => cluster.max,
+    };
+    let (min, max) = clusters
+        .iter()
+        .filter_map(value)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+
+    let step = CLUSTER_SIZE + CLUSTER_GAP;
+    let width = columns as f32 * step + CLUSTER_GAP;
+    let height = rows as f32 * step + CLUSTER_GAP;
+    let font_size = CLUSTER_SIZE * 0.14;
+
+    let mut ret = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\"><g id=\"clusters\" data-attribute=\"{}\">",
+        escape(configuration.attribute.as_str())
+    );
+
+    for cluster in clusters {
+        let x = CLUSTER_GAP + cluster.column as f32 * step;
+        let y = CLUSTER_GAP + cluster.row as f32 * step;
+        let colour = match value(cluster) {
+            Some(value) => configuration.colour_map.colour(if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            }),
+            None => EMPTY_CLUSTER_COLOUR.to_string(),
+        };
+
+        let _ = write!(
+            ret,
+            "<g data-cluster=\"{}\"><rect x=\"{x}\" y=\"{y}\" width=\"{CLUSTER_SIZE}\" height=\"{CLUSTER_SIZE}\" fill=\"{colour}\" stroke=\"black\"/><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\" fill-opacity=\"0.8\"/>",
+            cluster.id,
+            x + CLUSTER_SIZE * 0.05,
+            y + CLUSTER_SIZE * 0.05,
+            CLUSTER_SIZE * 0.9,
+            font_size * 3.6,
+        );
+        for (line, text) in [
+            format!("#{} ({} cores)", cluster.id, cluster.cores.len()),
+            format!("mean {}", format_value(cluster.mean)),
+            format!("max {}", format_value(cluster.max)),
+        ]
+        .iter()
+        .enumerate()
+        {
+            push_text(
+                &mut ret,
+                x + CLUSTER_SIZE * 0.08,
+                y + CLUSTER_SIZE * 0.05 + font_size * (line as f32 + 1.0) * 1.1,
+                font_size,
+                "black",
+                text.as_str(),
+            );
+        }
+        ret.push_str("</g>");
+    }
+
+    ret.push_str("</g></svg>");
+    ret
+}
+
+#[derive(Serialize)]
+pub struct ClusterViewResult {
+    status: ResultStatus,
+    message: String,
+    clusters: Vec<ClusterSummary>,
+    svg: Option<SVGObject>,
+}
+
+/// Level of detail view of the loaded system: cores are grouped in clusters, each drawn as a
+/// single tile summarising the attribute over its elements.
+#[tauri::command]
+pub fn get_clustered_view(
+    configuration: ClusterConfiguration,
+    state: tauri::State<State>,
+) -> ClusterViewResult {
+    let mut ret = ClusterViewResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        clusters: Vec::new(),
+        svg: None,
+    };
+
+    let document = state.read();
+    let Some(snapshot) = &document.snapshot else {
+        ret.message = String::from("Load a system before clustering it.");
+        return ret;
+    };
+
+    match summarise(snapshot, &configuration) {
+        Ok(clusters) => {
+            let rows = snapshot.rows.div_ceil(configuration.cluster_rows);
+            let columns = snapshot.columns.div_ceil(configuration.cluster_columns);
+
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Successfully generated clustered view.");
+            ret.svg = Some(SVGObject::new(clustered_svg(
+                &clusters,
+                &configuration,
+                rows,
+                columns,
+            )));
+            ret.clusters = clusters;
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
+
+#[derive(Serialize)]
+pub struct ClusterDetailResult {
+    status: ResultStatus,
+    message: String,
+    cores: Vec<usize>,
+    svg: Option<SVGObject>,
+}
+
+/// Renders the cores of one cluster at full detail, overlays included. Drawings outside the
+/// cluster are left out.
+#[tauri::command]
+pub fn drill_into_cluster(
+    cluster_rows: usize,
+    cluster_columns: usize,
+    cluster: usize,
+    state: tauri::State<State>,
+) -> ClusterDetailResult {
+    let mut ret = ClusterDetailResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        cores: Vec::new(),
+        svg: None,
+    };
+
+    let mut document = state.write();
+    let (Some(_), Some(snapshot), Some(geometry)) = (
+        &document.svg,
+        &document.snapshot,
+        document.overlays.geometry,
+    ) else {
+        ret.message = String::from("Load a system before clustering it.");
+        return ret;
+    };
+
+    let topology = Topology::from_snapshot(snapshot);
+    let clustering = match Clustering::new(snapshot, cluster_rows, cluster_columns) {
+        Ok(clustering) => clustering,
+        Err(e) => {
+            ret.message = e;
+            return ret;
+        }
+    };
+    if cluster >= clustering.cluster_count() {
+        ret.message = format!("Cluster {cluster} does not exist.");
+        return ret;
+    }
+
+    let region = Region::from_rect(clustering.rect(&topology, &geometry, cluster));
+
+    // Only the cluster's cores are sent to the frontend
+    match serialise_render(&mut document, None, Some(region), None, None)
+        .and_then(|svg_string| region.strip_outside(svg_string.as_str()))
+    {
+        Ok(svg_string) => {
+            ret.status = ResultStatus::Ok;
+            ret.message = format!("Showing cluster {cluster}.");
+            ret.cores = clustering.cores(&topology, cluster);
+            ret.svg = Some(SVGObject::new(svg_string));
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
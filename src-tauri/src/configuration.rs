@@ -1,5 +1,7 @@
 use manycore_parser::ConfigurableAttributes;
 use manycore_svg::{ConfigurableBaseConfiguration, CONFIGURABLE_BASE_CONFIGURATION};
+use std::sync::Arc;
+
 use serde::Serialize;
 
 use crate::{result_status::ResultStatus, State};
@@ -8,7 +10,7 @@ use crate::{result_status::ResultStatus, State};
 pub struct AttributesResult {
     status: ResultStatus,
     message: String,
-    attributes: Option<ConfigurableAttributes>,
+    attributes: Option<Arc<ConfigurableAttributes>>,
 }
 
 #[tauri::command]
@@ -19,17 +21,16 @@ pub fn get_attributes(state: tauri::State<State>) -> AttributesResult {
         attributes: None,
     };
 
-    if let Ok(manycore_mutex) = state.manycore.lock() {
-        match &*manycore_mutex {
-            Some(manycore) => {
-                ret.status = ResultStatus::Ok;
-                ret.message = String::from("Ok");
-                // TODO: Might want to do this without clone if possible, can't think of how now
-                ret.attributes = Some(manycore.configurable_attributes().clone());
-            }
-            None => {
-                ret.message = String::from("Load a system before generating a render.");
-            }
+    let document = state.read();
+    match &document.manycore {
+        Some(manycore) => {
+            // Attributes only change with the system, clone them once
+            ret.attributes = Some(document.cache.attributes(manycore));
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Ok");
+        }
+        None => {
+            ret.message = String::from("Load a system before generating a render.");
         }
     }
 
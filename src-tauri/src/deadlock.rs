@@ -0,0 +1,304 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write,
+};
+
+use serde::Serialize;
+
+use crate::{
+    document::Document,
+    overlay::GridGeometry,
+    result_status::ResultStatus,
+    routing::{route_task_graph, RoutingAlgorithm},
+    svg::{display_render, SVGObject, SVGResult},
+    topology::{Channel, Topology},
+    State,
+};
+
+static DEADLOCK_LAYER: &'static str = "deadlock_cycles";
+static DEADLOCK_COLOUR: &'static str = "#ff0000";
+static OBSERVED_ALGORITHM: &'static str = "Observed";
+
+/// Channel dependency graph: an edge `a -> b` means a flow holding `a` requests `b`.
+#[derive(Default)]
+pub(crate) struct ChannelDependencyGraph {
+    channels: Vec<Channel>,
+    indices: BTreeMap<Channel, usize>,
+    successors: Vec<BTreeSet<usize>>,
+}
+
+impl ChannelDependencyGraph {
+    fn index(&mut self, channel: Channel) -> usize {
+        if let Some(index) = self.indices.get(&channel) {
+            return *index;
+        }
+
+        let index = self.channels.len();
+        self.channels.push(channel);
+        self.indices.insert(channel, index);
+        self.successors.push(BTreeSet::new());
+
+        index
+    }
+
+    pub fn add_dependency(&mut self, from: Channel, to: Channel) {
+        let from = self.index(from);
+        let to = self.index(to);
+
+        self.successors[from].insert(to);
+    }
+
+    pub fn dependency_count(&self) -> usize {
+        self.successors.iter().map(BTreeSet::len).sum()
+    }
+
+    /// Strongly connected components, computed with an iterative Kosaraju.
+    fn components(&self) -> Vec<Vec<usize>> {
+        let n = self.channels.len();
+
+        // First pass: post-order over the graph
+        let mut visited = vec![false; n];
+        let mut order = Vec::with_capacity(n);
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+
+            visited[start] = true;
+            let mut stack = vec![(start, self.successors[start].iter())];
+            while let Some((node, successors)) = stack.last_mut() {
+                let node = *node;
+                let next = successors.next().copied();
+
+                match next {
+                    Some(next) if !visited[next] => {
+                        visited[next] = true;
+                        stack.push((next, self.successors[next].iter()));
+                    }
+                    Some(_) => {}
+                    None => {
+                        order.push(node);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // Second pass: flood the transposed graph in reverse post-order
+        let mut predecessors = vec![Vec::new(); n];
+        for (from, successors) in self.successors.iter().enumerate() {
+            for to in successors {
+                predecessors[*to].push(from);
+            }
+        }
+
+        let mut assigned = vec![false; n];
+        let mut ret = Vec::new();
+        for start in order.into_iter().rev() {
+            if assigned[start] {
+                continue;
+            }
+
+            assigned[start] = true;
+            let mut component = Vec::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                component.push(node);
+                for previous in &predecessors[node] {
+                    if !assigned[*previous] {
+                        assigned[*previous] = true;
+                        stack.push(*previous);
+                    }
+                }
+            }
+
+            ret.push(component);
+        }
+
+        ret
+    }
+
+    /// Shortest cycle through `start` that stays inside `component`.
+    fn cycle_through(&self, start: usize, component: &BTreeSet<usize>) -> Option<Vec<usize>> {
+        let mut parents = BTreeMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            for next in &self.successors[node] {
+                if *next == start {
+                    // Walk back to start
+                    let mut cycle = vec![node];
+                    let mut current = node;
+                    while current != start {
+                        current = parents[&current];
+                        cycle.push(current);
+                    }
+                    cycle.reverse();
+
+                    return Some(cycle);
+                }
+
+                if component.contains(next) && !parents.contains_key(next) {
+                    parents.insert(*next, node);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// One representative cycle per cyclic strongly connected component.
+    pub fn cycles(&self) -> Vec<Vec<Channel>> {
+        self.components()
+            .into_iter()
+            .filter_map(|component| {
+                let start = *component.iter().min()?;
+                let component = component.into_iter().collect::<BTreeSet<usize>>();
+
+                self.cycle_through(start, &component)
+            })
+            .map(|cycle| cycle.into_iter().map(|i| self.channels[i]).collect())
+            .collect()
+    }
+}
+
+fn cycles_markup(topology: &Topology, geometry: &GridGeometry, cycles: &[Vec<Channel>]) -> String {
+    let stroke_width = geometry.stroke_width() * 2.5;
+    let mut markup = String::from("<g id=\"deadlockCycles\">");
+
+    let channels = cycles.iter().flatten().collect::<BTreeSet<&Channel>>();
+    for channel in channels {
+        if let Some(to) = topology.neighbour(channel.core, channel.direction) {
+            let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, channel, to);
+            let _ = write!(
+                markup,
+                "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{DEADLOCK_COLOUR}\" stroke-width=\"{stroke_width}\" stroke-dasharray=\"{} {}\" stroke-opacity=\"0.9\"/>",
+                stroke_width * 2.0,
+                stroke_width
+            );
+        }
+    }
+    markup.push_str("</g>");
+
+    markup
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadlockReport {
+    algorithm: String,
+    channel_count: usize,
+    dependency_count: usize,
+    cycles: Vec<Vec<Channel>>,
+    overlay: String,
+}
+
+#[derive(Serialize)]
+pub struct DeadlockResult {
+    status: ResultStatus,
+    message: String,
+    report: Option<DeadlockReport>,
+    svg: Option<SVGObject>,
+}
+
+/// Builds the channel dependency graph of the loaded system's task graph and reports its cycles.
+/// Uses the system's routing algorithm when none is provided.
+/// Returns the render with the cycles drawn on it.
+#[tauri::command]
+pub fn detect_deadlocks(algorithm: Option<String>, state: tauri::State<State>) -> DeadlockResult {
+    let mut ret = DeadlockResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        report: None,
+        svg: None,
+    };
+
+    let mut document = state.write();
+    let Document {
+        snapshot, overlays, ..
+    } = &mut *document;
+    let (Some(snapshot), Some(geometry)) = (&*snapshot, overlays.geometry) else {
+        ret.message = String::from("Load a system before running deadlock detection.");
+        return ret;
+    };
+
+    let Some(algorithm) = algorithm.or(snapshot.routing_algorithm.clone()) else {
+        ret.message = String::from("Please select a routing algorithm.");
+        return ret;
+    };
+    // Loads say how busy each channel was, not which channel a flow requested next
+    if algorithm == OBSERVED_ALGORITHM {
+        ret.message = String::from(
+            "Observed routing only records the load of each channel, not the path of each flow, so channel dependencies can't be derived from it. Select RowFirst or ColumnFirst to analyse the routes the task graph would take.",
+        );
+        return ret;
+    }
+
+    let topology = Topology::from_snapshot(snapshot);
+    let flows = algorithm
+        .parse::<RoutingAlgorithm>()
+        .and_then(|parsed| route_task_graph(snapshot, &topology, parsed));
+
+    match flows {
+        Ok(flows) => {
+            let mut graph = ChannelDependencyGraph::default();
+            for flow in flows {
+                for pair in flow.hops.windows(2) {
+                    graph.add_dependency(pair[0].channel, pair[1].channel);
+                }
+            }
+
+            let cycles = graph.cycles();
+            let overlay = cycles_markup(&topology, &geometry, &cycles);
+
+            if cycles.is_empty() {
+                overlays.remove(DEADLOCK_LAYER);
+                ret.message = String::from("No cyclic channel dependencies found.");
+            } else {
+                overlays.set(DEADLOCK_LAYER, overlay.clone());
+                ret.message = format!(
+                    "Found {} cyclic channel dependencies, deadlock is possible.",
+                    cycles.len()
+                );
+            }
+
+            ret.status = ResultStatus::Ok;
+            ret.report = Some(DeadlockReport {
+                algorithm,
+                channel_count: graph.channels.len(),
+                dependency_count: graph.dependency_count(),
+                cycles,
+                overlay,
+            });
+            ret.svg = display_render(&mut document).ok();
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
+
+/// Removes the deadlock cycles overlay. Returns the render without it.
+#[tauri::command]
+pub fn clear_deadlock_overlay(state: tauri::State<State>) -> SVGResult {
+    let mut ret = SVGResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        svg: None,
+    };
+
+    let mut document = state.write();
+    document.overlays.remove(DEADLOCK_LAYER);
+
+    match display_render(&mut document) {
+        Ok(svg) => {
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Deadlock cycles cleared.");
+            ret.svg = Some(svg);
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
@@ -0,0 +1,50 @@
+use manycore_parser::ManycoreSystem;
+use manycore_svg::SVG;
+
+use crate::{
+    cache::RenderCache,
+    export::WholeConfiguration,
+    faults::Faults,
+    overlay::{Overlays, Placement},
+    presets::ExportPreset,
+    system::SystemSnapshot,
+    theme::Appearance,
+    topology::TopologyKind,
+};
+
+/// The loaded system and everything derived from it.
+/// Kept behind a single lock so readers always see a system, its render and its overlays
+/// that belong together, and writers are serialised.
+#[derive(Default)]
+pub(crate) struct Document {
+    pub manycore: Option<ManycoreSystem>,
+    pub svg: Option<SVG>,
+    pub snapshot: Option<SystemSnapshot>,
+    pub overlays: Overlays,
+    /// Last configuration applied to the render.
+    pub configuration: Option<WholeConfiguration>,
+    /// `@font-face` rules of the configured font family, so the app draws the render with it.
+    pub font_faces: Option<String>,
+    /// Where the legend is drawn, if enabled.
+    pub legend: Option<Placement>,
+    /// Path of the file the loaded system was parsed from.
+    pub source_path: Option<String>,
+    /// Elements marked as faulty.
+    pub faults: Faults,
+    /// Topology of the loaded system, read from its XML file.
+    pub topology: TopologyKind,
+    /// Serialised render and attributes, see [`RenderCache`].
+    pub cache: RenderCache,
+    /// Named exports, kept across systems.
+    pub export_presets: Vec<ExportPreset>,
+    /// How the render looks, kept across systems.
+    pub appearance: Appearance,
+}
+
+impl Document {
+    /// Drops state tied to the previously loaded system.
+    pub fn forget_system(&mut self) {
+        self.faults = Faults::default();
+        self.topology = TopologyKind::Mesh;
+    }
+}
@@ -22,47 +22,64 @@ pub async fn initiate_edit(state: tauri::State<'_, State>) -> Result<SVGResult,
     };
 
     let tmp_dir = env::temp_dir();
+    // Serialise under the lock, the editor may stay open for a long time
+    let (generation, manycore_string) = {
+        let document = state.read();
+        let Some(manycore) = &document.manycore else {
+            return Ok(ret);
+        };
+
+        let mut manycore_string = String::new();
+        let mut serializer = quick_xml::se::Serializer::new(&mut manycore_string);
+        serializer.indent(' ', 4);
+
+        if manycore.serialize(serializer).is_err() {
+            return Ok(ret);
+        }
+
+        (state.load_generation.current(), manycore_string)
+    };
+
     // Full pattern matching becomes really hard to read here. Only using wanted outcomes.
-    if let Ok(mut manycore_mutex) = state.manycore.lock() {
-        if let Some(manycore) = &*manycore_mutex {
-            let mut manycore_string = String::new();
-            let mut serializer = quick_xml::se::Serializer::new(&mut manycore_string);
-            serializer.indent(' ', 4);
+    let file_path = tmp_dir.join(Uuid::new_v4().to_string());
+    if let Ok(_) = fs::write(file_path.clone(), manycore_string) {
+        if let Some((editor, args)) = get_editor() {
+            if let Ok(output) = Command::new(editor)
+                .args(args)
+                .arg(file_path.clone())
+                .output()
+            {
+                if output.status.success() {
+                    let manycore_parse_res = ManycoreSystem::parse_file(
+                        file_path.to_string_lossy().to_string().as_str(),
+                    );
 
-            if let Ok(_) = manycore.serialize(serializer) {
-                let file_path = tmp_dir.join(Uuid::new_v4().to_string());
-                if let Ok(_) = fs::write(file_path.clone(), manycore_string) {
-                    if let Some((editor, args)) = get_editor() {
-                        if let Ok(output) = Command::new(editor)
-                            .args(args)
-                            .arg(file_path.clone())
-                            .output()
-                        {
-                            if output.status.success() {
-                                let manycore_parse_res = ManycoreSystem::parse_file(
-                                    file_path.to_string_lossy().to_string().as_str(),
-                                );
+                    match manycore_parse_res {
+                        Ok(manycore) => {
+                            let mut document = state.write();
 
-                                match manycore_parse_res {
-                                    Ok(manycore) => {
-                                        generate_svg(&mut ret, &state, &manycore);
+                            // Don't overwrite a system loaded while the editor was open
+                            if state.load_generation.current() == generation {
+                                generate_svg(&mut ret, &mut document, &manycore);
 
-                                        let _ = manycore_mutex.insert(manycore);
-                                    }
-                                    Err(e) => {
-                                        ret.status = ResultStatus::Error;
-                                        ret.message = e.to_string();
-                                    }
-                                }
+                                let _ = document.manycore.insert(manycore);
+                            } else {
+                                ret.message = String::from(
+                                    "Another system was loaded while editing, edits were discarded.",
+                                );
                             }
                         }
+                        Err(e) => {
+                            ret.status = ResultStatus::Error;
+                            ret.message = e.to_string();
+                        }
                     }
-
-                    // Delete temporary file, doesn't particularly matter if we fail tho
-                    let _ = fs::remove_file(file_path);
                 }
             }
         }
+
+        // Delete temporary file, doesn't particularly matter if we fail tho
+        let _ = fs::remove_file(file_path);
     }
 
     Ok(ret)
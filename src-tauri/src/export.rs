@@ -1,101 +1,155 @@
-use std::{fs, ops::Mul, path::PathBuf};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::Mul,
+    path::PathBuf,
+};
 
 use manycore_svg::{BaseConfiguration, Configuration, CoordinateT, SVG};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader, Writer,
+};
 use resvg::{
     render,
-    tiny_skia::Pixmap,
+    tiny_skia::{Color, Pixmap},
     usvg::{fontdb::Database, Options, Size, Transform, Tree},
 };
 use serde::{Deserialize, Serialize};
-use tauri::{api::dialog::FileDialogBuilder, AppHandle, Manager, Window};
+use svg2pdf::usvg::{PostProcessingSteps, TreeParsing, TreePostProc};
+use tauri::{
+    api::dialog::{ask, FileDialogBuilder},
+    AppHandle, Manager, Window,
+};
 
-use crate::{State, EXPORT_CONFIGURATION, LOAD_CONFIGURATION};
+use crate::{
+    annotations::{ExportAnnotations, ExportStamp},
+    document::Document,
+    fonts::{embed_fonts, outline_text, with_font_family},
+    migration::{self, LoadReport, CONFIGURATION_VERSION},
+    overlay::layer_rect,
+    presets::ExportPreset,
+    system::{serialise, write_topology},
+    theme::Appearance,
+    topology::Topology,
+    validation::{describe, validate, without_mismatches},
+    State, EXPORT_CONFIGURATION, LOAD_CONFIGURATION,
+};
 
-static OK_EVENT: &'static str = "ok_message";
-static ERROR_EVENT: &'static str = "error_message";
+pub(crate) static OK_EVENT: &'static str = "ok_message";
+pub(crate) static ERROR_EVENT: &'static str = "error_message";
 
 static GENERIC_ERROR: &'static str = "Something went wrong, please try again.";
+// Elements whose content is not drawn where it is declared
+static UNDRAWN_ELEMENTS: &[&[u8]] = &[
+    b"defs",
+    b"clipPath",
+    b"marker",
+    b"mask",
+    b"pattern",
+    b"symbol",
+    b"style",
+];
+// Text is measured from its anchor, as this many times its font size on either side
+static TEXT_EXTENT: f32 = 8.0;
 
 /// Exports the loaded XML file to disk.
 pub(crate) fn export_xml(window: Window, state: tauri::State<'_, State>) {
+    let document = state.read();
     // Check we got a manycore system
-    if let Ok(manycore_guard) = state.manycore.try_lock() {
-        if let Some(manycore) = &*manycore_guard {
-            // Convert XML to string, pretty print
-            let mut xml_string = String::new();
-            let mut serializer = quick_xml::se::Serializer::new(&mut xml_string);
-            serializer.indent(' ', 4);
-            let xml_result = manycore.serialize(serializer);
-
-            // Async file picker, non-blocking of main process
-            FileDialogBuilder::new()
-                .add_filter("ManyCore XML", &["xml"])
-                .save_file(move |output_path| {
-                    if let Some(mut output_path) = output_path {
-                        // Sanitise extension
-                        output_path = output_path.with_extension("xml");
-
-                        if let Ok(_) = xml_result {
-                            match fs::write(output_path, xml_string) {
-                                Ok(_) => {
-                                    let _ = window.emit(OK_EVENT, "Successfully exported XML.");
-                                }
-                                Err(e) => {
-                                    let _ = window.emit(
-                                        ERROR_EVENT,
-                                        format!("Could not write XML to disk: {e}"),
-                                    );
-                                }
+    if let Some(manycore) = &document.manycore {
+        // Convert XML to string, pretty print. Topology is not part of the system.
+        let xml_result = serialise(manycore)
+            .and_then(|xml_string| write_topology(xml_string.as_str(), &document.topology));
+
+        // Async file picker, non-blocking of main process
+        FileDialogBuilder::new()
+            .add_filter("ManyCore XML", &["xml"])
+            .save_file(move |output_path| {
+                if let Some(mut output_path) = output_path {
+                    // Sanitise extension
+                    output_path = output_path.with_extension("xml");
+
+                    if let Ok(xml_string) = xml_result {
+                        match fs::write(output_path, xml_string) {
+                            Ok(_) => {
+                                let _ = window.emit(OK_EVENT, "Successfully exported XML.");
+                            }
+                            Err(e) => {
+                                let _ = window
+                                    .emit(ERROR_EVENT, format!("Could not write XML to disk: {e}"));
                             }
-
-                            return;
                         }
 
-                        // Failed to serialise
-                        let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+                        return;
                     }
-                });
 
-            // We are done here. File will either be saved or user informed of failure
-            return;
-        }
+                    // Failed to serialise
+                    let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+                }
+            });
 
-        // We don't have a system stored
-        let _ = window.emit(ERROR_EVENT, "You must load a system first.");
+        // We are done here. File will either be saved or user informed of failure
         return;
     }
 
-    // Couldn't lock mutex
-    let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+    // We don't have a system stored
+    let _ = window.emit(ERROR_EVENT, "You must load a system first.");
 }
 
 /// Checks data has been loaded then signals frontend to generate configuration
 pub(crate) fn export_configuration(window: Window, state: tauri::State<'_, State>) {
-    if let Ok(svg_guard) = state.svg.try_lock() {
-        if let Some(_) = &*svg_guard {
-            let _ = window.emit(EXPORT_CONFIGURATION, 0);
-            return;
-        }
-
-        // We don't have a system stored
-        let _ = window.emit(ERROR_EVENT, "You must load a system first.");
+    if let Some(_) = &state.read().svg {
+        let _ = window.emit(EXPORT_CONFIGURATION, 0);
         return;
     }
 
-    // Couldn't lock mutex
-    let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+    // We don't have a system stored
+    let _ = window.emit(ERROR_EVENT, "You must load a system first.");
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct WholeConfiguration {
-    base_configuration: BaseConfiguration,
-    configuration: Configuration,
+    /// Format version of the file, see [`crate::migration`].
+    #[serde(default = "migration::baseline_version")]
+    pub(crate) version: u32,
+    pub(crate) base_configuration: BaseConfiguration,
+    pub(crate) configuration: Configuration,
+    /// Font family of all text, the default one if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) font_family: Option<String>,
+    /// Saved with the configuration, kept in the [`Document`] while the app runs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) export_presets: Vec<ExportPreset>,
 }
 
-/// Exports the provided [`WholeConfiguration`] (provided as [`String`] to avoid serde back and forth).
+/// Exports the provided [`WholeConfiguration`], stamped with the current format version.
+/// Export presets are added to it, if there are any.
 #[tauri::command]
-pub(crate) fn store_configuration(window: Window, whole_configuration: String) {
+pub(crate) fn store_configuration(
+    window: Window,
+    state: tauri::State<'_, State>,
+    whole_configuration: String,
+) {
+    let export_presets = state.read().export_presets.clone();
+    let whole_configuration = match serde_json::from_str::<WholeConfiguration>(
+        whole_configuration.as_str(),
+    )
+    .and_then(|configuration| {
+        serde_json::to_string(&WholeConfiguration {
+            version: CONFIGURATION_VERSION,
+            export_presets,
+            ..configuration
+        })
+    }) {
+        Ok(whole_configuration) => whole_configuration,
+        Err(e) => {
+            let _ = window.emit(ERROR_EVENT, format!("Could not store configuration: {e}"));
+            return;
+        }
+    };
+
     FileDialogBuilder::new()
         .add_filter("ManyCore Visualiser Configuration", &["json"])
         .save_file(move |output_path| {
@@ -118,59 +172,114 @@ pub(crate) fn store_configuration(window: Window, whole_configuration: String) {
         })
 }
 
-/// Loads a configuration file from disk.
+/// Checks a loaded configuration fits the system, then sends it to the frontend.
+/// If some options don't, the user picks between applying the others and cancelling.
+fn apply_loaded_configuration(
+    window: Window,
+    mut whole_configuration: WholeConfiguration,
+    report: LoadReport,
+) {
+    let validation = validate(
+        &window.state::<State>().read(),
+        &whole_configuration.configuration,
+    );
+    let mismatches = match validation {
+        Ok(mismatches) => mismatches,
+        Err(e) => {
+            let _ = window.emit(ERROR_EVENT, e);
+            return;
+        }
+    };
+
+    let apply = move |window: Window, whole_configuration: WholeConfiguration| {
+        // The frontend gets the upgraded configuration, never the old format
+        let upgraded = match serde_json::to_string(&whole_configuration) {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                let _ = window.emit(
+                    ERROR_EVENT,
+                    format!("Could not process provided configuration: {e}"),
+                );
+                return;
+            }
+        };
+
+        // Keep the current presets if the file has none
+        if !whole_configuration.export_presets.is_empty() {
+            window.state::<State>().write().export_presets = whole_configuration.export_presets;
+        }
+
+        let _ = window.emit(LOAD_CONFIGURATION, upgraded);
+        if !report.is_clean() {
+            let _ = window.emit(OK_EVENT, report.message());
+        }
+    };
+
+    if mismatches.is_empty() {
+        apply(window, whole_configuration);
+        return;
+    }
+
+    let parent = window.clone();
+    ask(
+        Some(&parent),
+        "Configuration does not fit the system",
+        format!(
+            "Some options don't fit the loaded system:\n\n{}\n\nApply the other options?",
+            describe(&mismatches)
+        ),
+        move |partial| {
+            if partial {
+                without_mismatches(&mut whole_configuration.configuration, &mismatches);
+                apply(window, whole_configuration);
+            } else {
+                let _ = window.emit(OK_EVENT, "Configuration not loaded.");
+            }
+        },
+    );
+}
+
+/// Loads a configuration file from disk, upgrading files saved by older versions.
+/// Options that don't fit the loaded system are listed before applying it.
 pub(crate) fn load_configuration(window: Window, state: tauri::State<'_, State>) {
-    // Check we got a system loaded
-    if let Ok(svg_guard) = state.svg.try_lock() {
-        if let Some(_) = &*svg_guard {
-            FileDialogBuilder::new()
-                .add_filter("ManyCore Visualiser Configuration", &["json"])
-                .pick_file(move |file_path| {
-                    if let Some(file_path) = file_path {
-                        match fs::read_to_string(file_path) {
-                            Ok(configuration_content) => {
-                                match serde_json::from_str::<WholeConfiguration>(
-                                    configuration_content.as_str(),
-                                ) {
-                                    Ok(_) => {
-                                        // We pass configuration_content because it is an already serialised version of the whole configuration.
-                                        // We don't need to serialise again.
-                                        let _ =
-                                            window.emit(LOAD_CONFIGURATION, configuration_content);
-                                    }
-                                    Err(e) => {
-                                        let _ = window.emit(
-                                            ERROR_EVENT,
-                                            format!(
-                                                "Could not process provided configuration: {e}"
-                                            ),
-                                        );
-                                    }
+    // Check we got a system loaded. The picker stores presets, don't hold the document.
+    if state.read().svg.is_some() {
+        FileDialogBuilder::new()
+            .add_filter("ManyCore Visualiser Configuration", &["json"])
+            .pick_file(move |file_path| {
+                if let Some(file_path) = file_path {
+                    match fs::read_to_string(file_path) {
+                        Ok(configuration_content) => {
+                            match migration::load(configuration_content.as_str()) {
+                                Ok((whole_configuration, report)) => {
+                                    apply_loaded_configuration(window, whole_configuration, report)
+                                }
+                                Err(e) => {
+                                    let _ = window.emit(
+                                        ERROR_EVENT,
+                                        format!("Could not process provided configuration: {e}"),
+                                    );
                                 }
-                            }
-                            Err(e) => {
-                                let _ = window.emit(
-                                    ERROR_EVENT,
-                                    format!("Could not open configuration file: {e}"),
-                                );
                             }
                         }
+                        Err(e) => {
+                            let _ = window.emit(
+                                ERROR_EVENT,
+                                format!("Could not open configuration file: {e}"),
+                            );
+                        }
                     }
-                });
-
-            return;
-        }
+                }
+            });
 
-        // We don't have a system stored
-        let _ = window.emit(ERROR_EVENT, "You must load a system first.");
         return;
     }
 
-    // Couldn't lock mutex
-    let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+    // We don't have a system stored
+    let _ = window.emit(ERROR_EVENT, "You must load a system first.");
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ClipPathInput {
     clip_path: String,
@@ -180,43 +289,508 @@ pub struct ClipPathInput {
     height: CoordinateT,
 }
 
-#[derive(Deserialize)]
+impl ClipPathInput {
+    /// Bounding [`Region`] and path of the clip.
+    pub(crate) fn into_parts(self) -> (Region, String) {
+        (
+            Region {
+                x: self.x,
+                y: self.y,
+                width: self.width,
+                height: self.height,
+            },
+            self.clip_path,
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub(crate) enum RenderMode {
     SVG,
+    /// SVG with text converted to paths.
+    SVGTextAsPaths,
+    /// SVG with the fonts it uses embedded.
+    SVGEmbeddedFonts,
     PNG,
+    PDF,
 }
 
-/// Internal utility to wrap PNG export operations in a Result.
-fn export_png(
+/// Longest side of a PNG export, in pixels. A row of the widest strip must be addressable.
+static MAX_PNG_SIDE: u32 = i32::MAX as u32 / 4;
+/// Memory budget of a strip of the PNG export, in bytes.
+static STRIP_BYTES: usize = 32 * 1024 * 1024;
+
+/// Renders a [`Tree`] as PNG, adding the provided text as tEXt chunks, or as UTF-8 iTXt chunks
+/// where the text is not Latin-1, which is all tEXt can hold.
+/// The image is rendered in horizontal strips streamed to the encoder, so memory use is bounded
+/// by [`STRIP_BYTES`] rather than by the size of the image.
+fn write_png(
     file_path: PathBuf,
-    svg_str: &str,
-    font_database: &Database,
+    tree: &Tree,
     scale: f32,
+    text_chunks: &[(&'static str, String)],
 ) -> Result<(), String> {
-    let tree =
-        Tree::from_str(svg_str, &Options::default(), &font_database).map_err(|e| e.to_string())?;
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(String::from("Please provide a scale greater than zero."));
+    }
 
     // Scale dimensions
     let w = tree.size().width().mul(scale);
     let h = tree.size().height().mul(scale);
+    if w.max(h).ceil() > MAX_PNG_SIDE as f32 {
+        return Err(format!(
+            "The image would be {} x {} pixels, sides can be at most {MAX_PNG_SIDE} pixels. Please lower the scale.",
+            w.ceil(),
+            h.ceil()
+        ));
+    }
     let size = Size::from_wh(w, h)
         .ok_or("Please provide a valid scale value. Could not compute image size.")?
         .to_int_size();
+    let (width, height) = (size.width(), size.height());
+
+    let row_bytes = width as usize * 4;
+    let strip_height = (STRIP_BYTES / row_bytes).clamp(1, height as usize) as u32;
 
-    // Allocate buffer
-    let mut target_image = Pixmap::new(size.width(), size.height()).ok_or("err")?;
+    let file = File::create(file_path).map_err(|e| format!("Could not create PNG file: {e}"))?;
 
-    // Calculate transform
-    let transform = Transform::default().pre_scale(scale, scale);
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text) in text_chunks {
+        // Most readers only show tEXt
+        let added = if text.chars().all(|c| c <= '\u{ff}') {
+            encoder.add_text_chunk(keyword.to_string(), text.clone())
+        } else {
+            encoder.add_itxt_chunk(keyword.to_string(), text.clone())
+        };
+        added.map_err(|e| format!("Could not add {keyword} to the PNG: {e}"))?;
+    }
 
-    // This just does the rendering, no result/option
-    render(&tree, transform, &mut target_image.as_mut());
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Could not write PNG header: {e}"))?;
+    let mut stream = writer
+        .stream_writer()
+        .map_err(|e| format!("Could not start writing PNG data: {e}"))?;
+
+    let mut strip = Pixmap::new(width, strip_height).ok_or(format!(
+        "Could not allocate a {width} x {strip_height} pixels strip. Please lower the scale."
+    ))?;
+    let mut data = Vec::with_capacity(strip.data().len());
+
+    for top in (0..height).step_by(strip_height as usize) {
+        let rows = strip_height.min(height - top);
+
+        strip.fill(Color::TRANSPARENT);
+        // Scale, then shift the strip to the origin
+        let transform = Transform::from_row(scale, 0.0, 0.0, scale, 0.0, -(top as f32));
+
+        // This just does the rendering, no result/option
+        render(tree, transform, &mut strip.as_mut());
+
+        // Pixmap stores premultiplied alpha, PNG expects straight alpha
+        data.clear();
+        for pixel in &strip.pixels()[..rows as usize * width as usize] {
+            let colour = pixel.demultiply();
+            data.extend_from_slice(&[colour.red(), colour.green(), colour.blue(), colour.alpha()]);
+        }
+
+        stream
+            .write_all(&data)
+            .map_err(|e| format!("Could not write PNG data: {e}"))?;
+    }
+
+    stream
+        .finish()
+        .and_then(|_| writer.finish())
+        .map_err(|e| format!("Could not finish writing PNG: {e}"))
+}
+
+/// Internal utility to wrap PNG export operations in a Result.
+fn export_png(
+    file_path: PathBuf,
+    svg_str: &str,
+    font_database: &Database,
+    scale: f32,
+    text_chunks: &[(&'static str, String)],
+) -> Result<(), String> {
+    let tree = Tree::from_str(svg_str, &Options::default(), &font_database)
+        .map_err(|e| format!("Could not read the render: {e}"))?;
 
     // Write to disk
-    target_image.save_png(file_path).map_err(|e| e.to_string())
+    write_png(file_path, &tree, scale, text_chunks)
+}
+
+/// Converts a serialised render to PDF. Text is converted to paths with the provided fonts.
+/// The page takes the size of the render, scaling is left to the viewer.
+fn export_pdf(file_path: PathBuf, svg_str: &str, font_database: &Database) -> Result<(), String> {
+    let mut tree = svg2pdf::usvg::Tree::from_str(svg_str, &svg2pdf::usvg::Options::default())
+        .map_err(|e| format!("Could not read the render: {e}"))?;
+    tree.postprocess(PostProcessingSteps::default(), font_database);
+
+    let pdf = svg2pdf::convert_tree(&tree, svg2pdf::Options::default());
+
+    fs::write(file_path, pdf).map_err(|e| format!("Could not write PDF to disk: {e}"))
+}
+
+/// A rectangle of the render, in SVG user space.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Region {
+    pub x: CoordinateT,
+    pub y: CoordinateT,
+    pub width: CoordinateT,
+    pub height: CoordinateT,
+}
+
+impl Region {
+    /// Region from an (x, y, width, height) rectangle.
+    pub fn from_rect((x, y, width, height): (f32, f32, f32, f32)) -> Self {
+        Self {
+            x: x as CoordinateT,
+            y: y as CoordinateT,
+            width: width as CoordinateT,
+            height: height as CoordinateT,
+        }
+    }
+
+    /// Whether an (x1, y1, x2, y2) extent overlaps the region.
+    fn overlaps(&self, (x1, y1, x2, y2): (f32, f32, f32, f32)) -> bool {
+        let (x, y) = (self.x as f32, self.y as f32);
+
+        x1 <= x + self.width as f32 && x2 >= x && y1 <= y + self.height as f32 && y2 >= y
+    }
+
+    /// Drops the drawings of a serialised render that lie entirely outside the region, so that
+    /// renders of a small region of a large system stay small. Drawings whose extent can't be
+    /// read, e.g. transformed ones or relative paths, are kept.
+    pub fn strip_outside(&self, svg_string: &str) -> Result<String, String> {
+        let mut reader = Reader::from_str(svg_string);
+        let mut writer = Writer::new(Vec::with_capacity(svg_string.len()));
+        let write_err = |e: quick_xml::Error| format!("Could not write render: {e}");
+        let mut depth = 0usize;
+        // Depth of the element being dropped, with its content
+        let mut dropping: Option<usize> = None;
+        // Depth of the element whose content is kept whole
+        let mut keeping: Option<usize> = None;
+
+        loop {
+            let event = match reader.read_event() {
+                Ok(Event::Eof) => break,
+                Ok(event) => event,
+                Err(e) => return Err(format!("Could not read render: {e}")),
+            };
+
+            match &event {
+                Event::Start(e) => {
+                    if dropping.is_none() && keeping.is_none() {
+                        if !drawn_in_place(e) {
+                            keeping = Some(depth);
+                        } else if extent(e).is_some_and(|extent| !self.overlaps(extent)) {
+                            dropping = Some(depth);
+                        }
+                    }
+                    depth += 1;
+                }
+                Event::Empty(e) => {
+                    if dropping.is_none()
+                        && keeping.is_none()
+                        && drawn_in_place(e)
+                        && extent(e).is_some_and(|extent| !self.overlaps(extent))
+                    {
+                        continue;
+                    }
+                }
+                Event::End(_) => {
+                    depth = depth.saturating_sub(1);
+                    if dropping == Some(depth) {
+                        dropping = None;
+                        continue;
+                    }
+                    if keeping == Some(depth) {
+                        keeping = None;
+                    }
+                }
+                _ => {}
+            }
+
+            if dropping.is_none() {
+                writer.write_event(event).map_err(write_err)?;
+            }
+        }
+
+        String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+    }
+}
+
+/// Whether an element and its content are drawn at the coordinates they give.
+fn drawn_in_place(element: &BytesStart) -> bool {
+    !UNDRAWN_ELEMENTS.contains(&element.local_name().as_ref())
+        && !element
+            .attributes()
+            .flatten()
+            .any(|attribute| attribute.key.as_ref() == b"transform")
 }
 
-/// Exports the [`SVG`] in its current state as SVG or PNG, optionally adding a [`ClipPath`].
+/// Numeric attribute of an element, [`None`] if missing or not a plain number.
+fn number_attribute(element: &BytesStart, key: &str) -> Option<f32> {
+    element
+        .try_get_attribute(key)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok()?.trim().parse::<f32>().ok())
+}
+
+/// Numbers of a path or points list, in order. Exponents are not supported.
+fn numbers(list: &str) -> Vec<f32> {
+    let mut ret = Vec::new();
+    let mut current = String::new();
+    let flush = |current: &mut String, ret: &mut Vec<f32>| {
+        if let Ok(number) = current.parse::<f32>() {
+            ret.push(number);
+        }
+        current.clear();
+    };
+
+    for c in list.chars() {
+        match c {
+            '-' if !current.is_empty() => {
+                flush(&mut current, &mut ret);
+                current.push(c);
+            }
+            '.' if current.contains('.') => {
+                flush(&mut current, &mut ret);
+                current.push(c);
+            }
+            '0'..='9' | '-' | '.' => current.push(c),
+            _ => flush(&mut current, &mut ret),
+        }
+    }
+    flush(&mut current, &mut ret);
+
+    ret
+}
+
+/// Bounds of absolute path data. [`None`] for relative commands and arcs, which can't be bounded
+/// without following the path.
+fn path_extent(d: &str) -> Option<(f32, f32, f32, f32)> {
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let mut command = 'M';
+    let mut arguments = String::new();
+    let mut flush = |command: char, arguments: &mut String| -> Option<()> {
+        let values = numbers(arguments.as_str());
+        arguments.clear();
+
+        match command {
+            'M' | 'L' | 'T' | 'C' | 'S' | 'Q' => {
+                for pair in values.chunks(2) {
+                    xs.push(pair[0]);
+                    ys.push(*pair.get(1)?);
+                }
+            }
+            'H' => xs.extend(values),
+            'V' => ys.extend(values),
+            'Z' | 'z' => {}
+            _ => return None,
+        }
+
+        Some(())
+    };
+
+    for c in d.chars() {
+        if c.is_ascii_alphabetic() {
+            flush(command, &mut arguments)?;
+            command = c;
+        } else {
+            arguments.push(c);
+        }
+    }
+    flush(command, &mut arguments)?;
+
+    let bounds = |values: &Vec<f32>| {
+        values.iter().fold(None, |bounds, value| match bounds {
+            None => Some((*value, *value)),
+            Some((min, max)) => Some((value.min(min), value.max(max))),
+        })
+    };
+    let ((x1, x2), (y1, y2)) = (bounds(&xs)?, bounds(&ys)?);
+
+    Some((x1, y1, x2, y2))
+}
+
+/// Area an element is drawn in, as (x1, y1, x2, y2). [`None`] if it can't be read from its own
+/// attributes, e.g. for groups.
+fn extent(element: &BytesStart) -> Option<(f32, f32, f32, f32)> {
+    let number = |key: &str| number_attribute(element, key);
+    let text = |key: &str| {
+        element
+            .try_get_attribute(key)
+            .ok()
+            .flatten()
+            .and_then(|attribute| {
+                attribute
+                    .unescape_value()
+                    .ok()
+                    .map(|value| value.into_owned())
+            })
+    };
+
+    match element.local_name().as_ref() {
+        b"rect" => {
+            let (x, y) = (number("x").unwrap_or(0.0), number("y").unwrap_or(0.0));
+            Some((x, y, x + number("width")?, y + number("height")?))
+        }
+        b"circle" => {
+            let (cx, cy, r) = (number("cx")?, number("cy")?, number("r")?);
+            Some((cx - r, cy - r, cx + r, cy + r))
+        }
+        b"ellipse" => {
+            let (cx, cy) = (number("cx")?, number("cy")?);
+            let (rx, ry) = (number("rx")?, number("ry")?);
+            Some((cx - rx, cy - ry, cx + rx, cy + ry))
+        }
+        b"line" => {
+            let (x1, y1, x2, y2) = (number("x1")?, number("y1")?, number("x2")?, number("y2")?);
+            Some((x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)))
+        }
+        b"text" => {
+            let (x, y, size) = (number("x")?, number("y")?, number("font-size")?);
+            let reach = size * TEXT_EXTENT;
+            Some((x - reach, y - reach, x + reach, y + reach))
+        }
+        b"path" => path_extent(text("d")?.as_str()),
+        b"polyline" | b"polygon" => path_extent(format!("M{}", text("points")?).as_str()),
+        _ => None,
+    }
+}
+
+/// Serialises the [`SVG`] with all overlays, optionally restricted to a [`Region`] and clipped.
+/// Drawn with `appearance`, or the document's if not provided.
+/// The viewBox of the stored [`SVG`] is restored before returning.
+/// The whole, unclipped render is served from the document's [`RenderCache`](crate::cache::RenderCache).
+pub(crate) fn serialise_render(
+    document: &mut Document,
+    stamp: Option<&ExportStamp>,
+    region: Option<Region>,
+    clip_path: Option<String>,
+    appearance: Option<&Appearance>,
+) -> Result<String, String> {
+    let Document {
+        svg,
+        overlays,
+        cache,
+        configuration,
+        appearance: document_appearance,
+        ..
+    } = document;
+    let appearance = appearance.unwrap_or(document_appearance);
+    let svg = svg.as_mut().ok_or("You must load a system first.")?;
+
+    let view_box = region.map(|region| {
+        svg.view_box_mut()
+            .swap(region.x, region.y, region.width, region.height)
+    });
+    let has_clip_path = clip_path.is_some();
+    if let Some(clip_path) = clip_path {
+        svg.add_freeform_clip_path(clip_path);
+    }
+
+    let res = match (view_box.is_some(), has_clip_path) {
+        (false, false) => cache.serialise(svg),
+        _ => String::try_from(svg as &SVG).map_err(|e| e.to_string()),
+    };
+
+    if let Some(view_box) = view_box {
+        svg.view_box_mut().restore_from(&view_box);
+    }
+    if has_clip_path {
+        svg.clear_freeform_clip_path();
+    }
+
+    res.map(|svg_string| {
+        let svg_string = match stamp {
+            Some(stamp) => {
+                // Annotations only live for this export
+                let mut overlays = overlays.clone();
+                stamp.decorate(&mut overlays);
+
+                stamp.add_metadata(overlays.splice(svg_string))
+            }
+            None => overlays.splice(svg_string),
+        };
+
+        appearance.apply(with_font_family(svg_string, configuration.as_ref()))
+    })
+}
+
+impl RenderMode {
+    /// File dialog filter name, extension and success message.
+    pub fn file_details(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            RenderMode::PNG => (
+                "Portable Network Graphics (PNG)",
+                "png",
+                "Successfully exported PNG",
+            ),
+            RenderMode::SVG | RenderMode::SVGTextAsPaths | RenderMode::SVGEmbeddedFonts => (
+                "Scalable Vector Graphics (SVG)",
+                "svg",
+                "Successfully exported SVG",
+            ),
+            RenderMode::PDF => (
+                "Portable Document Format (PDF)",
+                "pdf",
+                "Successfully exported PDF",
+            ),
+        }
+    }
+
+    /// Writes a serialised render to disk in this mode.
+    /// Returns a note for the user about the written file, if any.
+    pub fn write(
+        &self,
+        file_path: PathBuf,
+        svg_string: String,
+        font_database: &Database,
+        scale: f32,
+        text_chunks: &[(&'static str, String)],
+    ) -> Result<Option<String>, String> {
+        match self {
+            // Attempt SVG -> PNG conversion
+            RenderMode::PNG => export_png(
+                file_path,
+                svg_string.as_str(),
+                font_database,
+                scale,
+                text_chunks,
+            )
+            .map(|_| None),
+            // Attempt writing SVG string to disk
+            RenderMode::SVG => fs::write(file_path, svg_string)
+                .map(|_| None)
+                .map_err(|e| e.to_string()),
+            RenderMode::SVGTextAsPaths => outline_text(svg_string.as_str(), font_database)
+                .and_then(|svg_string| fs::write(file_path, svg_string).map_err(|e| e.to_string()))
+                .map(|_| None),
+            RenderMode::SVGEmbeddedFonts => {
+                let (svg_string, sizes) = embed_fonts(svg_string, font_database);
+
+                fs::write(file_path, svg_string)
+                    .map(|_| sizes)
+                    .map_err(|e| e.to_string())
+            }
+            // Vector output, scale doesn't apply
+            RenderMode::PDF => {
+                export_pdf(file_path, svg_string.as_str(), font_database).map(|_| None)
+            }
+        }
+    }
+}
+
+/// Exports the [`SVG`] in its current state as SVG, PNG or PDF, optionally adding a [`ClipPath`].
+/// Optional [`ExportAnnotations`] are drawn in the render and stored as metadata.
+/// Drawn with the in-app [`Appearance`] unless one is provided.
 #[tauri::command]
 pub(crate) fn export_render(
     window: Window,
@@ -225,105 +799,162 @@ pub(crate) fn export_render(
     clip_path: Option<ClipPathInput>,
     render_mode: RenderMode,
     scale: f32,
+    annotations: Option<ExportAnnotations>,
+    appearance: Option<Appearance>,
 ) -> Result<(), String> {
-    if let (Ok(font_database_mutex), Ok(mut svg_mutex)) =
-        (state.font_database.try_lock(), state.svg.try_lock())
-    {
-        if let (font_database, Some(svg)) = (&*font_database_mutex, &mut *svg_mutex) {
-            // Clone font database because we can't move it in the asynchronouse file dialogue
-            let font_database = font_database.clone();
-
-            // Serialise SVG
-            let svg_string = match clip_path {
-                Some(clip_path) => {
-                    // Serialise SVG with user defined clipPath
-
-                    let view_box = svg.view_box_mut().swap(
-                        clip_path.x,
-                        clip_path.y,
-                        clip_path.width,
-                        clip_path.height,
-                    );
-
-                    svg.add_freeform_clip_path(clip_path.clip_path);
-                    let res = String::try_from(svg as &SVG);
-
-                    svg.view_box_mut().restore_from(&view_box);
-                    svg.clear_freeform_clip_path();
-
-                    res
-                }
-                None => String::try_from(svg as &SVG),
-            }
-            .map_err(|e| e.to_string())?;
-
-            // Calculate this to only call FileDialogBuilder once
-            let (filter_name, extension, message) = match render_mode {
-                RenderMode::PNG => (
-                    "Portable Network Graphics (PNG)",
-                    "png",
-                    "Successfully exported PNG",
-                ),
-                RenderMode::SVG => (
-                    "Scalable Vector Graphics (SVG)",
-                    "svg",
-                    "Successfully exported SVG",
-                ),
-            };
+    let mut document = state.write();
+    let stamp = annotations
+        .map(|annotations| ExportStamp::new(annotations, &document))
+        .transpose()?;
 
-            // Clone the window label, will be moved into FileDialogBuilder's closure
-            let window_label = window.label().to_owned();
-
-            // Open file picker
-            // ASYNC CONTEXT - CAN'T RETURN TO JS PROMISE
-            FileDialogBuilder::new()
-                .add_filter(filter_name, &[extension])
-                .save_file(move |file_path| {
-                    // Ensure user has picked a file path
-                    if let Some(mut file_path) = file_path {
-                        // Sanitise extension
-                        file_path = file_path.with_extension(extension);
-
-                        // Try to grab the original window
-                        match handle.get_window(window_label.as_str()) {
-                            Some(window) => {
-                                let export_res = match render_mode {
-                                    // Attempt SVG -> PNG conversion
-                                    RenderMode::PNG => export_png(
-                                        file_path,
-                                        svg_string.as_str(),
-                                        &font_database,
-                                        scale,
-                                    ),
-                                    // Attempt writing SVG string to disk
-                                    RenderMode::SVG => {
-                                        fs::write(file_path, svg_string).map_err(|e| e.to_string())
-                                    }
-                                };
-
-                                match export_res {
-                                    Ok(_) => {
-                                        let _ = window.emit(OK_EVENT, message);
-                                    }
-                                    Err(e) => {
-                                        let _ = window.emit(ERROR_EVENT, e.to_string());
-                                    }
-                                }
+    if document.svg.is_none() {
+        return Err("You must load a system first.".to_string());
+    }
+
+    // Clone font database because we can't move it in the asynchronouse file dialogue
+    let font_database = state.font_database().clone();
+
+    // Serialise SVG, with user defined clipPath if any
+    let (region, clip_path) = clip_path.map(ClipPathInput::into_parts).unzip();
+    let svg_string = serialise_render(
+        &mut document,
+        stamp.as_ref(),
+        region,
+        clip_path,
+        appearance.as_ref(),
+    )?;
+    let text_chunks = stamp.map(|stamp| stamp.entries).unwrap_or_default();
+
+    // Calculate this to only call FileDialogBuilder once
+    let (filter_name, extension, message) = render_mode.file_details();
+
+    // Clone the window label, will be moved into FileDialogBuilder's closure
+    let window_label = window.label().to_owned();
+
+    // The picker doesn't need the document
+    drop(document);
+
+    // Open file picker
+    // ASYNC CONTEXT - CAN'T RETURN TO JS PROMISE
+    FileDialogBuilder::new()
+        .add_filter(filter_name, &[extension])
+        .save_file(move |file_path| {
+            // Ensure user has picked a file path
+            if let Some(mut file_path) = file_path {
+                // Sanitise extension
+                file_path = file_path.with_extension(extension);
+
+                // Try to grab the original window
+                match handle.get_window(window_label.as_str()) {
+                    Some(window) => {
+                        let export_res = render_mode.write(
+                            file_path,
+                            svg_string,
+                            &font_database,
+                            scale,
+                            &text_chunks,
+                        );
+
+                        match export_res {
+                            Ok(None) => {
+                                let _ = window.emit(OK_EVENT, message);
+                            }
+                            Ok(Some(note)) => {
+                                let _ = window.emit(OK_EVENT, format!("{message}. {note}"));
                             }
-                            None => {
-                                // Could not grab the window
-                                let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+                            Err(e) => {
+                                let _ = window.emit(ERROR_EVENT, e.to_string());
                             }
                         }
                     }
-                });
-            // ASYNC CONTEXT ENDS
-            return Ok(());
-        } else {
-            return Err("You must load a system first.".to_string());
-        }
+                    None => {
+                        // Could not grab the window
+                        let _ = window.emit(ERROR_EVENT, GENERIC_ERROR);
+                    }
+                }
+            }
+        });
+    // ASYNC CONTEXT ENDS
+    Ok(())
+}
+
+/// Exports each layer of a stacked mesh as its own SVG, PNG or PDF.
+/// Files are named after the picked path with a `_layer<N>` suffix.
+/// Exporting all layers in one image is done through [`export_render`].
+#[tauri::command]
+pub(crate) fn export_layers(
+    window: Window,
+    handle: AppHandle,
+    state: tauri::State<'_, State>,
+    render_mode: RenderMode,
+    scale: f32,
+) -> Result<(), String> {
+    let mut document = state.write();
+    let (Some(snapshot), Some(_)) = (&document.snapshot, &document.svg) else {
+        return Err("You must load a system first.".to_string());
+    };
+    let topology = Topology::from_snapshot(snapshot);
+    if topology.layers() < 2 {
+        return Err("The loaded system has a single layer.".to_string());
     }
+    let geometry = document
+        .overlays
+        .geometry
+        .ok_or("Could not locate layers in the render.")?;
 
-    // Could not lock mutex
-    return Err(GENERIC_ERROR.to_string());
+    let layers = (0..topology.layers())
+        .map(|layer| {
+            let region = Region::from_rect(layer_rect(&topology, &geometry, layer));
+
+            serialise_render(&mut document, None, Some(region), None, None)
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+
+    drop(document);
+
+    let font_database = state.font_database().clone();
+    let (filter_name, extension, message) = render_mode.file_details();
+    let window_label = window.label().to_owned();
+
+    // ASYNC CONTEXT - CAN'T RETURN TO JS PROMISE
+    FileDialogBuilder::new()
+        .add_filter(filter_name, &[extension])
+        .save_file(move |file_path| {
+            let (Some(file_path), Some(window)) =
+                (file_path, handle.get_window(window_label.as_str()))
+            else {
+                return;
+            };
+            let stem = file_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let export_res = layers
+                .into_iter()
+                .enumerate()
+                .try_for_each(|(layer, svg_string)| {
+                    render_mode
+                        .write(
+                            file_path.with_file_name(format!("{stem}_layer{layer}.{extension}")),
+                            svg_string,
+                            &font_database,
+                            scale,
+                            &[],
+                        )
+                        .map(|_| ())
+                        .map_err(|e| format!("Could not export layer {layer}: {e}"))
+                });
+
+            match export_res {
+                Ok(_) => {
+                    let _ = window.emit(OK_EVENT, message);
+                }
+                Err(e) => {
+                    let _ = window.emit(ERROR_EVENT, e);
+                }
+            }
+        });
+    // ASYNC CONTEXT ENDS
+    Ok(())
 }
@@ -0,0 +1,410 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use serde::Serialize;
+
+use crate::{
+    document::Document,
+    overlay::{push_text, GridGeometry},
+    result_status::ResultStatus,
+    routing::{route, Hop, RoutingAlgorithm},
+    system::{ElementId, SystemSnapshot},
+    topology::{Channel, Topology},
+    State,
+};
+
+static FAULTS_LAYER: &'static str = "faults";
+static FAULT_COLOUR: &'static str = "#b00020";
+static UNROUTABLE_COLOUR: &'static str = "#ff6f00";
+static LOAD_COLOUR: &'static str = "#37474f";
+
+/// Elements marked as faulty, and the routing algorithm flows are checked against.
+#[derive(Default, Clone, Debug)]
+pub struct Faults {
+    elements: BTreeSet<ElementId>,
+    algorithm: Option<String>,
+    /// Whether blocked flows may take the dimensions in the opposite order.
+    adaptive: bool,
+}
+
+impl Faults {
+    fn is_faulty(&self, element: ElementId) -> bool {
+        self.elements.contains(&element)
+    }
+
+    /// First faulty element on a route, routers included.
+    fn blocking_element(&self, source: usize, hops: &[Hop]) -> Option<ElementId> {
+        std::iter::once(ElementId::Router(source))
+            .chain(
+                hops.iter()
+                    .flat_map(|hop| [ElementId::Channel(hop.channel), ElementId::Router(hop.to)]),
+            )
+            .find(|element| self.is_faulty(*element))
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlowOutcome {
+    /// The algorithm's route avoids every fault.
+    Routed,
+    /// The algorithm's route is blocked, the other dimension order is not. Only with adaptive
+    /// routing enabled.
+    Rerouted,
+    /// The algorithm's route crosses a fault.
+    Blocked,
+    /// The flow's source or destination core is faulty.
+    Unroutable,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowReport {
+    from_task: u16,
+    to_task: u16,
+    source_core: usize,
+    destination_core: usize,
+    outcome: FlowOutcome,
+    /// Why the flow is blocked or unroutable.
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultReport {
+    algorithm: String,
+    faults: Vec<ElementId>,
+    /// Flows that are rerouted, blocked or unroutable.
+    affected_flows: Vec<FlowReport>,
+    /// Flows that are blocked or unroutable.
+    unroutable_count: usize,
+    /// Summed communication cost through each channel, faults taken into account.
+    channel_loads: BTreeMap<ElementId, u64>,
+    overlay: String,
+}
+
+fn other_order(algorithm: RoutingAlgorithm) -> RoutingAlgorithm {
+    match algorithm {
+        RoutingAlgorithm::RowFirst => RoutingAlgorithm::ColumnFirst,
+        RoutingAlgorithm::ColumnFirst => RoutingAlgorithm::RowFirst,
+    }
+}
+
+/// Routes a flow around faults. Dimension order routing is deterministic, so a route crossing
+/// a fault is blocked. With adaptive routing, the flow may take the dimensions in the opposite
+/// order instead.
+fn route_around(
+    topology: &Topology,
+    faults: &Faults,
+    algorithm: RoutingAlgorithm,
+    source: usize,
+    destination: usize,
+) -> Result<(FlowOutcome, Vec<Hop>), (FlowOutcome, String)> {
+    for core in [source, destination] {
+        if faults.is_faulty(ElementId::Core(core)) {
+            return Err((FlowOutcome::Unroutable, format!("Core {core} is faulty.")));
+        }
+    }
+
+    let hops = route(topology, algorithm, source, destination)
+        .map_err(|e| (FlowOutcome::Unroutable, e))?;
+    let Some(blocking) = faults.blocking_element(source, &hops) else {
+        return Ok((FlowOutcome::Routed, hops));
+    };
+    let blocked = (
+        FlowOutcome::Blocked,
+        format!("Route blocked by faulty {blocking}."),
+    );
+
+    if !faults.adaptive {
+        return Err(blocked);
+    }
+
+    match route(topology, other_order(algorithm), source, destination) {
+        Ok(alternative) if faults.blocking_element(source, &alternative).is_none() => {
+            Ok((FlowOutcome::Rerouted, alternative))
+        }
+        _ => Err(blocked),
+    }
+}
+
+fn cross(markup: &mut String, (x, y): (f32, f32), size: f32, stroke_width: f32) {
+    let _ = write!(
+        markup,
+        "<path d=\"M{} {}L{} {}M{} {}L{} {}\" stroke=\"{FAULT_COLOUR}\" stroke-width=\"{stroke_width}\" stroke-linecap=\"round\"/>",
+        x - size,
+        y - size,
+        x + size,
+        y + size,
+        x - size,
+        y + size,
+        x + size,
+        y - size
+    );
+}
+
+fn fault_markup(
+    topology: &Topology,
+    geometry: &GridGeometry,
+    faults: &Faults,
+    unroutable: &[FlowReport],
+    channel_loads: &BTreeMap<Channel, u64>,
+) -> String {
+    let stroke_width = geometry.stroke_width();
+    let mut markup = String::from("<g id=\"faults\">");
+
+    // Channel loads once flows are routed around the faults, next to each loaded channel
+    let font_size = geometry.font_size() * 0.75;
+    for (channel, load) in channel_loads {
+        if let Some(to) = topology.neighbour(channel.core, channel.direction) {
+            let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, channel, to);
+            push_text(
+                &mut markup,
+                (x1 + x2) / 2.0 + font_size * 0.25,
+                (y1 + y2) / 2.0 - font_size * 0.25,
+                font_size,
+                LOAD_COLOUR,
+                load.to_string().as_str(),
+            );
+        }
+    }
+
+    for element in &faults.elements {
+        match element {
+            ElementId::Core(core) => {
+                let (x, y, width, height) = geometry.core_rect(topology, *core);
+                let _ = write!(
+                    markup,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"{FAULT_COLOUR}\" fill-opacity=\"0.25\" stroke=\"{FAULT_COLOUR}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+                    stroke_width * 1.5,
+                    stroke_width * 3.0
+                );
+                cross(
+                    &mut markup,
+                    (x + width / 2.0, y + height / 2.0),
+                    width.min(height) * 0.3,
+                    stroke_width * 1.5,
+                );
+            }
+            ElementId::Router(core) => {
+                let (cx, cy, r) = geometry.router_circle(topology, *core);
+                let _ = write!(
+                    markup,
+                    "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"{FAULT_COLOUR}\" fill-opacity=\"0.25\" stroke=\"{FAULT_COLOUR}\" stroke-width=\"{}\"/>",
+                    stroke_width * 1.5
+                );
+                cross(&mut markup, (cx, cy), r * 0.6, stroke_width * 1.5);
+            }
+            ElementId::Channel(channel) => {
+                if let Some(to) = topology.neighbour(channel.core, channel.direction) {
+                    let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, channel, to);
+                    let _ = write!(
+                        markup,
+                        "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{FAULT_COLOUR}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+                        stroke_width * 2.0,
+                        stroke_width * 2.0
+                    );
+                    cross(
+                        &mut markup,
+                        ((x1 + x2) / 2.0, (y1 + y2) / 2.0),
+                        geometry.font_size() * 0.4,
+                        stroke_width * 1.5,
+                    );
+                }
+            }
+        }
+    }
+
+    // Blocked and unroutable flows as dotted lines between their cores
+    for flow in unroutable {
+        let (x1, y1) = geometry.centre(topology, flow.source_core);
+        let (x2, y2) = geometry.centre(topology, flow.destination_core);
+        let _ = write!(
+            markup,
+            "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{UNROUTABLE_COLOUR}\" stroke-width=\"{}\" stroke-dasharray=\"{} {}\" stroke-linecap=\"round\" stroke-opacity=\"0.8\"/>",
+            stroke_width,
+            stroke_width * 0.1,
+            stroke_width * 2.5
+        );
+    }
+
+    markup.push_str("</g>");
+
+    markup
+}
+
+/// Routes every allocated flow around the faults and builds the fault overlay.
+fn fault_report(
+    snapshot: &SystemSnapshot,
+    geometry: &GridGeometry,
+    faults: &Faults,
+) -> Result<FaultReport, String> {
+    let algorithm_name = faults
+        .algorithm
+        .clone()
+        .or(snapshot.routing_algorithm.clone())
+        .ok_or("Please select a routing algorithm.")?;
+    let algorithm = algorithm_name.parse::<RoutingAlgorithm>()?;
+    let topology = Topology::from_snapshot(snapshot);
+
+    let mut affected_flows = Vec::new();
+    let mut channel_loads: BTreeMap<Channel, u64> = BTreeMap::new();
+
+    for edge in &snapshot.edges {
+        let (Some(source), Some(destination)) =
+            (snapshot.task_core(edge.from), snapshot.task_core(edge.to))
+        else {
+            continue;
+        };
+
+        let mut report = FlowReport {
+            from_task: edge.from,
+            to_task: edge.to,
+            source_core: source,
+            destination_core: destination,
+            outcome: FlowOutcome::Routed,
+            reason: None,
+        };
+
+        match route_around(&topology, faults, algorithm, source, destination) {
+            Ok((outcome, hops)) => {
+                for hop in hops {
+                    *channel_loads.entry(hop.channel).or_default() += edge.communication_cost;
+                }
+                report.outcome = outcome;
+            }
+            Err((outcome, reason)) => {
+                report.outcome = outcome;
+                report.reason = Some(reason);
+            }
+        }
+
+        if report.outcome != FlowOutcome::Routed {
+            affected_flows.push(report);
+        }
+    }
+
+    let unroutable = affected_flows
+        .iter()
+        .filter(|flow| matches!(flow.outcome, FlowOutcome::Blocked | FlowOutcome::Unroutable))
+        .cloned()
+        .collect::<Vec<FlowReport>>();
+
+    Ok(FaultReport {
+        algorithm: algorithm_name,
+        faults: faults.elements.iter().copied().collect(),
+        unroutable_count: unroutable.len(),
+        overlay: fault_markup(&topology, geometry, faults, &unroutable, &channel_loads),
+        affected_flows,
+        channel_loads: channel_loads
+            .into_iter()
+            .map(|(channel, load)| (ElementId::Channel(channel), load))
+            .collect(),
+    })
+}
+
+/// Redraws the fault overlay after the render or the faults changed.
+pub(crate) fn refresh_faults(document: &mut Document) -> Result<Option<FaultReport>, String> {
+    if document.faults.elements.is_empty() {
+        document.overlays.remove(FAULTS_LAYER);
+        return Ok(None);
+    }
+
+    let (Some(snapshot), Some(geometry)) = (&document.snapshot, document.overlays.geometry) else {
+        return Err(String::from("Load a system before injecting faults."));
+    };
+
+    let report = fault_report(snapshot, &geometry, &document.faults)?;
+    document.overlays.set(FAULTS_LAYER, report.overlay.clone());
+
+    Ok(Some(report))
+}
+
+#[derive(Serialize)]
+pub struct FaultResult {
+    status: ResultStatus,
+    message: String,
+    report: Option<FaultReport>,
+}
+
+impl From<Result<Option<FaultReport>, String>> for FaultResult {
+    fn from(value: Result<Option<FaultReport>, String>) -> Self {
+        match value {
+            Ok(Some(report)) => Self {
+                status: ResultStatus::Ok,
+                message: match report.unroutable_count {
+                    0 => String::from("All flows can be routed."),
+                    1 => String::from("1 flow cannot be routed."),
+                    count => format!("{count} flows cannot be routed."),
+                },
+                report: Some(report),
+            },
+            Ok(None) => Self {
+                status: ResultStatus::Ok,
+                message: String::from("No faults."),
+                report: None,
+            },
+            Err(message) => Self {
+                status: ResultStatus::Error,
+                message,
+                report: None,
+            },
+        }
+    }
+}
+
+/// Marks an element as faulty, or repairs it, and reroutes flows around the faults.
+/// Uses the system's routing algorithm when none is provided. With `adaptive`, blocked flows may
+/// take the dimensions in the opposite order; the previous choice is kept when not provided.
+#[tauri::command]
+pub fn set_fault(
+    element: ElementId,
+    faulty: bool,
+    algorithm: Option<String>,
+    adaptive: Option<bool>,
+    state: tauri::State<State>,
+) -> FaultResult {
+    let mut document = state.write();
+    let exists = document
+        .snapshot
+        .as_ref()
+        .is_some_and(|snapshot| match element {
+            ElementId::Channel(Channel { core, direction }) => Topology::from_snapshot(snapshot)
+                .neighbour(core, direction)
+                .is_some(),
+            _ => snapshot.attributes(&element).is_some(),
+        });
+
+    if !exists {
+        return FaultResult::from(Err(format!("Element \"{element}\" does not exist.")));
+    }
+    // Faults are left as they were if the algorithm can't route
+    if let Some(Err(e)) = algorithm.as_deref().map(str::parse::<RoutingAlgorithm>) {
+        return FaultResult::from(Err(e));
+    }
+
+    if faulty {
+        document.faults.elements.insert(element);
+    } else {
+        document.faults.elements.remove(&element);
+    }
+    if algorithm.is_some() {
+        document.faults.algorithm = algorithm;
+    }
+    if let Some(adaptive) = adaptive {
+        document.faults.adaptive = adaptive;
+    }
+
+    FaultResult::from(refresh_faults(&mut document))
+}
+
+/// Repairs every element and removes the fault overlay.
+#[tauri::command]
+pub fn clear_faults(state: tauri::State<State>) -> FaultResult {
+    let mut document = state.write();
+    document.faults = Faults::default();
+
+    FaultResult::from(refresh_faults(&mut document))
+}
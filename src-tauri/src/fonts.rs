@@ -0,0 +1,307 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use resvg::usvg::{
+    fontdb::{Database, Source, Style},
+    Options, Tree, WriteOptions,
+};
+use serde::Serialize;
+
+use crate::{
+    export::WholeConfiguration,
+    overlay::{escape, insert_markup, insert_root_attribute},
+    result_status::ResultStatus,
+    State,
+};
+
+static FONT_ATTRIBUTE: &'static str = "data-font";
+static FONT_FAMILY_PROPERTY: &'static str = "font-family";
+
+#[derive(Serialize)]
+pub struct FontsResult {
+    status: ResultStatus,
+    message: String,
+    /// Every family that can be used in the render.
+    families: Vec<String>,
+}
+
+/// Names of the font families in `font_database`, sorted.
+fn families(font_database: &Database) -> Vec<String> {
+    font_database
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(family, _)| family.clone()))
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// Whether any face of `family` is loaded.
+pub(crate) fn has_family(font_database: &Database, family: &str) -> bool {
+    font_database
+        .faces()
+        .any(|face| face.families.iter().any(|(name, _)| name == family))
+}
+
+#[tauri::command]
+pub fn get_fonts(state: tauri::State<State>) -> FontsResult {
+    FontsResult {
+        status: ResultStatus::Ok,
+        message: String::from("Ok"),
+        families: families(&state.font_database()),
+    }
+}
+
+/// Loads the fonts in a TrueType, OpenType or collection file, for the render and exports.
+#[tauri::command]
+pub fn load_font(file_path: &str, state: tauri::State<State>) -> FontsResult {
+    let mut font_database = state.font_database_mut();
+
+    let (status, message) = match fs::read(file_path) {
+        Ok(data) => {
+            let loaded = font_database.load_font_source(Source::Binary(Arc::new(data)));
+            let loaded_families = loaded
+                .iter()
+                .filter_map(|id| font_database.face(*id))
+                .flat_map(|face| face.families.iter().map(|(family, _)| family.clone()))
+                .collect::<BTreeSet<String>>();
+
+            if loaded_families.is_empty() {
+                (
+                    ResultStatus::Error,
+                    String::from("The file does not contain any font."),
+                )
+            } else {
+                (
+                    ResultStatus::Ok,
+                    format!(
+                        "Loaded {}.",
+                        loaded_families.into_iter().collect::<Vec<_>>().join(", ")
+                    ),
+                )
+            }
+        }
+        Err(e) => (
+            ResultStatus::Error,
+            format!("Could not open font file: {e}"),
+        ),
+    };
+
+    FontsResult {
+        status,
+        message,
+        families: families(&font_database),
+    }
+}
+
+/// Draws all text of a serialised render in the configured font family, if any.
+/// Overlays are included, so the font must be applied after they are spliced.
+pub(crate) fn with_font_family(
+    svg_string: String,
+    configuration: Option<&WholeConfiguration>,
+) -> String {
+    let Some(family) = configuration.and_then(|configuration| configuration.font_family.as_ref())
+    else {
+        return svg_string;
+    };
+
+    // Quotes would end the attribute and the rule
+    let family = family.replace(['"', '\''], "");
+    // After the render's own style, which the app replaces on updates
+    let svg_string = insert_markup(
+        svg_string,
+        format!(
+            "<style>[{FONT_ATTRIBUTE}] text{{font-family:\"{}\"}}</style>",
+            escape(family.as_str())
+        )
+        .as_str(),
+    );
+
+    insert_root_attribute(svg_string, FONT_ATTRIBUTE, family.as_str())
+}
+
+/// Replaces all text of a serialised render with paths, so it looks the same without the fonts.
+pub(crate) fn outline_text(svg_str: &str, font_database: &Database) -> Result<String, String> {
+    let tree = Tree::from_str(svg_str, &Options::default(), font_database)
+        .map_err(|e| format!("Could not read the render: {e}"))?;
+
+    Ok(tree.to_string(&WriteOptions::default()))
+}
+
+/// Adds the families of a CSS `font-family` list to `families`, lowercase.
+/// The list ends with the declaration, generic families are included.
+fn family_list(list: &str, families: &mut BTreeSet<String>) {
+    let mut rest = list;
+
+    loop {
+        rest = rest.trim_start();
+        let family = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let Some(end) = rest[1..].find(quote) else {
+                    return;
+                };
+                let family = &rest[1..1 + end];
+                rest = &rest[2 + end..];
+
+                family
+            }
+            _ => {
+                let end = rest.find([',', ';', '}', '"', '\'']).unwrap_or(rest.len());
+                let family = rest[..end].trim();
+                rest = &rest[end..];
+
+                family
+            }
+        };
+
+        if !family.is_empty() {
+            families.insert(family.to_lowercase());
+        }
+        match rest.trim_start().strip_prefix(',') {
+            Some(next) => rest = next,
+            None => return,
+        }
+    }
+}
+
+/// Adds the families of every `font-family` declaration of a stylesheet to `families`.
+fn stylesheet_families(stylesheet: &str, families: &mut BTreeSet<String>) {
+    for (index, _) in stylesheet.match_indices(FONT_FAMILY_PROPERTY) {
+        if let Some(list) = stylesheet[index + FONT_FAMILY_PROPERTY.len()..]
+            .trim_start()
+            .strip_prefix(':')
+        {
+            family_list(list, families);
+        }
+    }
+}
+
+/// Adds the families of the `font-family` and style attributes of an element to `families`.
+fn attribute_families(element: &BytesStart, families: &mut BTreeSet<String>) {
+    for attribute in element.attributes().flatten() {
+        let Ok(value) = attribute.unescape_value() else {
+            continue;
+        };
+        match attribute.key.as_ref() {
+            b"font-family" => family_list(value.as_ref(), families),
+            b"style" => stylesheet_families(value.as_ref(), families),
+            _ => {}
+        }
+    }
+}
+
+/// Font families a serialised render draws text with, lowercase: `font-family` attributes,
+/// style attributes and stylesheets.
+fn used_families(svg_str: &str) -> BTreeSet<String> {
+    let mut ret = BTreeSet::new();
+    let mut reader = Reader::from_str(svg_str);
+    let mut in_style = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                in_style = e.local_name().as_ref() == b"style";
+                attribute_families(&e, &mut ret);
+            }
+            Ok(Event::Empty(e)) => attribute_families(&e, &mut ret),
+            Ok(Event::Text(text)) if in_style => {
+                if let Ok(text) = text.unescape() {
+                    stylesheet_families(text.as_ref(), &mut ret);
+                }
+            }
+            Ok(Event::CData(text)) if in_style => {
+                stylesheet_families(String::from_utf8_lossy(&text).as_ref(), &mut ret);
+            }
+            Ok(Event::End(_)) => in_style = false,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    ret
+}
+
+/// `@font-face` rules embedding every face of `families`, matched case-insensitively.
+/// Faces are embedded whole, the bytes each family adds are returned with the rules.
+fn font_face_rules(
+    font_database: &Database,
+    families: &BTreeSet<String>,
+) -> (String, BTreeMap<String, usize>) {
+    let mut rules = String::new();
+    let mut sizes = BTreeMap::new();
+
+    for face in font_database.faces() {
+        let Some((family, _)) = face
+            .families
+            .iter()
+            .find(|(family, _)| families.contains(&family.to_lowercase()))
+        else {
+            continue;
+        };
+
+        let Some(data) = font_database.with_face_data(face.id, |data, _| STANDARD.encode(data))
+        else {
+            continue;
+        };
+        let font_style = match face.style {
+            Style::Normal => "normal",
+            Style::Italic => "italic",
+            Style::Oblique => "oblique",
+        };
+
+        *sizes.entry(family.clone()).or_insert(0) += data.len();
+        let _ = write!(
+            rules,
+            "@font-face{{font-family:\"{}\";font-weight:{};font-style:{font_style};src:url(data:font/ttf;base64,{data})}}",
+            escape(family.replace('"', "").as_str()),
+            face.weight.0
+        );
+    }
+
+    (rules, sizes)
+}
+
+/// `@font-face` rules of a font family, for webviews that only know the fonts of the system.
+pub(crate) fn family_font_faces(font_database: &Database, family: &str) -> String {
+    font_face_rules(font_database, &BTreeSet::from([family.to_lowercase()])).0
+}
+
+/// Adds `@font-face` rules to a serialised render, if any.
+pub(crate) fn with_font_faces(svg_string: String, rules: &str) -> String {
+    if rules.is_empty() {
+        return svg_string;
+    }
+
+    insert_markup(svg_string, format!("<style>{rules}</style>").as_str())
+}
+
+/// Embeds every face of the font families a serialised render draws text with, so viewers don't
+/// need them installed. Returns the render and what the fonts added to its size, if anything.
+pub(crate) fn embed_fonts(
+    svg_string: String,
+    font_database: &Database,
+) -> (String, Option<String>) {
+    let (rules, sizes) = font_face_rules(font_database, &used_families(svg_string.as_str()));
+
+    let report = match sizes.is_empty() {
+        true => None,
+        false => Some(format!(
+            "Embedded fonts: {}.",
+            sizes
+                .iter()
+                .map(|(family, bytes)| format!("{family} {} KB", bytes.div_ceil(1024)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    };
+
+    (with_font_faces(svg_string, rules.as_str()), report)
+}
@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+
+use manycore_parser::ManycoreSystem;
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Writer,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    result_status::ResultStatus,
+    svg::SVGObject,
+    system::{reparse, TaskEdge},
+    topology::{TopologyKind, DIRECTIONS},
+    update::{load_system, AttributeValue},
+    State,
+};
+
+static NAMESPACE: &'static str =
+    "https://www.york.ac.uk/physics-engineering-technology/ManycoreSystems";
+
+// Ranges enforced by the ManyCore XML schema
+static MIN_COMPUTATION_COST: u8 = 10;
+static MAX_COMPUTATION_COST: u8 = 200;
+static COMMUNICATION_COST_STEP: u64 = 10;
+pub(crate) static DEFAULT_BANDWIDTH: u64 = 100;
+// Attributes written by the generator itself
+static RESERVED_CORE_ATTRIBUTES: &[&str] = &["id", "allocatedTask"];
+// Upper bound of generated communication costs, in steps
+static MAX_COMMUNICATION_STEPS: u64 = 10;
+/// Random seeds stay below 2^53, so the frontend shows them exactly.
+static MAX_RANDOM_SEED: u64 = 1 << 53;
+
+/// A task graph node.
+#[derive(Clone, Debug)]
+pub(crate) struct TaskNode {
+    pub id: u16,
+    pub computation_cost: u8,
+}
+
+/// Everything needed to write a ManyCore XML system from scratch.
+#[derive(Clone, Debug)]
+pub(crate) struct SystemBlueprint {
+    pub rows: u8,
+    pub columns: u8,
+    pub routing_algorithm: Option<String>,
+    pub tasks: Vec<TaskNode>,
+    pub edges: Vec<TaskEdge>,
+    /// Task allocated to each core, keyed by core id.
+    pub allocations: BTreeMap<usize, u16>,
+    /// Attributes given to every core.
+    pub core_attributes: BTreeMap<String, String>,
+    /// Attributes given to every router.
+    pub router_attributes: BTreeMap<String, String>,
+    pub channel_bandwidth: u64,
+}
+
+/// Clamps a computation cost to the range allowed by the schema.
+/// Returns the clamped value and whether it changed.
+pub(crate) fn normalise_computation_cost(cost: u64) -> (u8, bool) {
+    let clamped = cost.clamp(MIN_COMPUTATION_COST.into(), MAX_COMPUTATION_COST.into());
+
+    (clamped as u8, clamped != cost)
+}
+
+/// Rounds a communication cost up to the closest multiple of ten allowed by the schema.
+/// Returns the rounded value and whether it changed.
+pub(crate) fn normalise_communication_cost(cost: u64) -> (u64, bool) {
+    let rounded = cost
+        .max(1)
+        .div_ceil(COMMUNICATION_COST_STEP)
+        .saturating_mul(COMMUNICATION_COST_STEP);
+
+    (rounded, rounded != cost)
+}
+
+impl SystemBlueprint {
+    pub fn core_count(&self) -> usize {
+        usize::from(self.rows) * usize::from(self.columns)
+    }
+
+    fn write_empty(
+        writer: &mut Writer<Vec<u8>>,
+        name: &str,
+        attributes: &[(&str, &str)],
+    ) -> Result<(), String> {
+        let mut element = BytesStart::new(name);
+        for attribute in attributes {
+            element.push_attribute(*attribute);
+        }
+
+        writer
+            .write_event(Event::Empty(element))
+            .map_err(|e| e.to_string())
+    }
+
+    fn write_start(
+        writer: &mut Writer<Vec<u8>>,
+        name: &str,
+        attributes: &[(&str, &str)],
+    ) -> Result<(), String> {
+        let mut element = BytesStart::new(name);
+        for attribute in attributes {
+            element.push_attribute(*attribute);
+        }
+
+        writer
+            .write_event(Event::Start(element))
+            .map_err(|e| e.to_string())
+    }
+
+    fn write_end(writer: &mut Writer<Vec<u8>>, name: &str) -> Result<(), String> {
+        writer
+            .write_event(Event::End(BytesEnd::new(name)))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Writes the system as a ManyCore XML string.
+    pub fn to_xml(&self) -> Result<String, String> {
+        if self.rows == 0 || self.columns == 0 {
+            return Err(String::from("Rows and columns must be at least 1."));
+        }
+
+        if let Some((core, _)) = self
+            .allocations
+            .iter()
+            .find(|(core, _)| **core >= self.core_count())
+        {
+            return Err(format!(
+                "Core {core} does not exist in a {}x{} mesh.",
+                self.rows, self.columns
+            ));
+        }
+
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+        writer
+            .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+            .map_err(|e| e.to_string())?;
+
+        let rows = self.rows.to_string();
+        let columns = self.columns.to_string();
+        let mut root_attributes = vec![
+            ("xmlns", NAMESPACE),
+            ("rows", rows.as_str()),
+            ("columns", columns.as_str()),
+        ];
+        if let Some(routing_algorithm) = &self.routing_algorithm {
+            root_attributes.push(("routingAlgo", routing_algorithm.as_str()));
+        }
+        Self::write_start(&mut writer, "ManycoreSystem", &root_attributes)?;
+
+        Self::write_start(&mut writer, "TaskGraph", &[])?;
+        for task in &self.tasks {
+            Self::write_empty(
+                &mut writer,
+                "Task",
+                &[
+                    ("id", task.id.to_string().as_str()),
+                    (
+                        "computationCost",
+                        task.computation_cost.to_string().as_str(),
+                    ),
+                ],
+            )?;
+        }
+        for edge in &self.edges {
+            Self::write_empty(
+                &mut writer,
+                "Edge",
+                &[
+                    ("from", edge.from.to_string().as_str()),
+                    ("to", edge.to.to_string().as_str()),
+                    (
+                        "communicationCost",
+                        edge.communication_cost.to_string().as_str(),
+                    ),
+                ],
+            )?;
+        }
+        Self::write_end(&mut writer, "TaskGraph")?;
+
+        let bandwidth = self.channel_bandwidth.to_string();
+        let router_attributes = self
+            .router_attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        Self::write_start(&mut writer, "Cores", &[])?;
+        for core in 0..self.core_count() {
+            let id = core.to_string();
+            let allocated_task = self.allocations.get(&core).map(u16::to_string);
+            let mut core_attributes = vec![("id", id.as_str())];
+            if let Some(allocated_task) = &allocated_task {
+                core_attributes.push(("allocatedTask", allocated_task.as_str()));
+            }
+            core_attributes.extend(
+                self.core_attributes
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            );
+            Self::write_start(&mut writer, "Core", &core_attributes)?;
+
+            Self::write_empty(&mut writer, "Router", &router_attributes)?;
+
+            Self::write_start(&mut writer, "Channels", &[])?;
+            for direction in DIRECTIONS {
+                Self::write_empty(
+                    &mut writer,
+                    "Channel",
+                    &[
+                        ("direction", direction.to_string().as_str()),
+                        ("actualComCost", "0"),
+                        ("bandwidth", bandwidth.as_str()),
+                    ],
+                )?;
+            }
+            Self::write_end(&mut writer, "Channels")?;
+
+            Self::write_end(&mut writer, "Core")?;
+        }
+        Self::write_end(&mut writer, "Cores")?;
+
+        Self::write_end(&mut writer, "ManycoreSystem")?;
+
+        String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+    }
+
+    pub fn build(&self) -> Result<ManycoreSystem, String> {
+        reparse(self.to_xml()?.as_str())
+    }
+}
+
+/// Generates a random task graph: every task but the first depends on an earlier task,
+/// so the graph is connected and acyclic.
+fn random_task_graph(rng: &mut StdRng, task_count: u16) -> (Vec<TaskNode>, Vec<TaskEdge>) {
+    let tasks = (0..task_count)
+        .map(|id| TaskNode {
+            id,
+            computation_cost: rng.gen_range(MIN_COMPUTATION_COST..=MAX_COMPUTATION_COST),
+        })
+        .collect();
+
+    let edges = (1..task_count)
+        .map(|to| TaskEdge {
+            from: rng.gen_range(0..to),
+            to,
+            communication_cost: rng.gen_range(1..=MAX_COMMUNICATION_STEPS)
+                * COMMUNICATION_COST_STEP,
+        })
+        .collect();
+
+    (tasks, edges)
+}
+
+/// Options of the "New system" wizard.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSystem {
+    rows: u8,
+    columns: u8,
+    routing_algorithm: Option<String>,
+    #[serde(default)]
+    core_attributes: BTreeMap<String, AttributeValue>,
+    #[serde(default)]
+    router_attributes: BTreeMap<String, AttributeValue>,
+    channel_bandwidth: Option<u64>,
+    /// Number of tasks in the generated task graph. Defaults to the number of cores.
+    task_count: Option<u16>,
+    /// Allocate tasks to random cores. Tasks are left unallocated otherwise.
+    #[serde(default)]
+    random_mapping: bool,
+    /// Seed for the task graph and mapping. A random seed is used if missing.
+    seed: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct NewSystemResult {
+    status: ResultStatus,
+    message: String,
+    /// Seed used, to generate the same system again.
+    seed: Option<u64>,
+    svg: Option<SVGObject>,
+}
+
+impl NewSystem {
+    fn blueprint(self, seed: u64) -> Result<SystemBlueprint, String> {
+        if self.rows == 0 || self.columns == 0 {
+            return Err(String::from("Rows and columns must be at least 1."));
+        }
+
+        if let Some(reserved) = self
+            .core_attributes
+            .keys()
+            .find(|key| RESERVED_CORE_ATTRIBUTES.contains(&key.as_str()))
+        {
+            return Err(format!("Core attribute \"{reserved}\" cannot be set."));
+        }
+
+        let core_count = usize::from(self.rows) * usize::from(self.columns);
+        // The format requires at least one edge
+        let task_count = self
+            .task_count
+            .unwrap_or(u16::try_from(core_count).unwrap_or(u16::MAX))
+            .max(2);
+
+        if self.random_mapping && usize::from(task_count) > core_count {
+            return Err(format!(
+                "{task_count} tasks do not fit in a {}x{} mesh.",
+                self.rows, self.columns
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (tasks, edges) = random_task_graph(&mut rng, task_count);
+
+        let allocations = if self.random_mapping {
+            let mut cores = (0..core_count).collect::<Vec<usize>>();
+            cores.shuffle(&mut rng);
+
+            cores
+                .into_iter()
+                .zip(tasks.iter().map(|task| task.id))
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(SystemBlueprint {
+            rows: self.rows,
+            columns: self.columns,
+            routing_algorithm: self.routing_algorithm,
+            tasks,
+            edges,
+            allocations,
+            core_attributes: self
+                .core_attributes
+                .into_iter()
+                .map(|(key, value)| (key, String::from(value)))
+                .collect(),
+            router_attributes: self
+                .router_attributes
+                .into_iter()
+                .map(|(key, value)| (key, String::from(value)))
+                .collect(),
+            channel_bandwidth: self.channel_bandwidth.unwrap_or(DEFAULT_BANDWIDTH),
+        })
+    }
+}
+
+/// Generates a new mesh system with a random task graph, stores it and renders it.
+/// The system can then be saved through `export_xml`.
+#[tauri::command]
+pub fn new_system(options: NewSystem, state: tauri::State<State>) -> NewSystemResult {
+    let mut ret = NewSystemResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        seed: None,
+        svg: None,
+    };
+
+    let seed = options
+        .seed
+        .unwrap_or_else(|| rand::thread_rng().gen_range(0..MAX_RANDOM_SEED));
+
+    match options
+        .blueprint(seed)
+        .and_then(|blueprint| blueprint.build())
+    {
+        Ok(manycore) => {
+            let mut document = state.write();
+            // Not backed by a file until exported
+            let svg_result = load_system(&state, &mut document, manycore, TopologyKind::Mesh, None);
+
+            ret.status = svg_result.status;
+            ret.message = svg_result.message;
+            ret.svg = svg_result.svg;
+            ret.seed = Some(seed);
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
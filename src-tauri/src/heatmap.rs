@@ -0,0 +1,342 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::Document,
+    overlay::{push_text, Decoration, GridGeometry, Placement},
+    result_status::ResultStatus,
+    svg::{display_render, SVGObject, SVGResult},
+    system::{ElementId, ElementKind, SystemSnapshot},
+    topology::Topology,
+    State,
+};
+
+static HEATMAP_LAYER: &'static str = "heatmap";
+static HEATMAP_LEGEND: &'static str = "heatmap_legend";
+static HEATMAP_OPACITY: f32 = 0.75;
+
+// Control points, evenly spaced over [0, 1]
+static VIRIDIS: &[(u8, u8, u8)] = &[
+    (68, 1, 84),
+    (59, 82, 139),
+    (33, 145, 140),
+    (94, 201, 98),
+    (253, 231, 37),
+];
+static MAGMA: &[(u8, u8, u8)] = &[
+    (0, 0, 4),
+    (59, 15, 112),
+    (140, 41, 129),
+    (222, 73, 104),
+    (254, 159, 109),
+    (252, 253, 191),
+];
+static DIVERGING: &[(u8, u8, u8)] = &[
+    (33, 102, 172),
+    (103, 169, 207),
+    (247, 247, 247),
+    (239, 138, 98),
+    (178, 24, 43),
+];
+
+/// Perceptual colour maps available for heatmaps.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub enum ColourMap {
+    #[default]
+    Viridis,
+    Magma,
+    /// Blue to red through white, centred on the middle of the range.
+    Diverging,
+}
+
+impl ColourMap {
+    fn control_points(&self) -> &'static [(u8, u8, u8)] {
+        match self {
+            ColourMap::Viridis => VIRIDIS,
+            ColourMap::Magma => MAGMA,
+            ColourMap::Diverging => DIVERGING,
+        }
+    }
+
+    /// Colour for `t` in [0, 1] as a hex string. Values outside the range are clamped.
+    pub fn colour(&self, t: f64) -> String {
+        let points = self.control_points();
+        let scaled = t.clamp(0.0, 1.0) * (points.len() - 1) as f64;
+        let i = (scaled.floor() as usize).min(points.len() - 2);
+        let f = scaled - i as f64;
+
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+        let (r1, g1, b1) = points[i];
+        let (r2, g2, b2) = points[i + 1];
+
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            lerp(r1, r2),
+            lerp(g1, g2),
+            lerp(b1, b2)
+        )
+    }
+
+    /// Gradient stops, one per control point.
+    fn stops(&self) -> String {
+        let points = self.control_points();
+        let mut ret = String::new();
+
+        for (i, (r, g, b)) in points.iter().enumerate() {
+            let _ = write!(
+                ret,
+                "<stop offset=\"{}\" stop-color=\"#{r:02x}{g:02x}{b:02x}\"/>",
+                i as f64 / (points.len() - 1) as f64
+            );
+        }
+
+        ret
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapConfiguration {
+    element: ElementKind,
+    attribute: String,
+    #[serde(default)]
+    colour_map: ColourMap,
+    /// Lower bound of the scale. Computed from the data if missing.
+    min: Option<f64>,
+    /// Upper bound of the scale. Computed from the data if missing.
+    max: Option<f64>,
+    #[serde(default)]
+    legend_placement: Placement,
+}
+
+fn element_markup(
+    markup: &mut String,
+    topology: &Topology,
+    geometry: &GridGeometry,
+    element: &ElementId,
+    colour: &str,
+) {
+    match element {
+        ElementId::Core(core) => {
+            let (x, y, width, height) = geometry.core_rect(topology, *core);
+            let _ = write!(
+                markup,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"{colour}\" fill-opacity=\"{HEATMAP_OPACITY}\"/>"
+            );
+        }
+        ElementId::Router(core) => {
+            let (cx, cy, r) = geometry.router_circle(topology, *core);
+            let _ = write!(
+                markup,
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"{colour}\" fill-opacity=\"{HEATMAP_OPACITY}\"/>"
+            );
+        }
+        ElementId::Channel(channel) => {
+            if let Some(to) = topology.neighbour(channel.core, channel.direction) {
+                let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, channel, to);
+                let _ = write!(
+                    markup,
+                    "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{colour}\" stroke-width=\"{}\" stroke-opacity=\"{HEATMAP_OPACITY}\"/>",
+                    geometry.stroke_width() * 3.0
+                );
+            }
+        }
+    }
+}
+
+/// Vertical gradient bar with the scale bounds.
+fn legend(
+    geometry: &GridGeometry,
+    configuration: &HeatmapConfiguration,
+    min: f64,
+    max: f64,
+) -> Decoration {
+    let font_size = geometry.font_size();
+    let bar_width = geometry.cell_width * 0.2;
+    let bar_height = geometry.cell_height * 2.0;
+    let label_x = bar_width + font_size * 0.5;
+
+    let mut markup = format!(
+        "<g id=\"heatmapLegend\"><defs><linearGradient id=\"heatmapGradient\" x1=\"0\" y1=\"1\" x2=\"0\" y2=\"0\">{}</linearGradient></defs>",
+        configuration.colour_map.stops()
+    );
+    push_text(
+        &mut markup,
+        0.0,
+        font_size,
+        font_size,
+        "black",
+        configuration.attribute.as_str(),
+    );
+    let _ = write!(
+        markup,
+        "<rect x=\"0\" y=\"{}\" width=\"{bar_width}\" height=\"{bar_height}\" fill=\"url(#heatmapGradient)\" stroke=\"black\" stroke-width=\"{}\"/>",
+        font_size * 1.5,
+        geometry.stroke_width() * 0.5
+    );
+    push_text(
+        &mut markup,
+        label_x,
+        font_size * 2.5,
+        font_size,
+        "black",
+        format!("{max}").as_str(),
+    );
+    push_text(
+        &mut markup,
+        label_x,
+        font_size * 1.5 + bar_height,
+        font_size,
+        "black",
+        format!("{min}").as_str(),
+    );
+    markup.push_str("</g>");
+
+    Decoration {
+        placement: configuration.legend_placement,
+        width: label_x + font_size * 0.6 * configuration.attribute.chars().count().max(8) as f32,
+        height: font_size * 2.0 + bar_height,
+        markup,
+    }
+}
+
+/// Builds the heatmap cells and legend. Returns the markup, legend and the scale bounds.
+pub(crate) fn heatmap(
+    snapshot: &SystemSnapshot,
+    geometry: &GridGeometry,
+    configuration: &HeatmapConfiguration,
+) -> Result<(String, Decoration, f64, f64), String> {
+    let values = snapshot.numeric_values(configuration.element, configuration.attribute.as_str());
+    if values.is_empty() {
+        return Err(format!(
+            "No numeric values found for \"{}\".",
+            configuration.attribute
+        ));
+    }
+
+    let min = configuration.min.unwrap_or_else(|| {
+        values
+            .iter()
+            .map(|(_, value)| *value)
+            .fold(f64::INFINITY, f64::min)
+    });
+    let max = configuration.max.unwrap_or_else(|| {
+        values
+            .iter()
+            .map(|(_, value)| *value)
+            .fold(f64::NEG_INFINITY, f64::max)
+    });
+    if min > max {
+        return Err(String::from(
+            "Heatmap minimum must not be greater than its maximum.",
+        ));
+    }
+
+    let topology = Topology::from_snapshot(snapshot);
+    let mut markup = String::from("<g id=\"heatmap\">");
+    for (element, value) in &values {
+        let t = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.5
+        };
+
+        element_markup(
+            &mut markup,
+            &topology,
+            geometry,
+            element,
+            configuration.colour_map.colour(t).as_str(),
+        );
+    }
+    markup.push_str("</g>");
+
+    Ok((markup, legend(geometry, configuration, min, max), min, max))
+}
+
+#[derive(Serialize)]
+pub struct HeatmapOverlay {
+    min: f64,
+    max: f64,
+    overlay: String,
+    legend: String,
+}
+
+#[derive(Serialize)]
+pub struct HeatmapResult {
+    status: ResultStatus,
+    message: String,
+    heatmap: Option<HeatmapOverlay>,
+    svg: Option<SVGObject>,
+}
+
+/// Colours every element of a kind by the value of a numeric attribute.
+/// Returns the render with the heatmap and its legend.
+#[tauri::command]
+pub fn set_heatmap(
+    configuration: HeatmapConfiguration,
+    state: tauri::State<State>,
+) -> HeatmapResult {
+    let mut ret = HeatmapResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        heatmap: None,
+        svg: None,
+    };
+
+    let mut document = state.write();
+    let Document {
+        snapshot, overlays, ..
+    } = &mut *document;
+    let (Some(snapshot), Some(geometry)) = (&*snapshot, overlays.geometry) else {
+        ret.message = String::from("Load a system before generating a heatmap.");
+        return ret;
+    };
+
+    match heatmap(snapshot, &geometry, &configuration) {
+        Ok((overlay, legend, min, max)) => {
+            overlays.set(HEATMAP_LAYER, overlay.clone());
+            let legend_markup = legend.markup.clone();
+            overlays.set_decoration(HEATMAP_LEGEND, legend);
+
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Successfully generated heatmap.");
+            ret.heatmap = Some(HeatmapOverlay {
+                min,
+                max,
+                overlay,
+                legend: legend_markup,
+            });
+            ret.svg = display_render(&mut document).ok();
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
+
+/// Removes the heatmap and its legend. Returns the render without them.
+#[tauri::command]
+pub fn clear_heatmap(state: tauri::State<State>) -> SVGResult {
+    let mut ret = SVGResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        svg: None,
+    };
+
+    let mut document = state.write();
+    document.overlays.remove(HEATMAP_LAYER);
+    document.overlays.remove_decoration(HEATMAP_LEGEND);
+
+    match display_render(&mut document) {
+        Ok(svg) => {
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Heatmap cleared.");
+            ret.svg = Some(svg);
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
@@ -0,0 +1,871 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generate::{
+        normalise_communication_cost, normalise_computation_cost, SystemBlueprint, TaskNode,
+        DEFAULT_BANDWIDTH,
+    },
+    result_status::ResultStatus,
+    svg::SVGObject,
+    system::TaskEdge,
+    topology::TopologyKind,
+    update::load_system,
+    State,
+};
+
+// Attributes read as costs, in order of preference
+static NODE_COST_KEYS: &[&str] = &["computationCost", "cost", "weight"];
+static EDGE_COST_KEYS: &[&str] = &["communicationCost", "cost", "weight", "label"];
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskGraphFormat {
+    Dot,
+    Json,
+    Tgff,
+}
+
+impl TaskGraphFormat {
+    fn from_path(file_path: &str) -> Option<Self> {
+        match Path::new(file_path)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase()
+            .as_str()
+        {
+            "dot" | "gv" => Some(Self::Dot),
+            "json" => Some(Self::Json),
+            "tgff" => Some(Self::Tgff),
+            _ => None,
+        }
+    }
+}
+
+/// Task graph as read from a file. Tasks are identified by their name in the file.
+#[derive(Default, Debug)]
+struct TaskGraph {
+    /// Task names in order of appearance, with their computation cost if provided.
+    tasks: Vec<(String, Option<u64>)>,
+    /// (from, to, communication cost) triples.
+    edges: Vec<(String, String, Option<u64>)>,
+}
+
+impl TaskGraph {
+    fn add_task(&mut self, name: &str, cost: Option<u64>) {
+        match self.tasks.iter_mut().find(|(task, _)| task == name) {
+            Some((_, existing)) => {
+                if cost.is_some() {
+                    *existing = cost;
+                }
+            }
+            None => self.tasks.push((name.to_string(), cost)),
+        }
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, cost: Option<u64>) {
+        self.add_task(from, None);
+        self.add_task(to, None);
+        self.edges.push((from.to_string(), to.to_string(), cost));
+    }
+}
+
+fn parse_cost(attributes: &BTreeMap<String, String>, keys: &[&str]) -> Option<u64> {
+    keys.iter().find_map(|key| {
+        attributes
+            .get(*key)
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .map(|value| value.round() as u64)
+    })
+}
+
+/// Removes `//`, `#` and `/* */` comments outside of quoted strings.
+fn strip_dot_comments(content: &str) -> String {
+    let mut ret = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut quoted = false;
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            ret.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    ret.push(escaped);
+                }
+            } else if c == '"' {
+                quoted = false;
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                quoted = true;
+                ret.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('#', _) if line_start => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                ret.push(' ');
+            }
+            _ => ret.push(c),
+        }
+
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+    }
+
+    ret
+}
+
+/// Splits a DOT body into tokens. Quoted strings become a single token without quotes.
+fn tokenise_dot(content: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                token.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        c => token.push(c),
+                    }
+                }
+                tokens.push(token);
+            }
+            '-' if matches!(chars.peek(), Some('>') | Some('-')) => {
+                chars.next();
+                tokens.push(String::from("->"));
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' => tokens.push(c.to_string()),
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !"{}[];,=\"-".contains(*c))
+                {
+                    token.push(c);
+                }
+                // Dashes that are not part of an edge operator
+                while chars.peek() == Some(&'-') {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    if matches!(lookahead.peek(), Some('>') | Some('-')) {
+                        break;
+                    }
+                    chars.next();
+                    token.push('-');
+                    while let Some(c) =
+                        chars.next_if(|c| !c.is_whitespace() && !"{}[];,=\"-".contains(*c))
+                    {
+                        token.push(c);
+                    }
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Reads an attribute list, `[` must have been consumed.
+fn dot_attributes(tokens: &mut VecDeque<String>) -> BTreeMap<String, String> {
+    let mut ret = BTreeMap::new();
+
+    while let Some(token) = tokens.pop_front() {
+        match token.as_str() {
+            "]" => break,
+            "," | ";" => {}
+            key => {
+                if tokens.front().map(String::as_str) == Some("=") {
+                    tokens.pop_front();
+                    if let Some(value) = tokens.pop_front() {
+                        ret.insert(key.to_string(), value);
+                    }
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// Parses the statements of a Graphviz DOT graph.
+/// Node and edge costs are read from `computationCost`/`communicationCost`, `cost`, `weight`
+/// or a numeric `label`. Subgraphs are flattened and graph level attributes ignored.
+fn parse_dot(content: &str) -> Result<TaskGraph, String> {
+    let mut tokens: VecDeque<String> = tokenise_dot(strip_dot_comments(content).as_str()).into();
+
+    // Skip the graph header
+    match tokens.iter().position(|token| token == "{") {
+        Some(position) => {
+            tokens.drain(..=position);
+        }
+        None => return Err(String::from("Could not find a graph body.")),
+    }
+
+    let mut graph = TaskGraph::default();
+    dot_statements(&mut graph, &mut tokens);
+
+    if graph.tasks.is_empty() {
+        return Err(String::from("Graph contains no nodes."));
+    }
+
+    Ok(graph)
+}
+
+/// Reads the statements of a graph or subgraph body up to its closing `}`, which must not have
+/// been consumed. Returns the nodes the body mentions, for edges to or from the subgraph.
+/// Statements end at `;`, or where the next one starts: `a -> b c -> d` is two edges.
+fn dot_statements(graph: &mut TaskGraph, tokens: &mut VecDeque<String>) -> Vec<String> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.front() {
+        match token.as_str() {
+            "}" => {
+                tokens.pop_front();
+                break;
+            }
+            ";" | "," => {
+                tokens.pop_front();
+            }
+            _ => dot_statement(graph, tokens, &mut nodes),
+        }
+    }
+
+    nodes
+}
+
+/// An edge end: a node or the nodes of a subgraph.
+enum DotOperand {
+    Node(String),
+    Subgraph(Vec<String>),
+}
+
+impl DotOperand {
+    fn nodes(&self) -> &[String] {
+        match self {
+            DotOperand::Node(node) => std::slice::from_ref(node),
+            DotOperand::Subgraph(nodes) => nodes,
+        }
+    }
+}
+
+/// Reads a node or a subgraph, `{ ... }` or `subgraph [name] { ... }`.
+/// Nodes are added to the graph as they appear.
+fn dot_operand(graph: &mut TaskGraph, tokens: &mut VecDeque<String>) -> Option<DotOperand> {
+    let token = tokens.pop_front()?;
+
+    match token.as_str() {
+        "subgraph" => {
+            // Subgraph name, if any, is not a node
+            if tokens.front().is_some_and(|token| token != "{") {
+                tokens.pop_front();
+            }
+            if tokens.front().is_some_and(|token| token == "{") {
+                tokens.pop_front();
+            }
+            Some(DotOperand::Subgraph(dot_statements(graph, tokens)))
+        }
+        "{" => Some(DotOperand::Subgraph(dot_statements(graph, tokens))),
+        _ => {
+            graph.add_task(token.as_str(), None);
+            Some(DotOperand::Node(token))
+        }
+    }
+}
+
+/// Reads a single statement, adding the nodes it mentions to `nodes`.
+fn dot_statement(graph: &mut TaskGraph, tokens: &mut VecDeque<String>, nodes: &mut Vec<String>) {
+    match (
+        tokens.front().map(String::as_str),
+        tokens.get(1).map(String::as_str),
+    ) {
+        // Default attributes
+        (Some("graph" | "node" | "edge"), Some("[")) => {
+            tokens.drain(..2);
+            dot_attributes(tokens);
+            return;
+        }
+        // Graph attribute
+        (Some(_), Some("=")) => {
+            tokens.drain(..3.min(tokens.len()));
+            return;
+        }
+        _ => {}
+    }
+
+    let Some(first) = dot_operand(graph, tokens) else {
+        return;
+    };
+
+    let mut operands = vec![first];
+    while tokens.front().is_some_and(|token| token == "->") {
+        tokens.pop_front();
+        match dot_operand(graph, tokens) {
+            Some(operand) => operands.push(operand),
+            None => break,
+        }
+    }
+
+    let attributes = match tokens.front().map(String::as_str) {
+        Some("[") => {
+            tokens.pop_front();
+            dot_attributes(tokens)
+        }
+        _ => BTreeMap::new(),
+    };
+
+    match operands.as_slice() {
+        [DotOperand::Node(node)] => {
+            graph.add_task(node, parse_cost(&attributes, NODE_COST_KEYS));
+            nodes.push(node.clone());
+        }
+        _ => {
+            let cost = parse_cost(&attributes, EDGE_COST_KEYS);
+            // Subgraph ends connect every node they contain
+            for pair in operands.windows(2) {
+                for from in pair[0].nodes() {
+                    for to in pair[1].nodes() {
+                        graph.add_edge(from, to, cost);
+                    }
+                }
+            }
+
+            for operand in &operands {
+                nodes.extend(operand.nodes().iter().cloned());
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTaskId {
+    Number(u64),
+    Name(String),
+}
+
+impl From<JsonTaskId> for String {
+    fn from(value: JsonTaskId) -> Self {
+        match value {
+            JsonTaskId::Number(number) => number.to_string(),
+            JsonTaskId::Name(name) => name,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTask {
+    Id(JsonTaskId),
+    Task {
+        id: JsonTaskId,
+        #[serde(alias = "computationCost", alias = "cost", alias = "weight")]
+        computation_cost: Option<u64>,
+    },
+}
+
+#[derive(Deserialize)]
+struct JsonEdge {
+    #[serde(alias = "source")]
+    from: JsonTaskId,
+    #[serde(alias = "target")]
+    to: JsonTaskId,
+    #[serde(alias = "communicationCost", alias = "cost", alias = "weight")]
+    communication_cost: Option<u64>,
+}
+
+/// `{"tasks": [0, {"id": 1, "computationCost": 20}], "edges": [{"from": 0, "to": 1, "communicationCost": 30}]}`.
+/// Tasks only referenced by edges do not need to be listed.
+#[derive(Deserialize)]
+struct JsonTaskGraph {
+    #[serde(default, alias = "nodes")]
+    tasks: Vec<JsonTask>,
+    #[serde(default, alias = "links")]
+    edges: Vec<JsonEdge>,
+}
+
+fn parse_json(content: &str) -> Result<TaskGraph, String> {
+    let json_graph = serde_json::from_str::<JsonTaskGraph>(content).map_err(|e| e.to_string())?;
+    let mut graph = TaskGraph::default();
+
+    for task in json_graph.tasks {
+        match task {
+            JsonTask::Id(id) => graph.add_task(String::from(id).as_str(), None),
+            JsonTask::Task {
+                id,
+                computation_cost,
+            } => graph.add_task(String::from(id).as_str(), computation_cost),
+        }
+    }
+
+    for edge in json_graph.edges {
+        graph.add_edge(
+            String::from(edge.from).as_str(),
+            String::from(edge.to).as_str(),
+            edge.communication_cost,
+        );
+    }
+
+    if graph.tasks.is_empty() {
+        return Err(String::from("Graph contains no tasks."));
+    }
+
+    Ok(graph)
+}
+
+/// Parses TGFF task graphs. `TASK` and `ARC` lines of every `@TASK_GRAPH` block are merged
+/// into one graph. Arc costs are looked up by type in `@COMMUN` tables, if present.
+fn parse_tgff(content: &str) -> Result<TaskGraph, String> {
+    let mut graph = TaskGraph::default();
+    // Arcs with their communication type
+    let mut arcs: Vec<(String, String, Option<usize>)> = Vec::new();
+    let mut communication: BTreeMap<usize, u64> = BTreeMap::new();
+    let mut block: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('@') {
+            block = header.split_whitespace().next().map(String::from);
+            continue;
+        }
+
+        if line.starts_with('}') {
+            block = None;
+            continue;
+        }
+
+        let fields = line
+            .trim_end_matches('}')
+            .split_whitespace()
+            .collect::<Vec<&str>>();
+
+        match block.as_deref() {
+            Some("TASK_GRAPH") => match fields.as_slice() {
+                ["TASK", name, ..] => graph.add_task(name, None),
+                ["ARC", _, "FROM", from, "TO", to, rest @ ..] => {
+                    let arc_type = match rest {
+                        ["TYPE", arc_type, ..] => arc_type.parse().ok(),
+                        _ => None,
+                    };
+                    arcs.push((from.to_string(), to.to_string(), arc_type));
+                }
+                _ => {}
+            },
+            Some(name) if name.starts_with("COMMUN") => {
+                if let [arc_type, quantity, ..] = fields.as_slice() {
+                    if let (Ok(arc_type), Ok(quantity)) =
+                        (arc_type.parse::<usize>(), quantity.parse::<f64>())
+                    {
+                        communication
+                            .entry(arc_type)
+                            .or_insert(quantity.max(0.0).round() as u64);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (from, to, arc_type) in arcs {
+        let cost = arc_type.and_then(|arc_type| communication.get(&arc_type).copied());
+        graph.add_edge(from.as_str(), to.as_str(), cost);
+    }
+
+    if graph.tasks.is_empty() {
+        return Err(String::from("File contains no TASK_GRAPH tasks."));
+    }
+
+    Ok(graph)
+}
+
+/// Allocates tasks to cores in topological order, snaking through the mesh rows
+/// so that consecutive tasks land on neighbouring cores.
+fn snake_mapping(tasks: &[TaskNode], edges: &[TaskEdge], columns: usize) -> BTreeMap<usize, u16> {
+    let mut in_degree: BTreeMap<u16, usize> = tasks.iter().map(|task| (task.id, 0)).collect();
+    let mut successors: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+    for edge in edges {
+        *in_degree.entry(edge.to).or_default() += 1;
+        successors.entry(edge.from).or_default().push(edge.to);
+    }
+
+    let mut queue: VecDeque<u16> = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(task, _)| *task)
+        .collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    let mut visited = BTreeSet::new();
+
+    loop {
+        while let Some(task) = queue.pop_front() {
+            if !visited.insert(task) {
+                continue;
+            }
+            order.push(task);
+
+            for successor in successors.get(&task).into_iter().flatten() {
+                if let Some(degree) = in_degree.get_mut(successor) {
+                    *degree = degree.saturating_sub(1);
+                    if *degree == 0 {
+                        queue.push_back(*successor);
+                    }
+                }
+            }
+        }
+
+        // Break cycles at the lowest unvisited task
+        match tasks.iter().find(|task| !visited.contains(&task.id)) {
+            Some(task) => queue.push_back(task.id),
+            None => break,
+        }
+    }
+
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let (row, column) = (i / columns, i % columns);
+            let column = if row % 2 == 0 {
+                column
+            } else {
+                columns - 1 - column
+            };
+
+            (row * columns + column, task)
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskGraphImport {
+    file_path: String,
+    /// Guessed from the file extension if missing.
+    format: Option<TaskGraphFormat>,
+    rows: u8,
+    columns: u8,
+    routing_algorithm: Option<String>,
+    /// Core each task is allocated to, keyed by task name as it appears in the file.
+    /// Tasks are mapped automatically if missing.
+    mapping: Option<BTreeMap<String, usize>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    status: ResultStatus,
+    message: String,
+    /// Values changed to fit the ManyCore XML format.
+    warnings: Vec<String>,
+    /// Task id assigned to each task name.
+    task_ids: BTreeMap<String, u16>,
+    svg: Option<SVGObject>,
+}
+
+impl TaskGraphImport {
+    fn blueprint(
+        &self,
+        graph: TaskGraph,
+    ) -> Result<(SystemBlueprint, Vec<String>, BTreeMap<String, u16>), String> {
+        let mut warnings = Vec::new();
+
+        // Keep numeric names as ids when they all fit
+        let numeric = graph
+            .tasks
+            .iter()
+            .map(|(name, _)| name.parse::<u16>().ok())
+            .collect::<Option<BTreeSet<u16>>>()
+            .filter(|ids| ids.len() == graph.tasks.len());
+
+        let task_ids = graph
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                let id = match &numeric {
+                    Some(_) => name.parse::<u16>().ok(),
+                    None => u16::try_from(i).ok(),
+                };
+                id.map(|id| (name.clone(), id))
+                    .ok_or_else(|| String::from("Task graph has too many tasks."))
+            })
+            .collect::<Result<BTreeMap<String, u16>, String>>()?;
+
+        let tasks = graph
+            .tasks
+            .iter()
+            .map(|(name, cost)| {
+                let (computation_cost, changed) = match cost {
+                    Some(cost) => normalise_computation_cost(*cost),
+                    None => normalise_computation_cost(0),
+                };
+                if changed && cost.is_some() {
+                    warnings.push(format!(
+                        "Task \"{name}\" computation cost clamped to {computation_cost}."
+                    ));
+                }
+
+                TaskNode {
+                    id: task_ids[name],
+                    computation_cost,
+                }
+            })
+            .collect::<Vec<TaskNode>>();
+
+        let edges = graph
+            .edges
+            .iter()
+            .map(|(from, to, cost)| {
+                let (communication_cost, changed) = normalise_communication_cost(cost.unwrap_or(0));
+                if changed && cost.is_some() {
+                    warnings.push(format!(
+                        "Edge \"{from}\" -> \"{to}\" communication cost rounded to {communication_cost}."
+                    ));
+                }
+
+                TaskEdge {
+                    from: task_ids[from],
+                    to: task_ids[to],
+                    communication_cost,
+                }
+            })
+            .collect::<Vec<TaskEdge>>();
+
+        if graph.edges.iter().any(|(_, _, cost)| cost.is_none()) {
+            warnings.push(String::from(
+                "Edges without a cost were given the minimum communication cost.",
+            ));
+        }
+
+        let core_count = usize::from(self.rows) * usize::from(self.columns);
+        let allocations = match &self.mapping {
+            Some(mapping) => {
+                let mut allocations = BTreeMap::new();
+                for (name, core) in mapping {
+                    let Some(task) = task_ids.get(name) else {
+                        return Err(format!("Task \"{name}\" is not in the task graph."));
+                    };
+                    if *core >= core_count {
+                        return Err(format!("Core {core} does not exist."));
+                    }
+                    if allocations.insert(*core, *task).is_some() {
+                        return Err(format!("Core {core} has more than one task."));
+                    }
+                }
+                allocations
+            }
+            None => {
+                if tasks.len() > core_count {
+                    return Err(format!(
+                        "{} tasks do not fit in a {}x{} mesh.",
+                        tasks.len(),
+                        self.rows,
+                        self.columns
+                    ));
+                }
+                snake_mapping(&tasks, &edges, self.columns.into())
+            }
+        };
+
+        Ok((
+            SystemBlueprint {
+                rows: self.rows,
+                columns: self.columns,
+                routing_algorithm: self.routing_algorithm.clone(),
+                tasks,
+                edges,
+                allocations,
+                core_attributes: BTreeMap::new(),
+                router_attributes: BTreeMap::new(),
+                channel_bandwidth: DEFAULT_BANDWIDTH,
+            },
+            warnings,
+            task_ids,
+        ))
+    }
+}
+
+/// Imports a task graph from a DOT, JSON or TGFF file and maps it onto a new mesh.
+/// The resulting system replaces the loaded one and can be exported as XML.
+#[tauri::command]
+pub fn import_task_graph(import: TaskGraphImport, state: tauri::State<State>) -> ImportResult {
+    let mut ret = ImportResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        warnings: Vec::new(),
+        task_ids: BTreeMap::new(),
+        svg: None,
+    };
+
+    let Some(format) = import
+        .format
+        .or_else(|| TaskGraphFormat::from_path(import.file_path.as_str()))
+    else {
+        ret.message = String::from("Unknown task graph format.");
+        return ret;
+    };
+
+    let graph = fs::read_to_string(&import.file_path)
+        .map_err(|e| format!("Could not open task graph: {e}"))
+        .and_then(|content| match format {
+            TaskGraphFormat::Dot => parse_dot(content.as_str()),
+            TaskGraphFormat::Json => parse_json(content.as_str()),
+            TaskGraphFormat::Tgff => parse_tgff(content.as_str()),
+        })
+        .map_err(|e| format!("Could not read task graph: {e}"));
+
+    let built = graph
+        .and_then(|graph| import.blueprint(graph))
+        .and_then(|(blueprint, warnings, task_ids)| Ok((blueprint.build()?, warnings, task_ids)));
+
+    match built {
+        Ok((manycore, warnings, task_ids)) => {
+            let mut document = state.write();
+            let svg_result = load_system(
+                &state,
+                &mut document,
+                manycore,
+                TopologyKind::Mesh,
+                Some(import.file_path.clone()),
+            );
+
+            ret.status = svg_result.status;
+            ret.message = svg_result.message;
+            ret.svg = svg_result.svg;
+            ret.warnings = warnings;
+            ret.task_ids = task_ids;
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(graph: &TaskGraph) -> Vec<(&str, &str)> {
+        graph
+            .edges
+            .iter()
+            .map(|(from, to, _)| (from.as_str(), to.as_str()))
+            .collect()
+    }
+
+    fn tasks(graph: &TaskGraph) -> Vec<&str> {
+        graph.tasks.iter().map(|(task, _)| task.as_str()).collect()
+    }
+
+    #[test]
+    fn dot_statements_end_at_newlines() {
+        let graph = parse_dot("digraph {\n  a\n  b\n  c -> d\n  d -> e\n}").unwrap();
+
+        assert_eq!(tasks(&graph), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(edges(&graph), vec![("c", "d"), ("d", "e")]);
+    }
+
+    #[test]
+    fn dot_statements_end_at_semicolons() {
+        let graph = parse_dot("digraph G { a -> b; b -> c; c; }").unwrap();
+
+        assert_eq!(tasks(&graph), vec!["a", "b", "c"]);
+        assert_eq!(edges(&graph), vec![("a", "b"), ("b", "c")]);
+    }
+
+    #[test]
+    fn dot_edge_chains() {
+        let graph = parse_dot("digraph { a -> b -> c }").unwrap();
+
+        assert_eq!(edges(&graph), vec![("a", "b"), ("b", "c")]);
+    }
+
+    #[test]
+    fn dot_brace_targets_are_expanded() {
+        let graph = parse_dot("digraph { a -> {b c} }").unwrap();
+
+        assert_eq!(tasks(&graph), vec!["a", "b", "c"]);
+        assert_eq!(edges(&graph), vec![("a", "b"), ("a", "c")]);
+    }
+
+    #[test]
+    fn dot_brace_sources_are_expanded() {
+        let graph = parse_dot("digraph { {a b} -> subgraph s { c; d } }").unwrap();
+
+        assert_eq!(
+            edges(&graph),
+            vec![("a", "c"), ("a", "d"), ("b", "c"), ("b", "d")]
+        );
+    }
+
+    #[test]
+    fn dot_costs_are_read_from_attributes() {
+        let graph = parse_dot(
+            "digraph {\n  node [shape=box]\n  a [computationCost=40]\n  b [cost=\"25\"]\n  a -> b [label=12]\n}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            graph.tasks,
+            vec![(String::from("a"), Some(40)), (String::from("b"), Some(25))]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![(String::from("a"), String::from("b"), Some(12))]
+        );
+    }
+
+    #[test]
+    fn dot_graph_attributes_and_comments_are_ignored() {
+        let graph = parse_dot(
+            "// tasks\ndigraph {\n  rankdir = LR\n  # a comment\n  a /* inline */ -> b // trailing\n}",
+        )
+        .unwrap();
+
+        assert_eq!(tasks(&graph), vec!["a", "b"]);
+        assert_eq!(edges(&graph), vec![("a", "b")]);
+    }
+
+    #[test]
+    fn dot_subgraphs_are_flattened() {
+        let graph =
+            parse_dot("digraph { subgraph cluster_0 { a -> b } subgraph { c } b -> c }").unwrap();
+
+        assert_eq!(tasks(&graph), vec!["a", "b", "c"]);
+        assert_eq!(edges(&graph), vec![("a", "b"), ("b", "c")]);
+    }
+
+    #[test]
+    fn dot_without_nodes_is_rejected() {
+        assert!(parse_dot("digraph { }").is_err());
+        assert!(parse_dot("a -> b").is_err());
+    }
+}
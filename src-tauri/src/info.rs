@@ -19,17 +19,15 @@ pub fn get_info(group_id: String, state: tauri::State<State>) -> InfoResult {
         info: None,
     };
 
-    if let Ok(manycore_mutex) = state.manycore.lock() {
-        if let Some(manycore) = &*manycore_mutex {
-            match manycore.get_core_router_specific_info(group_id) {
-                Ok(info_tree) => {
-                    ret.status = ResultStatus::Ok;
-                    ret.message = String::from("Successfully retrieved attributes");
-                    ret.info = info_tree;
-                }
-                Err(e) => {
-                    ret.message = e.to_string();
-                }
+    if let Some(manycore) = &state.read().manycore {
+        match manycore.get_core_router_specific_info(group_id) {
+            Ok(info_tree) => {
+                ret.status = ResultStatus::Ok;
+                ret.message = String::from("Successfully retrieved attributes");
+                ret.info = info_tree;
+            }
+            Err(e) => {
+                ret.message = e.to_string();
             }
         }
     }
@@ -0,0 +1,270 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    document::Document,
+    export::WholeConfiguration,
+    overlay::{push_text, Decoration, GridGeometry, Placement},
+    result_status::ResultStatus,
+    svg::{display_render, SVGObject},
+    State,
+};
+
+static LEGEND: &'static str = "legend";
+
+// Configuration groups as serialised by serde, with their display name
+static ELEMENT_GROUPS: &[(&str, &str)] = &[
+    ("coreConfig", "Core"),
+    ("routerConfig", "Router"),
+    ("channelConfig", "Channel"),
+];
+static FILL_GROUPS: &[(&str, &str)] = &[("coreFills", "Core"), ("routerFills", "Router")];
+
+/// How a legend entry draws its samples.
+enum Sample {
+    Swatch,
+    Text,
+}
+
+struct LegendEntry {
+    title: String,
+    sample: Sample,
+    /// (colour, label) pairs
+    items: Vec<(String, String)>,
+}
+
+fn display_name(key: &str, argument: &Value) -> String {
+    argument
+        .get("display")
+        .and_then(Value::as_str)
+        .filter(|display| !display.is_empty())
+        .unwrap_or(key.trim_start_matches('@'))
+        .to_string()
+}
+
+/// Pairs up the four bounds and colours of a colour configuration.
+fn bounds_items(argument: &Value, suffix: &str) -> Vec<(String, String)> {
+    let (Some(bounds), Some(colours)) = (
+        argument.get("bounds").and_then(Value::as_array),
+        argument.get("colours").and_then(Value::as_array),
+    ) else {
+        return Vec::new();
+    };
+
+    bounds
+        .iter()
+        .zip(colours)
+        .filter_map(|(bound, colour)| {
+            Some((colour.as_str()?.to_string(), format!("≤ {bound}{suffix}")))
+        })
+        .collect()
+}
+
+/// Extracts coloured configuration options from a serialised [`WholeConfiguration`].
+fn entries(whole_configuration: &Value) -> Vec<LegendEntry> {
+    let mut ret = Vec::new();
+    let Some(configuration) = whole_configuration.get("configuration") else {
+        return ret;
+    };
+
+    for (group, element) in ELEMENT_GROUPS {
+        let Some(arguments) = configuration.get(*group).and_then(Value::as_object) else {
+            continue;
+        };
+
+        for (key, argument) in arguments {
+            let name = display_name(key, argument);
+            let entry = match argument.get("type").and_then(Value::as_str) {
+                Some("Fill") => LegendEntry {
+                    title: format!("{element} fill: {name}"),
+                    sample: Sample::Swatch,
+                    items: bounds_items(argument, ""),
+                },
+                Some("ColouredText") => LegendEntry {
+                    title: format!("{element} {name}"),
+                    sample: Sample::Text,
+                    items: bounds_items(argument, ""),
+                },
+                Some("Routing") => {
+                    let percentage = argument.get("loadConfiguration").and_then(Value::as_str)
+                        == Some("Percentage");
+                    let algorithm = argument
+                        .get("algorithm")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+
+                    LegendEntry {
+                        title: format!("{name} ({algorithm})"),
+                        sample: Sample::Swatch,
+                        items: bounds_items(argument, if percentage { "%" } else { "" }),
+                    }
+                }
+                _ => continue,
+            };
+
+            if !entry.items.is_empty() {
+                ret.push(entry);
+            }
+        }
+    }
+
+    for (group, element) in FILL_GROUPS {
+        let Some(fills) = configuration.get(*group).and_then(Value::as_object) else {
+            continue;
+        };
+
+        let items = fills
+            .iter()
+            .filter_map(|(id, colour)| {
+                Some((colour.as_str()?.to_string(), format!("{element} {id}")))
+            })
+            .collect::<Vec<(String, String)>>();
+
+        if !items.is_empty() {
+            ret.push(LegendEntry {
+                title: format!("{element} fill overrides"),
+                sample: Sample::Swatch,
+                items,
+            });
+        }
+    }
+
+    ret
+}
+
+/// Generates a legend for the coloured options of the provided configuration.
+/// Returns [`None`] if the configuration has no coloured options.
+pub(crate) fn legend(
+    geometry: &GridGeometry,
+    whole_configuration: &WholeConfiguration,
+    placement: Placement,
+) -> Option<Decoration> {
+    let value = serde_json::to_value(whole_configuration).ok()?;
+    let entries = entries(&value);
+    if entries.is_empty() {
+        return None;
+    }
+
+    let font_size = geometry.font_size();
+    let line_height = font_size * 1.6;
+    let swatch = font_size;
+    let mut markup = String::from("<g id=\"legend\">");
+    let mut y = 0.0;
+    let mut width: f32 = 0.0;
+
+    for entry in entries {
+        y += line_height;
+        push_text(
+            &mut markup,
+            0.0,
+            y,
+            font_size,
+            "black",
+            entry.title.as_str(),
+        );
+        width = width.max(font_size * 0.6 * entry.title.chars().count() as f32);
+
+        for (colour, label) in entry.items {
+            y += line_height;
+            match entry.sample {
+                Sample::Swatch => {
+                    let _ = write!(
+                        markup,
+                        "<rect x=\"0\" y=\"{}\" width=\"{swatch}\" height=\"{swatch}\" fill=\"{colour}\" stroke=\"black\" stroke-width=\"{}\"/>",
+                        y - swatch * 0.85,
+                        geometry.stroke_width() * 0.25
+                    );
+                }
+                Sample::Text => {
+                    push_text(&mut markup, 0.0, y, font_size, colour.as_str(), "Aa");
+                }
+            }
+            push_text(
+                &mut markup,
+                swatch * 1.8,
+                y,
+                font_size,
+                "black",
+                label.as_str(),
+            );
+            width = width.max(swatch * 1.8 + font_size * 0.6 * label.chars().count() as f32);
+        }
+
+        // Space entries out
+        y += line_height * 0.5;
+    }
+    markup.push_str("</g>");
+
+    Some(Decoration {
+        placement,
+        width,
+        height: y,
+        markup,
+    })
+}
+
+/// Regenerates the legend decoration, if enabled, from the active configuration.
+pub(crate) fn refresh_legend(document: &mut Document) {
+    let decoration = match (
+        document.legend,
+        &document.configuration,
+        document.overlays.geometry,
+    ) {
+        (Some(placement), Some(configuration), Some(geometry)) => {
+            legend(&geometry, configuration, placement)
+        }
+        _ => None,
+    };
+
+    match decoration {
+        Some(decoration) => document.overlays.set_decoration(LEGEND, decoration),
+        None => document.overlays.remove_decoration(LEGEND),
+    }
+}
+
+#[derive(Serialize)]
+pub struct LegendResult {
+    status: ResultStatus,
+    message: String,
+    legend: Option<String>,
+    svg: Option<SVGObject>,
+}
+
+/// Enables the legend at the given placement, or disables it if no placement is provided.
+/// The legend is kept in sync with the configuration applied through `update_svg`.
+/// Returns the render with the legend next to it.
+#[tauri::command]
+pub fn set_legend(placement: Option<Placement>, state: tauri::State<State>) -> LegendResult {
+    let mut document = state.write();
+    document.legend = placement;
+    refresh_legend(&mut document);
+
+    let legend = document
+        .overlays
+        .decoration(LEGEND)
+        .map(|decoration| decoration.markup.clone());
+    let message = match (placement, &legend) {
+        (None, _) => String::from("Legend disabled."),
+        (Some(_), Some(_)) => String::from("Legend enabled."),
+        (Some(_), None) => String::from(
+            "Legend enabled. It will appear once a configuration with colours is applied.",
+        ),
+    };
+
+    match display_render(&mut document) {
+        Ok(svg) => LegendResult {
+            status: ResultStatus::Ok,
+            message,
+            legend,
+            svg: Some(svg),
+        },
+        Err(e) => LegendResult {
+            status: ResultStatus::Error,
+            message: e,
+            legend,
+            svg: None,
+        },
+    }
+}
@@ -0,0 +1,256 @@
+mod annotations;
+#[doc(hidden)]
+pub mod benchmark;
+mod cache;
+mod cluster;
+mod configuration;
+mod deadlock;
+mod document;
+mod edit;
+mod export;
+mod faults;
+mod fonts;
+mod generate;
+mod heatmap;
+mod import;
+mod info;
+mod legend;
+mod live;
+mod mapping;
+mod migration;
+mod overlay;
+mod pages;
+mod parse;
+mod playback;
+mod presets;
+mod progress;
+mod result_status;
+mod routing;
+mod svg;
+mod system;
+mod theme;
+mod topology;
+mod update;
+mod validation;
+
+use std::{
+    error::Error,
+    sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use document::Document;
+use export::{export_configuration, export_xml, load_configuration};
+use live::LiveServer;
+use playback::Playback;
+use progress::LoadGeneration;
+use resvg::usvg::fontdb::Database;
+use tauri::{App, AppHandle, CustomMenuItem, Manager, Menu, Submenu};
+
+// Event names
+static LOAD_NEW_SYSTEM: &'static str = "load_new_system";
+static NEW_SYSTEM: &'static str = "new_system";
+static LOAD_CONFIGURATION: &'static str = "load_config";
+static EXPORT_CONFIGURATION: &'static str = "export_config";
+static EXPORT_XML: &'static str = "export_xml";
+static LOAD_UPDATES: &'static str = "load_updates";
+static TRACE_ROUTE: &'static str = "trace_route";
+static DETECT_DEADLOCKS: &'static str = "detect_deadlocks";
+static HEATMAP: &'static str = "heatmap";
+static LEGEND: &'static str = "legend";
+static LICENSES: &'static str = "licenses";
+static LICENSES_TITLE: &'static str = "Licenses";
+static MANUAL: &'static str = "manual";
+static MANUAL_TITLE: &'static str = "User manual";
+
+/// Cloning shares the state, e.g. with commands running on a blocking thread.
+#[derive(Clone)]
+pub(crate) struct State {
+    /// The loaded system and everything derived from it.
+    pub document: Arc<RwLock<Document>>,
+    pub font_database: Arc<RwLock<Database>>,
+    pub live_server: Arc<Mutex<Option<LiveServer>>>,
+    /// Loaded simulation trace.
+    /// May be locked while holding the document, never lock the document while holding it.
+    pub playback: Arc<Mutex<Option<Playback>>>,
+    /// Bumped on every file load to cancel work for the previous one.
+    pub load_generation: Arc<LoadGeneration>,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut font_database = Database::new();
+        let robot = include_bytes!("../assets/Roboto Mono.ttf");
+        font_database.load_font_data(robot.to_vec());
+
+        let font_database_arc = Arc::new(RwLock::new(font_database));
+
+        Self {
+            document: Default::default(),
+            font_database: font_database_arc,
+            live_server: Default::default(),
+            playback: Default::default(),
+            load_generation: Default::default(),
+        }
+    }
+
+    /// Shared access to the [`Document`].
+    /// A command that panicked while writing doesn't lock everyone else out.
+    pub(crate) fn read(&self) -> RwLockReadGuard<'_, Document> {
+        self.document.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Exclusive access to the [`Document`]. Writers are serialised.
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, Document> {
+        self.document
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn font_database(&self) -> RwLockReadGuard<'_, Database> {
+        self.font_database
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Exclusive access to the fonts, to load new ones.
+    pub(crate) fn font_database_mut(&self) -> RwLockWriteGuard<'_, Database> {
+        self.font_database
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn open_window(handle: &AppHandle, path: &str, title: &str) {
+    if let Ok(window) =
+        tauri::WindowBuilder::new(handle, LICENSES, tauri::WindowUrl::App(path.into())).build()
+    {
+        let _ = window.set_title(title);
+        let _ = window.menu_handle().hide();
+    }
+}
+
+fn app_setup(app: &mut App) -> Result<(), Box<dyn Error>> {
+    let main_window = app.get_window("main").unwrap();
+    let handle = app.handle();
+
+    // Move is gonna capture handle in the closure environment
+    main_window.on_menu_event(move |event| {
+        let window = handle.get_window("main").unwrap();
+
+        let event_id = event.menu_item_id();
+
+        if event_id == LOAD_NEW_SYSTEM {
+            let _ = window.emit(LOAD_NEW_SYSTEM, 0);
+        } else if event_id == NEW_SYSTEM {
+            // Opens the wizard, which calls the new_system command
+            let _ = window.emit(NEW_SYSTEM, 0);
+        } else if event_id == LOAD_CONFIGURATION {
+            load_configuration(window, handle.state());
+        } else if event_id == LOAD_UPDATES {
+            update::load_updates(window, handle.clone());
+        } else if event_id == EXPORT_XML {
+            export_xml(window, handle.state());
+        } else if event_id == EXPORT_CONFIGURATION {
+            export_configuration(window, handle.state());
+        } else if event_id == TRACE_ROUTE
+            || event_id == DETECT_DEADLOCKS
+            || event_id == HEATMAP
+            || event_id == LEGEND
+        {
+            // Analysis items open a modal named as the event
+            let _ = window.emit(event_id, 0);
+        } else if event_id == LICENSES {
+            open_window(&handle, "/licenses.html", &LICENSES_TITLE);
+        } else if event_id == MANUAL {
+            open_window(&handle, "/manual/index.html", &MANUAL_TITLE);
+        }
+    });
+
+    Ok(())
+}
+
+/// Builds the app and runs it until the main window is closed.
+pub fn run() {
+    let load_submenu = Menu::new()
+        .add_item(CustomMenuItem::new(NEW_SYSTEM, "New system"))
+        .add_item(CustomMenuItem::new(LOAD_NEW_SYSTEM, "Load new system"))
+        .add_item(CustomMenuItem::new(
+            LOAD_CONFIGURATION,
+            "Load configuration",
+        ))
+        .add_item(CustomMenuItem::new(LOAD_UPDATES, "Load updates"));
+    let load = Submenu::new("Load", load_submenu);
+
+    let export_submenu = Menu::new()
+        .add_item(CustomMenuItem::new(
+            EXPORT_CONFIGURATION,
+            "Export configuration",
+        ))
+        .add_item(CustomMenuItem::new(EXPORT_XML, "Export XML"));
+    let export = Submenu::new("Export", export_submenu);
+
+    let analysis_submenu = Menu::new()
+        .add_item(CustomMenuItem::new(TRACE_ROUTE, "Trace route"))
+        .add_item(CustomMenuItem::new(DETECT_DEADLOCKS, "Detect deadlocks"))
+        .add_item(CustomMenuItem::new(HEATMAP, "Heatmap"))
+        .add_item(CustomMenuItem::new(LEGEND, "Legend"));
+    let analysis = Submenu::new("Analysis", analysis_submenu);
+
+    let menu = Menu::new()
+        .add_native_item(tauri::MenuItem::CloseWindow)
+        .add_submenu(load)
+        .add_submenu(export)
+        .add_submenu(analysis)
+        .add_item(CustomMenuItem::new(LICENSES, LICENSES_TITLE))
+        .add_item(CustomMenuItem::new(MANUAL, MANUAL_TITLE));
+
+    tauri::Builder::default()
+        .menu(menu)
+        .manage(State::new())
+        .invoke_handler(tauri::generate_handler![
+            parse::parse,
+            svg::get_svg,
+            svg::update_svg,
+            configuration::get_attributes,
+            configuration::get_base_configuration,
+            info::get_info,
+            edit::initiate_edit,
+            export::store_configuration,
+            validation::validate_configuration,
+            export::export_render,
+            export::export_layers,
+            pages::export_pages,
+            presets::get_export_presets,
+            presets::set_export_preset,
+            presets::remove_export_preset,
+            presets::run_export_preset,
+            presets::run_export_presets,
+            theme::set_appearance,
+            fonts::get_fonts,
+            fonts::load_font,
+            cluster::get_clustered_view,
+            cluster::drill_into_cluster,
+            routing::trace_route,
+            routing::clear_route_trace,
+            deadlock::detect_deadlocks,
+            deadlock::clear_deadlock_overlay,
+            heatmap::set_heatmap,
+            heatmap::clear_heatmap,
+            legend::set_legend,
+            live::start_live_server,
+            live::stop_live_server,
+            update::apply_update_message,
+            playback::load_trace,
+            playback::seek_trace,
+            playback::play_trace,
+            playback::pause_trace,
+            import::import_task_graph,
+            generate::new_system,
+            mapping::remap_tasks,
+            faults::set_fault,
+            faults::clear_faults,
+        ])
+        .setup(&app_setup)
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
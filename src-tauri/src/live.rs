@@ -0,0 +1,708 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tungstenite::{
+    handshake::derive_accept_key,
+    http::StatusCode,
+    protocol::{Role, WebSocket},
+    Message,
+};
+
+use crate::{
+    result_status::ResultStatus,
+    update::{apply_updates, replace_system_xml, UpdateMessage},
+    State,
+};
+
+// Event names
+static LIVE_RENDER_EVENT: &'static str = "live_render";
+static LIVE_STATUS_EVENT: &'static str = "live_status";
+
+static DEFAULT_PORT: u16 = 7878;
+static ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+/// Upper bound for HTTP bodies, big meshes produce large XML files.
+static MAX_BODY_LENGTH: usize = 256 * 1024 * 1024;
+/// Upper bound for an HTTP request line and headers.
+static MAX_HEAD_LENGTH: u64 = 16 * 1024;
+/// Connections served at once, further ones are turned away.
+static MAX_CONNECTIONS: usize = 16;
+/// How long a client may stall while sending a request.
+static READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long an open WebSocket may stay silent. Clients can ping to keep it open.
+static WEBSOCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+static TOKEN_LENGTH: usize = 32;
+static TOKEN_QUERY_KEY: &'static str = "token";
+// Hosts that name this machine, in Host and Origin headers
+static LOCAL_HOSTS: &[&str] = &["localhost", "127.0.0.1", "::1", "tauri.localhost"];
+
+/// What a connection must present to be served.
+struct Access {
+    /// Generated when the server starts, clients send it as a bearer token or a `token` query
+    /// parameter.
+    token: String,
+    port: u16,
+}
+
+/// Whether `authority` (`host[:port]`) names this machine, on `port` if given.
+fn is_local_authority(authority: &str, port: Option<u16>) -> bool {
+    let (host, rest) = match authority.strip_prefix('[') {
+        // IPv6 literals are bracketed as they contain colons themselves
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some(split) => split,
+            None => return false,
+        },
+        None => authority.split_at(authority.find(':').unwrap_or(authority.len())),
+    };
+    let authority_port = match rest.strip_prefix(':') {
+        Some(authority_port) => match authority_port.parse::<u16>() {
+            Ok(authority_port) => Some(authority_port),
+            Err(_) => return false,
+        },
+        None if rest.is_empty() => None,
+        None => return false,
+    };
+
+    LOCAL_HOSTS.contains(&host.to_ascii_lowercase().as_str())
+        && (port.is_none() || authority_port.is_none() || authority_port == port)
+}
+
+/// Compares tokens in time independent of where they differ.
+fn tokens_match(provided: &str, token: &str) -> bool {
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+impl Access {
+    fn new(port: u16) -> Self {
+        Self {
+            token: rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(TOKEN_LENGTH)
+                .map(char::from)
+                .collect(),
+            port,
+        }
+    }
+
+    /// Checks a request is addressed to this machine, comes from a local page if sent by a
+    /// browser, and carries the session token.
+    /// The Host check defeats DNS rebinding, the Origin check keeps websites from driving the
+    /// server through the user's browser.
+    fn check(
+        &self,
+        host: Option<&str>,
+        origin: Option<&str>,
+        authorization: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<(), (StatusCode, &'static str)> {
+        if !host.is_some_and(|host| is_local_authority(host.trim(), Some(self.port))) {
+            return Err((StatusCode::FORBIDDEN, "Only localhost may be used as host."));
+        }
+
+        // Scripts send no Origin, browsers always do
+        if let Some(origin) = origin {
+            let authority = origin
+                .trim()
+                .split_once("://")
+                .map_or("", |(_, authority)| authority.trim_end_matches('/'));
+            if !is_local_authority(authority, None) {
+                return Err((StatusCode::FORBIDDEN, "Only local pages may connect."));
+            }
+        }
+
+        let bearer = authorization
+            .and_then(|authorization| authorization.trim().strip_prefix("Bearer ").map(str::trim));
+        let query_token = query.and_then(|query| {
+            query.split('&').find_map(|parameter| {
+                parameter
+                    .split_once('=')
+                    .filter(|(key, _)| *key == TOKEN_QUERY_KEY)
+                    .map(|(_, value)| value)
+            })
+        });
+
+        match bearer.or(query_token) {
+            Some(provided) if tokens_match(provided, self.token.as_str()) => Ok(()),
+            _ => Err((
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong live server token.",
+            )),
+        }
+    }
+}
+
+/// Counts a served connection until dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Takes a slot, unless [`MAX_CONNECTIONS`] are being served.
+    fn take(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Handle to a running live server.
+pub struct LiveServer {
+    address: SocketAddr,
+    token: String,
+    running: Arc<AtomicBool>,
+}
+
+impl Drop for LiveServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Messages accepted by the live server.
+/// Over HTTP the message type is given by the path, over WebSocket by the `type` field.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum LiveMessage {
+    /// A whole ManyCore XML system.
+    System { xml: String },
+    /// Attribute updates to apply to the loaded system.
+    Update { updates: UpdateMessage },
+}
+
+#[derive(Serialize)]
+struct LiveReply {
+    status: ResultStatus,
+    message: String,
+}
+
+/// Applies a message to the app state and notifies the frontend.
+fn handle_message(handle: &AppHandle, message: LiveMessage) -> LiveReply {
+    let state = handle.state::<State>();
+
+    // Event payloads must be Clone, go through a Value
+    let (reply, payload) = match message {
+        LiveMessage::System { xml } => {
+            let result = replace_system_xml(&state, &mut state.write(), xml.as_str());
+            (
+                LiveReply {
+                    status: result.status,
+                    message: result.message.clone(),
+                },
+                serde_json::to_value(&result),
+            )
+        }
+        LiveMessage::Update { updates } => {
+            let result = apply_updates(&mut state.write(), &updates.into_updates());
+            (
+                LiveReply {
+                    status: result.status,
+                    message: result.message.clone(),
+                },
+                serde_json::to_value(&result),
+            )
+        }
+    };
+
+    if let Ok(payload) = payload {
+        let _ = handle.emit_all(LIVE_RENDER_EVENT, payload);
+    }
+
+    reply
+}
+
+fn reply_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| String::from("{}"))
+}
+
+fn write_http_response(stream: &mut TcpStream, status: StatusCode, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        status.as_str(),
+        status.canonical_reason().unwrap_or_default(),
+        body.len()
+    );
+}
+
+/// Reads a line of the request head. Lines cut short by the head limit are refused.
+fn read_head_line(head: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    head.read_line(&mut line).map_err(|e| e.to_string())?;
+
+    match line.strip_suffix('\n') {
+        Some(line) => Ok(line.trim_end_matches('\r').to_string()),
+        None => Err(String::from("Incomplete or too long request head.")),
+    }
+}
+
+/// Request line and headers of an HTTP request or WebSocket handshake.
+struct RequestHead {
+    method: String,
+    path: String,
+    query: Option<String>,
+    /// Header names are lowercase.
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    /// Reads the head, up to [`MAX_HEAD_LENGTH`], leaving any body in `reader`.
+    fn read(reader: &mut BufReader<TcpStream>) -> Result<Self, String> {
+        let mut head = reader.by_ref().take(MAX_HEAD_LENGTH);
+
+        let request_line = read_head_line(&mut head)?;
+        let mut request = request_line.split_ascii_whitespace();
+        let method = request.next().unwrap_or_default().to_string();
+        let target = request.next().unwrap_or_default();
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (target.to_string(), None),
+        };
+
+        let mut headers = Vec::new();
+        loop {
+            let header = read_head_line(&mut head)?;
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        Ok(Self {
+            method,
+            path,
+            query,
+            headers,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_websocket(&self) -> bool {
+        self.header("upgrade")
+            .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+    }
+}
+
+/// Serves a single HTTP request.
+/// `POST /system` takes a ManyCore XML body, `POST /update` a JSON [`UpdateMessage`].
+fn serve_http(
+    handle: &AppHandle,
+    head: RequestHead,
+    mut reader: BufReader<TcpStream>,
+    mut stream: TcpStream,
+) -> Result<(), String> {
+    let content_length = match head.header("content-length") {
+        Some(length) => length.parse().map_err(|_| "Invalid length.")?,
+        None => 0,
+    };
+
+    if content_length > MAX_BODY_LENGTH {
+        write_http_response(&mut stream, StatusCode::PAYLOAD_TOO_LARGE, "{}");
+        return Ok(());
+    }
+
+    // Grows with what is actually sent rather than trusting the declared length
+    let mut body = Vec::new();
+    reader
+        .by_ref()
+        .take(content_length as u64)
+        .read_to_end(&mut body)
+        .map_err(|e| e.to_string())?;
+    if body.len() < content_length {
+        return Err(String::from("The request body is incomplete."));
+    }
+    let body = String::from_utf8(body).map_err(|e| e.to_string())?;
+
+    let message = match (head.method.as_str(), head.path.as_str()) {
+        ("GET", "/status") => {
+            write_http_response(&mut stream, StatusCode::OK, "{\"status\":\"ok\"}");
+            return Ok(());
+        }
+        ("POST", "/system") => Ok(LiveMessage::System { xml: body }),
+        ("POST", "/update") => serde_json::from_str::<UpdateMessage>(body.as_str())
+            .map(|updates| LiveMessage::Update { updates })
+            .map_err(|e| e.to_string()),
+        _ => {
+            write_http_response(&mut stream, StatusCode::NOT_FOUND, "{}");
+            return Ok(());
+        }
+    };
+
+    match message {
+        Ok(message) => {
+            let reply = handle_message(handle, message);
+            write_http_response(&mut stream, StatusCode::OK, reply_json(&reply).as_str());
+        }
+        Err(e) => {
+            let reply = LiveReply {
+                status: ResultStatus::Error,
+                message: e,
+            };
+            write_http_response(
+                &mut stream,
+                StatusCode::BAD_REQUEST,
+                reply_json(&reply).as_str(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Completes a WebSocket handshake and serves the connection.
+/// Each text message is a JSON [`LiveMessage`] and gets a JSON reply.
+fn serve_websocket(
+    handle: &AppHandle,
+    head: RequestHead,
+    reader: BufReader<TcpStream>,
+    mut stream: TcpStream,
+) -> Result<(), String> {
+    let key = match head.header("sec-websocket-key") {
+        Some(key) if head.method == "GET" && head.header("sec-websocket-version") == Some("13") => {
+            key
+        }
+        _ => {
+            write_http_response(&mut stream, StatusCode::BAD_REQUEST, "{}");
+            return Ok(());
+        }
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    )
+    .map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(WEBSOCKET_IDLE_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    // Frames the client sent early may already sit in the reader's buffer
+    let mut socket =
+        WebSocket::from_partially_read(stream, reader.buffer().to_vec(), Role::Server, None);
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<LiveMessage>(text.as_str()) {
+                    Ok(message) => handle_message(handle, message),
+                    Err(e) => LiveReply {
+                        status: ResultStatus::Error,
+                        message: e.to_string(),
+                    },
+                };
+
+                socket
+                    .send(Message::Text(reply_json(&reply)))
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            // Pings are answered by tungstenite
+            Ok(_) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Reads the request head once, checks it passes [`Access::check`], then serves the
+/// connection as a WebSocket or as a single HTTP request depending on its `Upgrade` header.
+fn serve_request(handle: &AppHandle, access: &Access, mut stream: TcpStream) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let head = RequestHead::read(&mut reader)?;
+
+    if let Err((status, message)) = access.check(
+        head.header("host"),
+        head.header("origin"),
+        head.header("authorization"),
+        head.query.as_deref(),
+    ) {
+        let reply = LiveReply {
+            status: ResultStatus::Error,
+            message: String::from(message),
+        };
+        write_http_response(&mut stream, status, reply_json(&reply).as_str());
+        return Ok(());
+    }
+
+    if head.is_websocket() {
+        serve_websocket(handle, head, reader, stream)
+    } else {
+        serve_http(handle, head, reader, stream)
+    }
+}
+
+fn serve_connection(
+    handle: AppHandle,
+    access: Arc<Access>,
+    stream: TcpStream,
+    _slot: ConnectionSlot,
+) {
+    // Accepted streams inherit non blocking mode from the listener on some platforms
+    if stream.set_nonblocking(false).is_err()
+        || stream.set_read_timeout(Some(READ_TIMEOUT)).is_err()
+    {
+        return;
+    }
+
+    if let Err(e) = serve_request(&handle, &access, stream) {
+        let _ = handle.emit_all(LIVE_STATUS_EVENT, format!("Live connection error: {e}"));
+    }
+}
+
+fn accept_loop(handle: AppHandle, listener: TcpListener, access: Access, running: Arc<AtomicBool>) {
+    let access = Arc::new(access);
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    while running.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((mut stream, _)) => match ConnectionSlot::take(&connections) {
+                Some(slot) => {
+                    let (handle, access) = (handle.clone(), Arc::clone(&access));
+                    thread::spawn(move || serve_connection(handle, access, stream, slot));
+                }
+                None => {
+                    if stream.set_nonblocking(false).is_ok() {
+                        write_http_response(&mut stream, StatusCode::SERVICE_UNAVAILABLE, "{}");
+                    }
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+            Err(e) => {
+                let _ = handle.emit_all(LIVE_STATUS_EVENT, format!("Live server stopped: {e}"));
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct LiveServerResult {
+    status: ResultStatus,
+    message: String,
+    address: Option<String>,
+    /// Session token clients must present, shown to the user to configure them.
+    token: Option<String>,
+}
+
+/// Starts the live server on localhost. Systems and updates pushed to it are rendered
+/// and announced to the frontend through a `live_render` event.
+/// Clients authenticate with a token generated for each start, see [`Access`].
+#[tauri::command]
+pub fn start_live_server(
+    port: Option<u16>,
+    handle: AppHandle,
+    state: tauri::State<State>,
+) -> LiveServerResult {
+    let mut ret = LiveServerResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        address: None,
+        token: None,
+    };
+
+    if let Ok(mut live_server_mutex) = state.live_server.lock() {
+        if let Some(live_server) = &*live_server_mutex {
+            ret.message = String::from("Live server is already running.");
+            ret.address = Some(live_server.address.to_string());
+            ret.token = Some(live_server.token.clone());
+            return ret;
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port.unwrap_or(DEFAULT_PORT)))
+            .and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .and_then(|listener| Ok((listener.local_addr()?, listener)));
+
+        match listener {
+            Ok((address, listener)) => {
+                let access = Access::new(address.port());
+                let token = access.token.clone();
+                let running = Arc::new(AtomicBool::new(true));
+                let running_clone = Arc::clone(&running);
+                thread::spawn(move || accept_loop(handle, listener, access, running_clone));
+
+                let _ = live_server_mutex.insert(LiveServer {
+                    address,
+                    token: token.clone(),
+                    running,
+                });
+
+                ret.status = ResultStatus::Ok;
+                ret.message =
+                    format!("Live server listening on {address}. Clients must use token {token}.");
+                ret.address = Some(address.to_string());
+                ret.token = Some(token);
+            }
+            Err(e) => ret.message = format!("Could not start live server: {e}"),
+        }
+    }
+
+    ret
+}
+
+/// Stops the live server. Open WebSocket connections are served until they close.
+#[tauri::command]
+pub fn stop_live_server(state: tauri::State<State>) -> ResultStatus {
+    match state.live_server.lock() {
+        Ok(mut live_server_mutex) => {
+            // Dropping the handle stops the accept loop
+            live_server_mutex.take();
+            ResultStatus::Ok
+        }
+        Err(_) => ResultStatus::Error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TOKEN: &'static str = "s3cr3t";
+    static PORT: u16 = 7878;
+
+    fn access() -> Access {
+        Access {
+            token: String::from(TOKEN),
+            port: PORT,
+        }
+    }
+
+    fn status(result: Result<(), (StatusCode, &'static str)>) -> Option<StatusCode> {
+        result.err().map(|(status, _)| status)
+    }
+
+    #[test]
+    fn accepts_token_as_bearer_or_query() {
+        let access = access();
+
+        assert!(access
+            .check(Some("localhost:7878"), None, Some("Bearer s3cr3t"), None)
+            .is_ok());
+        assert!(access
+            .check(Some("127.0.0.1:7878"), None, None, Some("a=1&token=s3cr3t"))
+            .is_ok());
+    }
+
+    #[test]
+    fn refuses_wrong_or_missing_token() {
+        let access = access();
+
+        assert_eq!(
+            status(access.check(Some("localhost:7878"), None, None, None)),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(access.check(Some("localhost:7878"), None, Some("Bearer s3cr3"), None)),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(access.check(Some("localhost:7878"), None, Some("s3cr3t"), None)),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            status(access.check(Some("localhost:7878"), None, None, Some("token=S3CR3T"))),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn refuses_foreign_host() {
+        let access = access();
+
+        // A rebound DNS name still carries the attacker's host
+        assert_eq!(
+            status(access.check(
+                Some("attacker.example:7878"),
+                None,
+                Some("Bearer s3cr3t"),
+                None
+            )),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            status(access.check(Some("localhost:8080"), None, Some("Bearer s3cr3t"), None)),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            status(access.check(None, None, Some("Bearer s3cr3t"), None)),
+            Some(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn refuses_foreign_origin() {
+        let access = access();
+        let check = |origin| {
+            access.check(
+                Some("localhost:7878"),
+                Some(origin),
+                Some("Bearer s3cr3t"),
+                None,
+            )
+        };
+
+        assert_eq!(
+            status(check("https://attacker.example")),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            status(check("http://localhost.attacker.example")),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(status(check("null")), Some(StatusCode::FORBIDDEN));
+        assert!(check("https://tauri.localhost").is_ok());
+        assert!(check("http://localhost:1420/").is_ok());
+    }
+
+    #[test]
+    fn local_authorities() {
+        assert!(is_local_authority("localhost", Some(PORT)));
+        assert!(is_local_authority("LOCALHOST:7878", Some(PORT)));
+        assert!(is_local_authority("[::1]", Some(PORT)));
+        assert!(is_local_authority("[::1]:7878", Some(PORT)));
+        assert!(is_local_authority("127.0.0.1:1420", None));
+
+        assert!(!is_local_authority("[::1]:8080", Some(PORT)));
+        assert!(!is_local_authority("[::1", None));
+        assert!(!is_local_authority("[::2]:7878", Some(PORT)));
+        assert!(!is_local_authority("::1", None));
+        assert!(!is_local_authority("localhost:", Some(PORT)));
+        assert!(!is_local_authority("localhost:port", Some(PORT)));
+        assert!(!is_local_authority("localhost:7878:7878", Some(PORT)));
+        assert!(!is_local_authority("127.0.0.2:7878", Some(PORT)));
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    result_status::ResultStatus,
+    routing::{route_task_graph, RoutingAlgorithm},
+    system::{AttributeUpdate, ElementId, SystemSnapshot, TaskEdge, ALLOCATED_TASK_ATTRIBUTE},
+    topology::{Channel, Topology},
+    update::{apply_updates, UpdateApplyResult},
+    State,
+};
+
+// Simulated annealing defaults
+static ITERATIONS_PER_TASK: usize = 2000;
+static MAX_ITERATIONS: usize = 2_000_000;
+/// Fraction of the initial temperature left at the last iteration.
+static FINAL_TEMPERATURE_RATIO: f64 = 1e-3;
+
+/// Core each task is allocated to.
+pub(crate) type Mapping = BTreeMap<u16, usize>;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MappingHeuristic {
+    /// Tasks on random cores.
+    #[serde(rename_all = "camelCase")]
+    Random { seed: Option<u64> },
+    /// Tasks on cores in id order, row by row.
+    RowMajor,
+    /// Greedily place the most communicating tasks next to each other.
+    NearestNeighbour,
+    /// Improve the current mapping by swapping and moving tasks.
+    #[serde(rename_all = "camelCase")]
+    SimulatedAnnealing {
+        seed: Option<u64>,
+        iterations: Option<usize>,
+        initial_temperature: Option<f64>,
+    },
+}
+
+/// Task graph adjacency, with costs of edges in both directions summed up.
+struct Neighbours(BTreeMap<u16, Vec<(u16, u64)>>);
+
+impl Neighbours {
+    fn new(edges: &[TaskEdge]) -> Self {
+        let mut combined: BTreeMap<(u16, u16), u64> = BTreeMap::new();
+        for edge in edges.iter().filter(|edge| edge.from != edge.to) {
+            let key = (edge.from.min(edge.to), edge.from.max(edge.to));
+            *combined.entry(key).or_default() += edge.communication_cost;
+        }
+
+        let mut ret: BTreeMap<u16, Vec<(u16, u64)>> = BTreeMap::new();
+        for ((a, b), cost) in combined {
+            ret.entry(a).or_default().push((b, cost));
+            ret.entry(b).or_default().push((a, cost));
+        }
+
+        Self(ret)
+    }
+
+    fn of(&self, task: u16) -> &[(u16, u64)] {
+        self.0.get(&task).map_or(&[], Vec::as_slice)
+    }
+
+    fn volume(&self, task: u16) -> u64 {
+        self.of(task).iter().map(|(_, cost)| cost).sum()
+    }
+
+    /// Cost of the edges of `task` if placed on `core`.
+    fn task_cost(
+        &self,
+        topology: &Topology,
+        placement: &[Option<usize>],
+        task: u16,
+        core: usize,
+    ) -> u64 {
+        self.of(task)
+            .iter()
+            .filter_map(|(neighbour, cost)| {
+                placement[usize::from(*neighbour)]
+                    .map(|other| cost * topology.distance(core, other) as u64)
+            })
+            .sum()
+    }
+}
+
+/// Total communication cost: every edge's cost times the hops between its tasks.
+pub(crate) fn communication_cost(
+    topology: &Topology,
+    edges: &[TaskEdge],
+    mapping: &Mapping,
+) -> u64 {
+    edges
+        .iter()
+        .filter_map(|edge| {
+            let (from, to) = (mapping.get(&edge.from)?, mapping.get(&edge.to)?);
+            Some(edge.communication_cost * topology.distance(*from, *to) as u64)
+        })
+        .sum()
+}
+
+fn random(task_ids: &[u16], core_count: usize, seed: u64) -> Mapping {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut cores = (0..core_count).collect::<Vec<usize>>();
+    cores.shuffle(&mut rng);
+
+    task_ids.iter().copied().zip(cores).collect()
+}
+
+fn row_major(task_ids: &[u16]) -> Mapping {
+    task_ids
+        .iter()
+        .enumerate()
+        .map(|(core, task)| (*task, core))
+        .collect()
+}
+
+/// Places the task with the highest communication volume in the middle of the mesh, then
+/// repeatedly places the task talking most to placed tasks on the cheapest free core.
+fn nearest_neighbour(topology: &Topology, task_ids: &[u16], neighbours: &Neighbours) -> Mapping {
+    let max_task = task_ids.iter().max().map_or(0, |task| usize::from(*task));
+    let mut placement: Vec<Option<usize>> = vec![None; max_task + 1];
+    let mut free = (0..topology.core_count()).collect::<BTreeSet<usize>>();
+    let mut unplaced = task_ids.iter().copied().collect::<BTreeSet<u16>>();
+    let centre = topology.core_at(topology.rows / 2, topology.columns / 2);
+
+    while !unplaced.is_empty() {
+        // Communication with placed tasks, then overall volume
+        let Some(task) = unplaced.iter().copied().max_by_key(|task| {
+            let attached: u64 = neighbours
+                .of(*task)
+                .iter()
+                .filter(|(neighbour, _)| placement[usize::from(*neighbour)].is_some())
+                .map(|(_, cost)| cost)
+                .sum();
+
+            (attached, neighbours.volume(*task), std::cmp::Reverse(*task))
+        }) else {
+            break;
+        };
+
+        let Some(core) = free.iter().copied().min_by_key(|core| {
+            (
+                neighbours.task_cost(topology, &placement, task, *core),
+                topology.distance(*core, centre),
+                *core,
+            )
+        }) else {
+            break;
+        };
+
+        placement[usize::from(task)] = Some(core);
+        free.remove(&core);
+        unplaced.remove(&task);
+    }
+
+    task_ids
+        .iter()
+        .filter_map(|task| Some((*task, placement[usize::from(*task)]?)))
+        .collect()
+}
+
+/// Simulated annealing over task moves and swaps, starting from `initial`.
+fn simulated_annealing(
+    topology: &Topology,
+    neighbours: &Neighbours,
+    initial: Mapping,
+    seed: u64,
+    iterations: usize,
+    initial_temperature: f64,
+) -> Mapping {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let task_ids = initial.keys().copied().collect::<Vec<u16>>();
+    let max_task = task_ids.iter().max().map_or(0, |task| usize::from(*task));
+    let core_count = topology.core_count();
+
+    if task_ids.is_empty() || core_count < 2 {
+        return initial;
+    }
+
+    let mut placement: Vec<Option<usize>> = vec![None; max_task + 1];
+    let mut occupant: Vec<Option<u16>> = vec![None; core_count];
+    for (task, core) in &initial {
+        placement[usize::from(*task)] = Some(*core);
+        occupant[*core] = Some(*task);
+    }
+
+    // Every edge is seen from both ends
+    let mut cost = task_ids
+        .iter()
+        .filter_map(|task| {
+            let core = placement[usize::from(*task)]?;
+            Some(neighbours.task_cost(topology, &placement, *task, core) as i64)
+        })
+        .sum::<i64>()
+        / 2;
+    let mut best = (cost, placement.clone());
+    let mut temperature = initial_temperature.max(f64::EPSILON);
+    let cooling = FINAL_TEMPERATURE_RATIO.powf(1.0 / iterations.max(1) as f64);
+
+    for _ in 0..iterations {
+        let task = task_ids[rng.gen_range(0..task_ids.len())];
+        let Some(from) = placement[usize::from(task)] else {
+            continue;
+        };
+        let to = rng.gen_range(0..core_count);
+        if to == from {
+            continue;
+        }
+        let other = occupant[to];
+
+        // Only edges of the moved tasks change
+        let before = neighbours.task_cost(topology, &placement, task, from) as i64
+            + other.map_or(0, |other| {
+                neighbours.task_cost(topology, &placement, other, to) as i64
+            });
+
+        placement[usize::from(task)] = Some(to);
+        if let Some(other) = other {
+            placement[usize::from(other)] = Some(from);
+        }
+
+        let after = neighbours.task_cost(topology, &placement, task, to) as i64
+            + other.map_or(0, |other| {
+                neighbours.task_cost(topology, &placement, other, from) as i64
+            });
+
+        // A swapped pair's shared edge is counted twice on both sides, so it cancels out
+        let delta = after - before;
+
+        if delta <= 0 || rng.gen::<f64>() < (-(delta as f64) / temperature).exp() {
+            occupant[from] = other;
+            occupant[to] = Some(task);
+            cost += delta;
+
+            if cost < best.0 {
+                best = (cost, placement.clone());
+            }
+        } else {
+            placement[usize::from(task)] = Some(from);
+            if let Some(other) = other {
+                placement[usize::from(other)] = Some(to);
+            }
+        }
+
+        temperature *= cooling;
+    }
+
+    task_ids
+        .iter()
+        .filter_map(|task| Some((*task, best.1[usize::from(*task)]?)))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingCost {
+    /// Sum of every edge's communication cost times its hop count.
+    total_cost: u64,
+    /// Highest summed communication cost through a single channel.
+    max_channel_load: u64,
+    /// Channels carrying traffic.
+    used_channels: usize,
+    /// Edges whose tasks are both allocated.
+    routed_edges: usize,
+}
+
+impl MappingCost {
+    fn new(
+        snapshot: &SystemSnapshot,
+        topology: &Topology,
+        algorithm: RoutingAlgorithm,
+        mapping: &Mapping,
+    ) -> Result<Self, String> {
+        let mut mapped = snapshot.clone();
+        for core in mapped.cores.iter_mut() {
+            core.allocated_task = None;
+        }
+        for (task, core) in mapping {
+            if let Some(core) = mapped.cores.get_mut(*core) {
+                core.allocated_task = Some(*task);
+            }
+        }
+
+        let flows = route_task_graph(&mapped, topology, algorithm)?;
+        let mut loads: BTreeMap<Channel, u64> = BTreeMap::new();
+        for flow in &flows {
+            for hop in &flow.hops {
+                *loads.entry(hop.channel).or_default() += flow.edge.communication_cost;
+            }
+        }
+
+        Ok(Self {
+            total_cost: communication_cost(topology, &snapshot.edges, mapping),
+            max_channel_load: loads.values().copied().max().unwrap_or_default(),
+            used_channels: loads.len(),
+            routed_edges: flows.len(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct MappingReport {
+    before: MappingCost,
+    after: MappingCost,
+    /// Core of each task.
+    mapping: Mapping,
+}
+
+#[derive(Serialize)]
+pub struct MappingResult {
+    status: ResultStatus,
+    message: String,
+    report: Option<MappingReport>,
+    /// Render of the remapped system.
+    render: Option<UpdateApplyResult>,
+}
+
+/// Computes a new mapping and the allocation updates needed to apply it.
+fn remap(
+    snapshot: &SystemSnapshot,
+    heuristic: MappingHeuristic,
+) -> Result<(MappingReport, Vec<AttributeUpdate>), String> {
+    let topology = Topology::from_snapshot(snapshot);
+    // Loads are reported for the system's algorithm, dimension order otherwise
+    let algorithm = snapshot
+        .routing_algorithm
+        .as_deref()
+        .and_then(|algorithm| algorithm.parse().ok())
+        .unwrap_or(RoutingAlgorithm::RowFirst);
+
+    let current = snapshot
+        .cores
+        .iter()
+        .filter_map(|core| Some((core.allocated_task?, core.id)))
+        .collect::<Mapping>();
+
+    let mut task_ids = snapshot.tasks.clone();
+    task_ids.extend(snapshot.edges.iter().flat_map(|edge| [edge.from, edge.to]));
+    task_ids.extend(current.keys());
+    task_ids.sort();
+    task_ids.dedup();
+
+    if task_ids.len() > topology.core_count() {
+        return Err(format!(
+            "{} tasks do not fit in {} cores.",
+            task_ids.len(),
+            topology.core_count()
+        ));
+    }
+
+    let neighbours = Neighbours::new(&snapshot.edges);
+    let random_seed = || rand::thread_rng().gen();
+
+    let mapping = match heuristic {
+        MappingHeuristic::Random { seed } => random(
+            &task_ids,
+            topology.core_count(),
+            seed.unwrap_or_else(random_seed),
+        ),
+        MappingHeuristic::RowMajor => row_major(&task_ids),
+        MappingHeuristic::NearestNeighbour => nearest_neighbour(&topology, &task_ids, &neighbours),
+        MappingHeuristic::SimulatedAnnealing {
+            seed,
+            iterations,
+            initial_temperature,
+        } => {
+            // Start from the current mapping if complete, it might have been tuned by hand
+            let initial = if task_ids.iter().all(|task| current.contains_key(task)) {
+                current.clone()
+            } else {
+                nearest_neighbour(&topology, &task_ids, &neighbours)
+            };
+            // Accept an average edge getting a hop longer with good probability at the start
+            let mean_cost = snapshot
+                .edges
+                .iter()
+                .map(|edge| edge.communication_cost as f64)
+                .sum::<f64>()
+                / snapshot.edges.len().max(1) as f64;
+
+            simulated_annealing(
+                &topology,
+                &neighbours,
+                initial,
+                seed.unwrap_or_else(random_seed),
+                iterations
+                    .unwrap_or(task_ids.len().saturating_mul(ITERATIONS_PER_TASK))
+                    .min(MAX_ITERATIONS),
+                initial_temperature.unwrap_or(mean_cost.max(1.0)),
+            )
+        }
+    };
+
+    let previous_cores = current
+        .iter()
+        .map(|(task, core)| (*core, *task))
+        .collect::<BTreeMap<usize, u16>>();
+    let new_cores = mapping
+        .iter()
+        .map(|(task, core)| (*core, *task))
+        .collect::<BTreeMap<usize, u16>>();
+
+    let updates = snapshot
+        .cores
+        .iter()
+        .filter(|core| previous_cores.get(&core.id) != new_cores.get(&core.id))
+        .map(|core| AttributeUpdate {
+            element: ElementId::Core(core.id),
+            attribute: String::from(ALLOCATED_TASK_ATTRIBUTE),
+            value: new_cores.get(&core.id).map(u16::to_string),
+        })
+        .collect();
+
+    Ok((
+        MappingReport {
+            before: MappingCost::new(snapshot, &topology, algorithm, &current)?,
+            after: MappingCost::new(snapshot, &topology, algorithm, &mapping)?,
+            mapping,
+        },
+        updates,
+    ))
+}
+
+/// Re-maps the tasks of the loaded system with the given heuristic and re-renders it.
+/// Routing loads are recomputed from the new mapping.
+#[tauri::command]
+pub fn remap_tasks(heuristic: MappingHeuristic, state: tauri::State<State>) -> MappingResult {
+    let mut ret = MappingResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        report: None,
+        render: None,
+    };
+
+    let mut document = state.write();
+    let remapped = match &document.snapshot {
+        Some(snapshot) => remap(snapshot, heuristic),
+        None => Err(String::from("Load a system before mapping tasks.")),
+    };
+
+    match remapped {
+        Ok((report, updates)) => {
+            let render = if updates.is_empty() {
+                None
+            } else {
+                Some(apply_updates(&mut document, &updates))
+            };
+
+            match &render {
+                Some(render) if matches!(render.status, ResultStatus::Error) => {
+                    ret.message = render.message.clone();
+                }
+                _ => {
+                    ret.status = ResultStatus::Ok;
+                    ret.message = format!(
+                        "Communication cost went from {} to {}.",
+                        report.before.total_cost, report.after.total_cost
+                    );
+                    ret.report = Some(report);
+                }
+            }
+            ret.render = render;
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
@@ -0,0 +1,391 @@
+use manycore_svg::{BaseConfiguration, Configuration};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{export::WholeConfiguration, presets::ExportPreset};
+
+static VERSION_KEY: &'static str = "version";
+// Sections of a configuration file as serialised by serde
+static BASE_CONFIGURATION_KEY: &'static str = "baseConfiguration";
+static CONFIGURATION_KEY: &'static str = "configuration";
+static FONT_FAMILY_KEY: &'static str = "fontFamily";
+static EXPORT_PRESETS_KEY: &'static str = "exportPresets";
+static CONFIGURATION_GROUPS: &[&str] = &[
+    "coreConfig",
+    "routerConfig",
+    "channelConfig",
+    "coreFills",
+    "routerFills",
+];
+
+/// Version of unversioned files, the layout the first versioned build wrote as well.
+pub(crate) const BASELINE_VERSION: u32 = 1;
+
+/// Upgrades a configuration file by one version: the migration at index `n` turns a version
+/// `BASELINE_VERSION + n` file into a version `BASELINE_VERSION + n + 1` one.
+const MIGRATIONS: [fn(&mut Map<String, Value>); 0] = [];
+
+/// Version of the configuration files written by this build.
+pub(crate) const CONFIGURATION_VERSION: u32 = BASELINE_VERSION + MIGRATIONS.len() as u32;
+
+/// Version of files that don't say, for serde.
+pub(crate) fn baseline_version() -> u32 {
+    BASELINE_VERSION
+}
+
+/// What loading a configuration file changed to make it fit this build.
+#[derive(Default)]
+pub(crate) struct LoadReport {
+    /// Version the file was written with.
+    pub from_version: u32,
+    /// Options that were not understood, by path in the file.
+    pub dropped: Vec<String>,
+    /// Options that were missing, by path in the file.
+    pub defaulted: Vec<String>,
+}
+
+impl LoadReport {
+    /// Whether the file was loaded as is.
+    pub fn is_clean(&self) -> bool {
+        self.from_version == CONFIGURATION_VERSION
+            && self.dropped.is_empty()
+            && self.defaulted.is_empty()
+    }
+
+    pub fn message(&self) -> String {
+        let mut notes = Vec::new();
+
+        if self.from_version > CONFIGURATION_VERSION {
+            notes.push(String::from(
+                "The file was saved by a newer version, options this version doesn't know were dropped.",
+            ));
+        } else if self.from_version < CONFIGURATION_VERSION {
+            notes.push(format!(
+                "Upgraded from version {} to version {CONFIGURATION_VERSION}.",
+                self.from_version
+            ));
+        }
+        if !self.dropped.is_empty() {
+            notes.push(format!("Dropped: {}.", self.dropped.join(", ")));
+        }
+        if !self.defaulted.is_empty() {
+            notes.push(format!("Defaulted: {}.", self.defaulted.join(", ")));
+        }
+
+        format!("Configuration loaded. {}", notes.join(" "))
+    }
+}
+
+/// Entries of `group` in a section, or the section itself if it has no groups.
+fn entries_of<'a>(
+    section: &'a mut Map<String, Value>,
+    group: &Option<String>,
+) -> &'a mut Map<String, Value> {
+    match group {
+        Some(group) => section
+            .entry(group.as_str())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .expect("Groups are objects"),
+        None => section,
+    }
+}
+
+/// Deserialises an object section of the file, keeping as many of its entries as possible.
+/// With `groups`, the section is an object of objects and entries are taken from those.
+/// Entries are added one at a time, each is dropped if `T` rejects it.
+fn section<T: DeserializeOwned>(
+    value: Option<Value>,
+    name: &str,
+    groups: &[&str],
+    report: &mut LoadReport,
+) -> Result<T, String> {
+    let entries = match value {
+        Some(Value::Object(entries)) => entries,
+        Some(_) => {
+            report.dropped.push(String::from(name));
+            report.defaulted.push(String::from(name));
+            Map::new()
+        }
+        None => {
+            report.defaulted.push(String::from(name));
+            Map::new()
+        }
+    };
+
+    // (group, key, value) of every entry, applied on top of an empty section
+    let mut skeleton = Map::new();
+    let mut candidates = Vec::new();
+    if groups.is_empty() {
+        candidates.extend(entries.into_iter().map(|(key, value)| (None, key, value)));
+    } else {
+        for group in groups {
+            skeleton.insert(group.to_string(), Value::Object(Map::new()));
+        }
+
+        let mut found = Vec::new();
+        for (group, value) in entries {
+            match (groups.contains(&group.as_str()), value) {
+                (true, Value::Object(group_entries)) => {
+                    found.push(group.clone());
+                    candidates.extend(
+                        group_entries
+                            .into_iter()
+                            .map(|(key, value)| (Some(group.clone()), key, value)),
+                    );
+                }
+                _ => report.dropped.push(format!("{name}.{group}")),
+            }
+        }
+
+        for group in groups
+            .iter()
+            .filter(|group| !found.iter().any(|f| f == *group))
+        {
+            report.defaulted.push(format!("{name}.{group}"));
+        }
+    }
+
+    // Most files are fine, try them whole first
+    let mut whole = skeleton.clone();
+    for (group, key, value) in &candidates {
+        entries_of(&mut whole, group).insert(key.clone(), value.clone());
+    }
+    if let Ok(section) = serde_json::from_value::<T>(Value::Object(whole.clone())) {
+        return Ok(section);
+    }
+
+    // Entries can only be checked one by one if the empty section is valid
+    if serde_json::from_value::<T>(Value::Object(skeleton.clone())).is_err() {
+        return serde_json::from_value::<T>(Value::Object(whole))
+            .map_err(|e| format!("Could not read {name}: {e}"));
+    }
+
+    for (group, key, value) in candidates {
+        entries_of(&mut skeleton, &group).insert(key.clone(), value);
+
+        if serde_json::from_value::<T>(Value::Object(skeleton.clone())).is_err() {
+            entries_of(&mut skeleton, &group).remove(key.as_str());
+            report.dropped.push(match group {
+                Some(group) => format!("{name}.{group}.{key}"),
+                None => format!("{name}.{key}"),
+            });
+        }
+    }
+
+    serde_json::from_value::<T>(Value::Object(skeleton))
+        .map_err(|e| format!("Could not read {name}: {e}"))
+}
+
+/// Reads a configuration file of any version, upgrading it to the current one.
+/// Options that can't be read are dropped and missing ones defaulted rather than refusing the
+/// file, the returned report lists them.
+pub(crate) fn load(content: &str) -> Result<(WholeConfiguration, LoadReport), String> {
+    let Value::Object(mut file) = serde_json::from_str::<Value>(content)
+        .map_err(|e| format!("Could not read configuration: {e}"))?
+    else {
+        return Err(String::from("The file is not a configuration."));
+    };
+
+    let mut report = LoadReport {
+        from_version: BASELINE_VERSION,
+        ..Default::default()
+    };
+    match file.remove(VERSION_KEY) {
+        None => {}
+        Some(version) => match version.as_u64().and_then(|v| u32::try_from(v).ok()) {
+            Some(version) => report.from_version = version,
+            None => report.dropped.push(String::from(VERSION_KEY)),
+        },
+    }
+
+    let applied = report.from_version.saturating_sub(BASELINE_VERSION);
+    for migration in MIGRATIONS.iter().skip(applied as usize) {
+        migration(&mut file);
+    }
+
+    let base_configuration = section::<BaseConfiguration>(
+        file.remove(BASE_CONFIGURATION_KEY),
+        BASE_CONFIGURATION_KEY,
+        &[],
+        &mut report,
+    )?;
+    let configuration = section::<Configuration>(
+        file.remove(CONFIGURATION_KEY),
+        CONFIGURATION_KEY,
+        CONFIGURATION_GROUPS,
+        &mut report,
+    )?;
+
+    let font_family = match file.remove(FONT_FAMILY_KEY) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(font_family)) => Some(font_family),
+        Some(_) => {
+            report.dropped.push(String::from(FONT_FAMILY_KEY));
+            None
+        }
+    };
+
+    let export_presets = match file.remove(EXPORT_PRESETS_KEY) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(presets)) => presets
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, preset)| {
+                let name = preset
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map_or(format!("#{}", index + 1), |name| format!("\"{name}\""));
+
+                match serde_json::from_value::<ExportPreset>(preset) {
+                    Ok(preset) => Some(preset),
+                    Err(_) => {
+                        report.dropped.push(format!("{EXPORT_PRESETS_KEY} {name}"));
+                        None
+                    }
+                }
+            })
+            .collect(),
+        Some(_) => {
+            report.dropped.push(String::from(EXPORT_PRESETS_KEY));
+            Vec::new()
+        }
+    };
+
+    report.dropped.extend(file.into_iter().map(|(key, _)| key));
+
+    Ok((
+        WholeConfiguration {
+            version: CONFIGURATION_VERSION,
+            base_configuration,
+            configuration,
+            font_family,
+            export_presets,
+        },
+        report,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_svg::CONFIGURABLE_BASE_CONFIGURATION;
+    use serde_json::json;
+
+    use super::*;
+
+    /// A base configuration with every option at its default.
+    fn base_configuration() -> Value {
+        let Value::Object(options) = serde_json::to_value(CONFIGURABLE_BASE_CONFIGURATION).unwrap()
+        else {
+            panic!("The configurable base configuration is not an object");
+        };
+
+        Value::Object(
+            options
+                .into_iter()
+                .map(|(key, option)| (key, option["default"].clone()))
+                .collect(),
+        )
+    }
+
+    fn file(version: Option<Value>) -> Map<String, Value> {
+        let mut file = Map::new();
+        file.insert(String::from(BASE_CONFIGURATION_KEY), base_configuration());
+        file.insert(
+            String::from(CONFIGURATION_KEY),
+            Value::Object(
+                CONFIGURATION_GROUPS
+                    .iter()
+                    .map(|group| (group.to_string(), json!({})))
+                    .collect(),
+            ),
+        );
+        if let Some(version) = version {
+            file.insert(String::from(VERSION_KEY), version);
+        }
+
+        file
+    }
+
+    fn load_file(file: Map<String, Value>) -> Result<(WholeConfiguration, LoadReport), String> {
+        load(Value::Object(file).to_string().as_str())
+    }
+
+    #[test]
+    fn unversioned_files_are_the_baseline() {
+        let (whole_configuration, report) = load_file(file(None)).unwrap();
+
+        assert_eq!(report.from_version, BASELINE_VERSION);
+        assert!(report.is_clean());
+        assert_eq!(whole_configuration.version, CONFIGURATION_VERSION);
+    }
+
+    #[test]
+    fn current_files_load_clean() {
+        let (_, report) = load_file(file(Some(json!(CONFIGURATION_VERSION)))).unwrap();
+
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn newer_files_are_reported() {
+        let (_, report) = load_file(file(Some(json!(CONFIGURATION_VERSION + 1)))).unwrap();
+
+        assert!(!report.is_clean());
+        assert!(report.message().contains("newer version"));
+    }
+
+    #[test]
+    fn unreadable_versions_are_dropped() {
+        let (_, report) = load_file(file(Some(json!("two")))).unwrap();
+
+        assert_eq!(report.from_version, BASELINE_VERSION);
+        assert_eq!(report.dropped, vec![VERSION_KEY]);
+    }
+
+    #[test]
+    fn unknown_options_are_dropped() {
+        let mut file = file(None);
+        file.insert(String::from("colourScheme"), json!("dark"));
+        file[CONFIGURATION_KEY]["coreConfig"]["@id"] = json!(42);
+        file[CONFIGURATION_KEY]["unknownConfig"] = json!({});
+        file.insert(String::from(EXPORT_PRESETS_KEY), json!([42]));
+
+        let (whole_configuration, report) = load_file(file).unwrap();
+
+        assert!(whole_configuration.configuration.core_config().is_empty());
+        assert!(whole_configuration.export_presets.is_empty());
+        for dropped in [
+            "colourScheme",
+            "configuration.coreConfig.@id",
+            "configuration.unknownConfig",
+            "exportPresets #1",
+        ] {
+            assert!(
+                report.dropped.iter().any(|path| path == dropped),
+                "{dropped} was not reported in {:?}",
+                report.dropped
+            );
+        }
+    }
+
+    #[test]
+    fn missing_sections_are_defaulted() {
+        let mut file = file(None);
+        file.remove(CONFIGURATION_KEY);
+
+        let (_, report) = load_file(file).unwrap();
+
+        assert!(report
+            .defaulted
+            .iter()
+            .any(|path| path == CONFIGURATION_KEY));
+        assert!(report.message().contains("Defaulted"));
+    }
+
+    #[test]
+    fn only_objects_are_configurations() {
+        assert!(load("[]").is_err());
+        assert!(load("not json").is_err());
+    }
+}
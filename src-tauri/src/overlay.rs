@@ -0,0 +1,694 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use resvg::usvg::{fontdb::Database, Node, Options, Tree};
+use serde::{Deserialize, Serialize};
+
+use crate::topology::{Channel, Direction, Topology, TopologyKind};
+
+static VIEW_BOX_ATTRIBUTE: &'static str = "viewBox=\"";
+static WIDTH_ATTRIBUTE: &'static str = "width=\"";
+static HEIGHT_ATTRIBUTE: &'static str = "height=\"";
+static SVG_OPENING_TAG: &'static str = "<svg";
+static SVG_CLOSING_TAG: &'static str = "</svg>";
+static DEFAULT_DECORATION_GAP: f32 = 10.0;
+static TOPOLOGY_COLOUR: &'static str = "#1565c0";
+/// Group of the render holding the core and router drawings.
+static PROCESSING_GROUP: &'static str = "processingGroup";
+
+/// Maps cores to SVG user space coordinates.
+/// Measured from the core and router drawings of the freshly generated render, which are laid
+/// out on a regular grid.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GridGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// Core drawing relative to the top left corner of its cell, as (x, y, width, height).
+    core_box: (f32, f32, f32, f32),
+    /// Router drawing relative to the top left corner of its cell, as (x, y, width, height).
+    router_box: (f32, f32, f32, f32),
+}
+
+impl GridGeometry {
+    /// Locates the cores of a serialised render laid out on a `rows` x `columns` grid.
+    /// Falls back to splitting the root viewBox if the cores can't be found in the render.
+    pub fn from_svg_str(svg_str: &str, rows: usize, columns: usize) -> Option<Self> {
+        if rows == 0 || columns == 0 {
+            return None;
+        }
+
+        Self::from_drawings(svg_str, rows, columns).or_else(|| {
+            let (_, [x, y, width, height]) = root_view_box(svg_str)?;
+            let (cell_width, cell_height) = (width / columns as f32, height / rows as f32);
+
+            Some(Self {
+                x,
+                y,
+                cell_width,
+                cell_height,
+                core_box: (
+                    cell_width * 0.1,
+                    cell_height * 0.1,
+                    cell_width * 0.5,
+                    cell_height * 0.5,
+                ),
+                router_box: (
+                    cell_width * 0.6,
+                    cell_height * 0.6,
+                    cell_width * 0.3,
+                    cell_height * 0.3,
+                ),
+            })
+        })
+    }
+
+    /// Measures the processing group of a render: a group per core, in id order, holding the
+    /// core and the router drawings. Text is not laid out, no fonts are needed.
+    fn from_drawings(svg_str: &str, rows: usize, columns: usize) -> Option<Self> {
+        let tree = Tree::from_str(svg_str, &Options::default(), &Database::new()).ok()?;
+        let Node::Group(processing_group) = tree.node_by_id(PROCESSING_GROUP)? else {
+            return None;
+        };
+
+        let cells = processing_group
+            .children()
+            .iter()
+            .filter_map(|node| match node {
+                Node::Group(group) => Some((node.abs_bounding_box(), group.children())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if cells.len() != rows * columns {
+            return None;
+        }
+
+        let (first, drawings) = cells[0];
+        let cell_width = match cells.get(1) {
+            Some((second, _)) if columns > 1 => second.x() - first.x(),
+            _ => first.width(),
+        };
+        let cell_height = match cells.get(columns) {
+            Some((below, _)) if rows > 1 => below.y() - first.y(),
+            _ => first.height(),
+        };
+        if !(cell_width > 0.0 && cell_height > 0.0) {
+            return None;
+        }
+
+        // The core is the larger drawing
+        let mut boxes = drawings
+            .iter()
+            .map(|drawing| {
+                let rect = drawing.abs_bounding_box();
+                (
+                    rect.x() - first.x(),
+                    rect.y() - first.y(),
+                    rect.width(),
+                    rect.height(),
+                )
+            })
+            .collect::<Vec<_>>();
+        boxes.sort_by(|(_, _, w1, h1), (_, _, w2, h2)| (w2 * h2).total_cmp(&(w1 * h1)));
+        let core_box = *boxes.first()?;
+        let router_box = *boxes.get(1).unwrap_or(&core_box);
+
+        Some(Self {
+            x: first.x(),
+            y: first.y(),
+            cell_width,
+            cell_height,
+            core_box,
+            router_box,
+        })
+    }
+
+    /// Top left corner of a core's cell.
+    pub fn origin(&self, topology: &Topology, core: usize) -> (f32, f32) {
+        let (row, column) = topology.position(core);
+
+        (
+            self.x + column as f32 * self.cell_width,
+            self.y + row as f32 * self.cell_height,
+        )
+    }
+
+    /// Font size for overlay annotations.
+    pub fn font_size(&self) -> f32 {
+        self.cell_height * 0.12
+    }
+
+    /// Centre of a core.
+    pub fn centre(&self, topology: &Topology, core: usize) -> (f32, f32) {
+        let (row, column) = topology.position(core);
+
+        (
+            self.x + (column as f32 + 0.5) * self.cell_width,
+            self.y + (row as f32 + 0.5) * self.cell_height,
+        )
+    }
+
+    /// Area covered by a core's drawing, as (x, y, width, height).
+    pub fn core_rect(&self, topology: &Topology, core: usize) -> (f32, f32, f32, f32) {
+        let (x, y) = self.origin(topology, core);
+        let (dx, dy, width, height) = self.core_box;
+
+        (x + dx, y + dy, width, height)
+    }
+
+    /// Centre and radius of a router's drawing.
+    pub fn router_circle(&self, topology: &Topology, core: usize) -> (f32, f32, f32) {
+        let (x, y) = self.origin(topology, core);
+        let (dx, dy, width, height) = self.router_box;
+
+        (
+            x + dx + width / 2.0,
+            y + dy + height / 2.0,
+            width.min(height) / 2.0,
+        )
+    }
+
+    /// Empty band between a row of cells and the row below it, as (y, height). Only channels
+    /// between the two rows are drawn there.
+    pub fn row_gap(&self, row: usize) -> Option<(f32, f32)> {
+        let (_, core_y, _, core_height) = self.core_box;
+        let (_, router_y, _, router_height) = self.router_box;
+        let bottom = (core_y + core_height).max(router_y + router_height);
+        let top = core_y.min(router_y);
+
+        let height = self.cell_height - bottom + top;
+        (height > 0.0).then_some((self.y + row as f32 * self.cell_height + bottom, height))
+    }
+
+    /// Centre and radius of the marker of a vertical channel.
+    /// Channels to the layer above are drawn above channels to the layer below.
+    pub fn tsv_marker(
+        &self,
+        topology: &Topology,
+        core: usize,
+        direction: Direction,
+    ) -> (f32, f32, f32) {
+        let (x, y) = self.origin(topology, core);
+        let row = match direction {
+            Direction::Down => 0.35,
+            _ => 0.15,
+        };
+
+        (
+            x + self.cell_width * 0.85,
+            y + self.cell_height * row,
+            self.cell_width.min(self.cell_height) * 0.07,
+        )
+    }
+
+    /// Endpoints of a channel segment. Segments are shifted sideways so that
+    /// the two channels connecting a pair of routers do not overlap.
+    pub fn channel_segment(
+        &self,
+        topology: &Topology,
+        channel: &Channel,
+        to: usize,
+    ) -> ((f32, f32), (f32, f32)) {
+        let (x1, y1) = self.centre(topology, channel.core);
+        let offset_x = self.cell_width * 0.08;
+        let offset_y = self.cell_height * 0.08;
+
+        // Vertical channels end at their marker, the other end is in another panel
+        if channel.direction.is_vertical() {
+            let (x2, y2, _) = self.tsv_marker(topology, channel.core, channel.direction);
+            return ((x1, y1), (x2, y2));
+        }
+
+        // Wraparound channels are drawn as stubs to the edge of the cell
+        let (x2, y2) = if topology.is_wraparound(channel) {
+            let (x, y) = self.origin(topology, channel.core);
+            match channel.direction {
+                Direction::North => (x1, y),
+                Direction::South => (x1, y + self.cell_height),
+                Direction::West => (x, y1),
+                Direction::East => (x + self.cell_width, y1),
+                Direction::Up | Direction::Down => (x1, y1),
+            }
+        } else {
+            self.centre(topology, to)
+        };
+
+        let (dx, dy) = match channel.direction {
+            Direction::North => (offset_x, 0.0),
+            Direction::South => (-offset_x, 0.0),
+            Direction::East => (0.0, -offset_y),
+            Direction::West => (0.0, offset_y),
+            Direction::Up | Direction::Down => (0.0, 0.0),
+        };
+
+        ((x1 + dx, y1 + dy), (x2 + dx, y2 + dy))
+    }
+
+    /// Stroke width that scales with the grid.
+    pub fn stroke_width(&self) -> f32 {
+        self.cell_width.min(self.cell_height) * 0.04
+    }
+}
+
+/// Span of a root tag attribute value and its parsed numbers.
+fn root_attribute<const N: usize>(
+    svg_str: &str,
+    attribute: &str,
+) -> Option<((usize, usize), [f32; N])> {
+    let root_start = svg_str.find(SVG_OPENING_TAG)?;
+    let root_end = root_start + svg_str[root_start..].find('>')?;
+    let root = &svg_str[root_start..root_end];
+
+    // Make sure we don't match e.g. stroke-width when looking for width
+    let mut search_from = 0;
+    let start = loop {
+        let index = search_from + root[search_from..].find(attribute)?;
+        if index == 0 || root.as_bytes()[index - 1].is_ascii_whitespace() {
+            break root_start + index + attribute.len();
+        }
+        search_from = index + attribute.len();
+    };
+    let end = start + svg_str[start..].find('"')?;
+
+    let values = svg_str[start..end]
+        .split_ascii_whitespace()
+        .map(|v| v.trim_end_matches("px").parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()?;
+
+    Some(((start, end), values.try_into().ok()?))
+}
+
+/// Span and values of the root viewBox of a serialised SVG.
+pub(crate) fn root_view_box(svg_str: &str) -> Option<((usize, usize), [f32; 4])> {
+    root_attribute::<4>(svg_str, VIEW_BOX_ATTRIBUTE)
+}
+
+/// Side of the render a [`Decoration`] is placed on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Placement {
+    Top,
+    #[default]
+    Right,
+    Bottom,
+    Left,
+}
+
+/// Markup anchored to a side of the render rather than to cores, e.g. a legend.
+/// Markup is drawn from (0, 0) and moved next to the render when spliced.
+/// The viewBox is enlarged to make room for decorations.
+#[derive(Clone, Debug)]
+pub(crate) struct Decoration {
+    pub placement: Placement,
+    pub width: f32,
+    pub height: f32,
+    pub markup: String,
+}
+
+/// Extra SVG groups drawn on top of the render, keyed by layer name.
+/// Layers and decorations are included in exports.
+#[derive(Default, Clone)]
+pub(crate) struct Overlays {
+    pub geometry: Option<GridGeometry>,
+    layers: BTreeMap<&'static str, String>,
+    decorations: BTreeMap<&'static str, Decoration>,
+}
+
+impl Overlays {
+    /// Drops all layers and stores the geometry of a new render.
+    pub fn reset(&mut self, geometry: Option<GridGeometry>) {
+        self.geometry = geometry;
+        self.layers.clear();
+        self.decorations.clear();
+    }
+
+    pub fn set(&mut self, layer: &'static str, markup: String) {
+        self.layers.insert(layer, markup);
+    }
+
+    pub fn remove(&mut self, layer: &'static str) {
+        self.layers.remove(layer);
+    }
+
+    pub fn set_decoration(&mut self, name: &'static str, decoration: Decoration) {
+        self.decorations.insert(name, decoration);
+    }
+
+    pub fn remove_decoration(&mut self, name: &'static str) {
+        self.decorations.remove(name);
+    }
+
+    pub fn decoration(&self, name: &'static str) -> Option<&Decoration> {
+        self.decorations.get(name)
+    }
+
+    pub fn layer(&self, layer: &'static str) -> Option<&String> {
+        self.layers.get(layer)
+    }
+
+    /// Whether splicing enlarges the render to fit decorations.
+    pub fn has_decorations(&self) -> bool {
+        !self.decorations.is_empty()
+    }
+
+    /// All layers, concatenated in layer name order.
+    pub fn markup(&self) -> String {
+        self.layers.values().map(String::as_str).collect()
+    }
+
+    /// Places decorations around `view_box`.
+    /// Returns the decorations markup and the enlarged viewBox.
+    fn place_decorations(&self, view_box: [f32; 4]) -> (String, [f32; 4]) {
+        let [x, y, width, height] = view_box;
+        let gap = self
+            .geometry
+            .map_or(DEFAULT_DECORATION_GAP, |geometry| geometry.cell_width * 0.2);
+
+        let band = |placement: Placement| {
+            let decorations = self
+                .decorations
+                .values()
+                .filter(move |decoration| decoration.placement == placement);
+
+            match placement {
+                Placement::Left | Placement::Right => decorations
+                    .map(|decoration| decoration.width + gap)
+                    .fold(0.0f32, f32::max),
+                Placement::Top | Placement::Bottom => decorations
+                    .map(|decoration| decoration.height + gap)
+                    .fold(0.0f32, f32::max),
+            }
+        };
+        let (top, right, bottom, left) = (
+            band(Placement::Top),
+            band(Placement::Right),
+            band(Placement::Bottom),
+            band(Placement::Left),
+        );
+
+        let mut markup = String::new();
+        // Decorations on the same side are stacked along it
+        let mut offsets: BTreeMap<u8, f32> = BTreeMap::new();
+        for decoration in self.decorations.values() {
+            let offset = offsets.entry(decoration.placement as u8).or_insert(0.0);
+
+            let (tx, ty) = match decoration.placement {
+                Placement::Top => (x + *offset, y - top),
+                Placement::Right => (x + width + gap, y + *offset),
+                Placement::Bottom => (x + *offset, y + height + gap),
+                Placement::Left => (x - left, y + *offset),
+            };
+            *offset += match decoration.placement {
+                Placement::Top | Placement::Bottom => decoration.width + gap,
+                Placement::Left | Placement::Right => decoration.height + gap,
+            };
+
+            let _ = write!(
+                markup,
+                "<g transform=\"translate({tx} {ty})\">{}</g>",
+                decoration.markup
+            );
+        }
+
+        (
+            markup,
+            [
+                x - left,
+                y - top,
+                width + left + right,
+                height + top + bottom,
+            ],
+        )
+    }
+
+    /// Inserts all layers right before the closing tag of a serialised SVG.
+    /// If there are decorations, the root viewBox (and size, if any) is enlarged to fit them.
+    pub fn splice(&self, mut svg_string: String) -> String {
+        if self.layers.is_empty() && self.decorations.is_empty() {
+            return svg_string;
+        }
+
+        let mut markup = self.markup();
+
+        if !self.decorations.is_empty() {
+            if let Some(((start, end), view_box)) = root_view_box(&svg_string) {
+                let (decorations, enlarged) = self.place_decorations(view_box);
+                markup.push_str(decorations.as_str());
+
+                // Scale explicit dimensions by the same factor as the viewBox.
+                // Work from the end of the tag so spans stay valid.
+                let mut replacements = vec![(
+                    (start, end),
+                    format!(
+                        "{} {} {} {}",
+                        enlarged[0], enlarged[1], enlarged[2], enlarged[3]
+                    ),
+                )];
+                if let Some((span, [w])) = root_attribute::<1>(&svg_string, WIDTH_ATTRIBUTE) {
+                    replacements.push((span, (w * enlarged[2] / view_box[2]).to_string()));
+                }
+                if let Some((span, [h])) = root_attribute::<1>(&svg_string, HEIGHT_ATTRIBUTE) {
+                    replacements.push((span, (h * enlarged[3] / view_box[3]).to_string()));
+                }
+                replacements.sort_by_key(|((start, _), _)| std::cmp::Reverse(*start));
+
+                for ((start, end), value) in replacements {
+                    svg_string.replace_range(start..end, value.as_str());
+                }
+            }
+        }
+
+        insert_markup(svg_string, markup.as_str())
+    }
+}
+
+/// Inserts markup right before the root closing tag.
+pub(crate) fn insert_markup(mut svg_string: String, markup: &str) -> String {
+    if let Some(index) = svg_string.rfind(SVG_CLOSING_TAG) {
+        svg_string.insert_str(index, markup);
+    }
+
+    svg_string
+}
+
+/// Index right after the root opening tag.
+fn root_tag_end(svg_str: &str) -> Option<usize> {
+    let root_start = svg_str.find(SVG_OPENING_TAG)?;
+
+    Some(root_start + svg_str[root_start..].find('>')? + 1)
+}
+
+/// Inserts markup right after the root opening tag, behind everything else.
+pub(crate) fn insert_first(mut svg_string: String, markup: &str) -> String {
+    if let Some(index) = root_tag_end(&svg_string) {
+        svg_string.insert_str(index, markup);
+    }
+
+    svg_string
+}
+
+/// Adds an attribute to the root tag. The root must not have it already.
+pub(crate) fn insert_root_attribute(mut svg_string: String, name: &str, value: &str) -> String {
+    if let Some(index) = svg_string.find(SVG_OPENING_TAG) {
+        svg_string.insert_str(
+            index + SVG_OPENING_TAG.len(),
+            format!(" {name}=\"{}\"", escape(value)).as_str(),
+        );
+    }
+
+    svg_string
+}
+
+/// Fills the root viewBox with `fill` at `opacity`, behind everything else.
+pub(crate) fn insert_background(svg_string: String, fill: &str, opacity: f32) -> String {
+    let Some((_, [x, y, width, height])) = root_view_box(&svg_string) else {
+        return svg_string;
+    };
+
+    let background = format!(
+        "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+        escape(fill),
+        opacity.clamp(0.0, 1.0)
+    );
+
+    insert_first(svg_string, background.as_str())
+}
+
+/// Area covered by a layer of a stacked mesh, as (x, y, width, height).
+pub(crate) fn layer_rect(
+    topology: &Topology,
+    geometry: &GridGeometry,
+    layer: usize,
+) -> (f32, f32, f32, f32) {
+    let height = topology.layer_rows() as f32 * geometry.cell_height;
+
+    (
+        geometry.x,
+        geometry.y + layer as f32 * height,
+        topology.columns as f32 * geometry.cell_width,
+        height,
+    )
+}
+
+/// Frames each layer of a stacked mesh as a labelled panel and marks vertical channels.
+/// Markers pointing up lead to the layer above, markers pointing down to the layer below.
+/// The base render lays the layers out as one mesh, the channels it draws between the last row of
+/// a layer and the first row of the next are covered by a gutter between the panels.
+fn layer_panels_markup(topology: &Topology, geometry: &GridGeometry, markup: &mut String) {
+    let stroke_width = geometry.stroke_width();
+    let font_size = geometry.font_size();
+
+    for layer in 1..topology.layers() {
+        let (x, _, width, _) = layer_rect(topology, geometry, layer);
+        if let Some((y, height)) = geometry.row_gap(layer * topology.layer_rows() - 1) {
+            let _ = write!(
+                markup,
+                "<rect x=\"{}\" y=\"{y}\" width=\"{}\" height=\"{height}\" fill=\"white\"/>",
+                x - stroke_width * 2.0,
+                width + stroke_width * 4.0
+            );
+        }
+    }
+
+    for layer in 0..topology.layers() {
+        let (x, y, width, height) = layer_rect(topology, geometry, layer);
+        let _ = write!(
+            markup,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"none\" stroke=\"{TOPOLOGY_COLOUR}\" stroke-width=\"{}\"/>",
+            stroke_width * 2.0
+        );
+        push_text(
+            markup,
+            x + stroke_width * 3.0,
+            y + font_size + stroke_width * 3.0,
+            font_size,
+            TOPOLOGY_COLOUR,
+            format!("Layer {layer}").as_str(),
+        );
+    }
+
+    for (channel, to) in topology.links() {
+        if !channel.direction.is_vertical() {
+            continue;
+        }
+
+        let (cx, cy, r) = geometry.tsv_marker(topology, channel.core, channel.direction);
+        let tip = match channel.direction {
+            Direction::Up => -r * 0.6,
+            _ => r * 0.6,
+        };
+        let _ = write!(
+            markup,
+            "<g><title>TSV {} → {to}</title><circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"white\" stroke=\"{TOPOLOGY_COLOUR}\" stroke-width=\"{}\"/><path d=\"M{} {}L{cx} {}L{} {}z\" fill=\"{TOPOLOGY_COLOUR}\"/></g>",
+            channel.core,
+            stroke_width * 0.5,
+            cx - r * 0.5,
+            cy - tip * 0.5,
+            cy + tip,
+            cx + r * 0.5,
+            cy - tip * 0.5,
+        );
+    }
+}
+
+/// Draws channels the base render can't: wraparound channels as stubs labelled with the
+/// core they reach, links between distant routers of irregular topologies as curves,
+/// layers of stacked meshes as panels with their vertical channels as markers.
+/// Returns [`None`] if every channel connects grid neighbours.
+pub(crate) fn topology_markup(topology: &Topology, geometry: &GridGeometry) -> Option<String> {
+    let stroke_width = geometry.stroke_width();
+    let font_size = geometry.font_size() * 0.8;
+    let mut markup = String::new();
+
+    if let TopologyKind::Stacked(_) = topology.kind {
+        layer_panels_markup(topology, geometry, &mut markup);
+    }
+
+    for (channel, to) in topology.links() {
+        if !topology.is_wraparound(&channel) {
+            continue;
+        }
+
+        match topology.kind {
+            TopologyKind::Irregular(_) => {
+                let (x1, y1) = geometry.centre(topology, channel.core);
+                let (x2, y2) = geometry.centre(topology, to);
+                // Bend to the right of the direction of travel, so opposite links don't overlap
+                let (cx, cy) = (
+                    (x1 + x2) / 2.0 - (y2 - y1) * 0.2,
+                    (y1 + y2) / 2.0 + (x2 - x1) * 0.2,
+                );
+                let _ = write!(
+                    markup,
+                    "<path d=\"M{x1} {y1}Q{cx} {cy} {x2} {y2}\" fill=\"none\" stroke=\"{TOPOLOGY_COLOUR}\" stroke-width=\"{stroke_width}\" marker-end=\"url(#topologyArrow)\"/>"
+                );
+            }
+            _ => {
+                let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, &channel, to);
+                let _ = write!(
+                    markup,
+                    "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{TOPOLOGY_COLOUR}\" stroke-width=\"{stroke_width}\" stroke-dasharray=\"{} {}\" marker-end=\"url(#topologyArrow)\"/>",
+                    stroke_width * 3.0,
+                    stroke_width * 1.5
+                );
+
+                let (label_x, label_y) = match channel.direction {
+                    Direction::North | Direction::South | Direction::Up | Direction::Down => {
+                        (x2 + stroke_width * 2.0, y2)
+                    }
+                    Direction::East | Direction::West => (x2 - font_size, y2 - stroke_width * 2.0),
+                };
+                push_text(
+                    &mut markup,
+                    label_x,
+                    label_y,
+                    font_size,
+                    TOPOLOGY_COLOUR,
+                    format!("→{to}").as_str(),
+                );
+            }
+        }
+    }
+
+    if markup.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "<g id=\"topology\"><defs><marker id=\"topologyArrow\" viewBox=\"0 0 10 10\" refX=\"9\" refY=\"5\" markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\"><path d=\"M0 0L10 5L0 10z\" fill=\"{TOPOLOGY_COLOUR}\"/></marker></defs>{markup}</g>"
+    ))
+}
+
+/// Escapes text content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            c => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+/// Appends a text element to `markup`.
+pub(crate) fn push_text(
+    markup: &mut String,
+    x: f32,
+    y: f32,
+    font_size: f32,
+    fill: &str,
+    text: &str,
+) {
+    let _ = write!(
+        markup,
+        "<text x=\"{x}\" y=\"{y}\" font-family=\"Roboto Mono\" font-size=\"{font_size}\" fill=\"{fill}\">{}</text>",
+        escape(text)
+    );
+}
@@ -0,0 +1,263 @@
+use std::fmt::Write;
+
+use serde::Deserialize;
+use tauri::{api::dialog::FileDialogBuilder, AppHandle, Manager, Window};
+
+use crate::{
+    cluster::Clustering,
+    export::{serialise_render, Region, RenderMode, ERROR_EVENT, OK_EVENT},
+    overlay::{insert_markup, push_text, GridGeometry},
+    system::SystemSnapshot,
+    topology::Topology,
+    State,
+};
+
+/// How the render is split in pages.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub(crate) enum Partition {
+    /// Equal pages on a `rows` x `columns` grid.
+    Grid { rows: usize, columns: usize },
+    /// One page per cluster of cores.
+    #[serde(rename_all = "camelCase")]
+    Clusters {
+        cluster_rows: usize,
+        cluster_columns: usize,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageLayout {
+    partition: Partition,
+    /// Render repeated on both sides of a page boundary, in SVG user space.
+    overlap: f32,
+    /// Whether to print the position of each page on it.
+    labels: bool,
+}
+
+/// A page of the export.
+struct Page {
+    /// Suffix of the page's file name.
+    name: String,
+    label: String,
+    /// Area of the page without overlap, as (x, y, width, height).
+    area: (f32, f32, f32, f32),
+}
+
+/// Splits the render in pages, in row order.
+fn pages(
+    partition: &Partition,
+    snapshot: &SystemSnapshot,
+    geometry: &GridGeometry,
+) -> Result<Vec<Page>, String> {
+    match *partition {
+        Partition::Grid { rows, columns } => {
+            if rows == 0 || columns == 0 {
+                return Err(String::from(
+                    "Pages must be split in at least one row and one column.",
+                ));
+            }
+
+            let width = snapshot.columns as f32 * geometry.cell_width / columns as f32;
+            let height = snapshot.rows as f32 * geometry.cell_height / rows as f32;
+
+            Ok((0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                .map(|(row, column)| {
+                    let (x, y) = (
+                        geometry.x + column as f32 * width,
+                        geometry.y + row as f32 * height,
+                    );
+
+                    Page {
+                        name: format!("page_r{row}_c{column}"),
+                        label: format!(
+                            "Page {}-{} of {rows}x{columns}, x {x:.0} to {:.0}, y {y:.0} to {:.0}",
+                            row + 1,
+                            column + 1,
+                            x + width,
+                            y + height
+                        ),
+                        area: (x, y, width, height),
+                    }
+                })
+                .collect())
+        }
+        Partition::Clusters {
+            cluster_rows,
+            cluster_columns,
+        } => {
+            let topology = Topology::from_snapshot(snapshot);
+            let clustering = Clustering::new(snapshot, cluster_rows, cluster_columns)?;
+
+            Ok((0..clustering.cluster_count())
+                .map(|cluster| {
+                    let (row, column, rows, columns) = clustering.span(&topology, cluster);
+
+                    Page {
+                        name: format!("cluster{cluster}"),
+                        label: format!(
+                            "Cluster {cluster}, core rows {row} to {}, columns {column} to {}",
+                            row + rows - 1,
+                            column + columns - 1
+                        ),
+                        area: clustering.rect(&topology, geometry, cluster),
+                    }
+                })
+                .collect())
+        }
+    }
+}
+
+/// Grows a page by `overlap` on every side, without leaving the `bounds` of the render.
+fn with_overlap(
+    (x, y, width, height): (f32, f32, f32, f32),
+    overlap: f32,
+    (left, top, right, bottom): (f32, f32, f32, f32),
+) -> (f32, f32, f32, f32) {
+    let (x0, y0) = ((x - overlap).max(left), (y - overlap).max(top));
+
+    (
+        x0,
+        y0,
+        (x + width + overlap).min(right) - x0,
+        (y + height + overlap).min(bottom) - y0,
+    )
+}
+
+/// Outlines the page's own area, so overlapping pages can be trimmed, and labels the page.
+/// Both are drawn in `ink`, to stand out from the render's background.
+fn page_markup(
+    page: &Page,
+    region: (f32, f32, f32, f32),
+    labels: bool,
+    geometry: &GridGeometry,
+    ink: &str,
+) -> String {
+    let stroke_width = geometry.stroke_width();
+    let font_size = geometry.font_size() * 1.5;
+    let mut markup = String::new();
+
+    if page.area != region {
+        let (x, y, width, height) = page.area;
+        let _ = write!(
+            markup,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"none\" stroke=\"{ink}\" stroke-width=\"{stroke_width}\" stroke-dasharray=\"{} {}\"/>",
+            stroke_width * 4.0,
+            stroke_width * 4.0
+        );
+    }
+
+    if labels {
+        push_text(
+            &mut markup,
+            region.0 + stroke_width * 3.0,
+            region.1 + font_size + stroke_width * 3.0,
+            font_size,
+            ink,
+            page.label.as_str(),
+        );
+    }
+
+    markup
+}
+
+/// Exports the render split in pages, one SVG, PNG or PDF per page, e.g. to print a poster.
+/// Files are named after the picked path with a `_page_r<R>_c<C>` or `_cluster<N>` suffix.
+#[tauri::command]
+pub(crate) fn export_pages(
+    window: Window,
+    handle: AppHandle,
+    state: tauri::State<'_, State>,
+    layout: PageLayout,
+    render_mode: RenderMode,
+    scale: f32,
+) -> Result<(), String> {
+    if !layout.overlap.is_finite() || layout.overlap < 0.0 {
+        return Err(String::from("Page overlap can't be negative."));
+    }
+
+    let mut document = state.write();
+    let (Some(snapshot), Some(_)) = (&document.snapshot, &document.svg) else {
+        return Err("You must load a system first.".to_string());
+    };
+    let geometry = document
+        .overlays
+        .geometry
+        .ok_or("Could not locate cores in the render.")?;
+    // Left, top, right and bottom edges of the cores
+    let bounds = (
+        geometry.x,
+        geometry.y,
+        geometry.x + snapshot.columns as f32 * geometry.cell_width,
+        geometry.y + snapshot.rows as f32 * geometry.cell_height,
+    );
+    let ink = document.appearance.ink();
+
+    let pages = pages(&layout.partition, snapshot, &geometry)?
+        .into_iter()
+        .map(|page| {
+            let region = with_overlap(page.area, layout.overlap, bounds);
+            let page_region = Region::from_rect(region);
+
+            // Cores of other pages are left out, not just clipped
+            serialise_render(&mut document, None, Some(page_region), None, None)
+                .and_then(|svg_string| page_region.strip_outside(svg_string.as_str()))
+                .map(|svg_string| {
+                    (
+                        page.name.clone(),
+                        insert_markup(
+                            svg_string,
+                            page_markup(&page, region, layout.labels, &geometry, ink).as_str(),
+                        ),
+                    )
+                })
+        })
+        .collect::<Result<Vec<(String, String)>, String>>()?;
+
+    drop(document);
+
+    let font_database = state.font_database().clone();
+    let (filter_name, extension, message) = render_mode.file_details();
+    let window_label = window.label().to_owned();
+
+    // ASYNC CONTEXT - CAN'T RETURN TO JS PROMISE
+    FileDialogBuilder::new()
+        .add_filter(filter_name, &[extension])
+        .save_file(move |file_path| {
+            let (Some(file_path), Some(window)) =
+                (file_path, handle.get_window(window_label.as_str()))
+            else {
+                return;
+            };
+            let stem = file_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let export_res = pages.into_iter().try_for_each(|(name, svg_string)| {
+                render_mode
+                    .write(
+                        file_path.with_file_name(format!("{stem}_{name}.{extension}")),
+                        svg_string,
+                        &font_database,
+                        scale,
+                        &[],
+                    )
+                    .map(|_| ())
+                    .map_err(|e| format!("Could not export {name}: {e}"))
+            });
+
+            match export_res {
+                Ok(_) => {
+                    let _ = window.emit(OK_EVENT, message);
+                }
+                Err(e) => {
+                    let _ = window.emit(ERROR_EVENT, e);
+                }
+            }
+        });
+    // ASYNC CONTEXT ENDS
+    Ok(())
+}
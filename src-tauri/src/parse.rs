@@ -1,7 +1,16 @@
+use std::fs;
+
 use manycore_parser::ManycoreSystem;
 use serde::Serialize;
+use tauri::Window;
 
-use crate::{result_status::ResultStatus, State};
+use crate::{
+    document::Document,
+    progress::{ProgressReporter, Stage},
+    result_status::ResultStatus,
+    system::read_topology,
+    State,
+};
 
 #[derive(Serialize, Debug)]
 pub struct ParseResult {
@@ -14,28 +23,70 @@ fn format_error_response(ret: &mut ParseResult, msg: String) {
     ret.message = msg;
 }
 
+/// Drops state tied to the previously loaded system.
+pub(crate) fn forget_system_state(state: &State, document: &mut Document) {
+    if let Ok(mut playback_mutex) = state.playback.lock() {
+        // Dropping stops playback
+        playback_mutex.take();
+    }
+
+    document.forget_system();
+}
+
+/// Parses a system file and stores it, reporting progress to `window`.
+/// Runs on a blocking thread. Loading another file meanwhile cancels this one.
 #[tauri::command]
-pub fn parse(file_path: &str, state: tauri::State<State>) -> ParseResult {
-    let manycore_parse_res = ManycoreSystem::parse_file(file_path);
+pub async fn parse(
+    file_path: String,
+    window: Window,
+    state: tauri::State<'_, State>,
+) -> Result<ParseResult, ()> {
+    let state = state.inner().clone();
+    let progress = ProgressReporter::new_load(window, &state.load_generation);
+
+    tauri::async_runtime::spawn_blocking(move || load_file(file_path, &state, &progress))
+        .await
+        .map_err(|_| ())
+}
 
+/// Parses `file_path` and stores the system, unless another file was loaded meanwhile.
+fn load_file(file_path: String, state: &State, progress: &ProgressReporter) -> ParseResult {
     let mut ret = ParseResult {
         status: ResultStatus::Ok,
         message: String::from("Successfully parsed file"),
     };
 
-    match manycore_parse_res {
-        Ok(manycore) => match state.manycore.lock() {
-            Ok(mut manycore_mutex) => {
-                let _ = manycore_mutex.insert(manycore);
-            }
-            Err(e) => {
-                format_error_response(&mut ret, e.to_string());
+    let parsed = progress.stage(Stage::Parsing).and_then(|_| {
+        let manycore = ManycoreSystem::parse_file(file_path.as_str()).map_err(|e| e.to_string())?;
+        // The parser drops the topology extension, read it from the file
+        let topology = fs::read_to_string(file_path.as_str())
+            .map_err(|e| e.to_string())
+            .and_then(|xml| read_topology(xml.as_str()))?;
+
+        Ok((manycore, topology))
+    });
+
+    match parsed {
+        Ok((manycore, topology)) => {
+            let mut document = state.write();
+
+            match progress.check() {
+                // Don't replace a system loaded after this one
+                Err(e) => format_error_response(&mut ret, e),
+                Ok(_) => {
+                    let _ = document.manycore.insert(manycore);
+                    let _ = document.source_path.insert(file_path);
+                    document.cache.invalidate();
+                    forget_system_state(state, &mut document);
+                    document.topology = topology;
+                    progress.done();
+                }
             }
-        },
+        }
         Err(e) => {
-            format_error_response(&mut ret, e.to_string());
+            format_error_response(&mut ret, e);
         }
     }
 
-    return ret;
+    ret
 }
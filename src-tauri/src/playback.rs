@@ -0,0 +1,487 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    result_status::ResultStatus,
+    system::{AttributeUpdate, ElementId, SystemSnapshot},
+    update::{apply_updates, AttributeValue, UpdateApplyResult, UpdateMessage},
+    State,
+};
+
+// Event names
+static TRACE_FRAME_EVENT: &'static str = "trace_frame";
+static TRACE_STATUS_EVENT: &'static str = "trace_status";
+
+static MAX_FRAME_RATE: f32 = 60.0;
+static STALE_FRAME_MESSAGE: &'static str = "The trace no longer applies to the loaded system.";
+
+/// A JSON lines trace entry. Either a single value or a batch of updates for one cycle.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TraceLine {
+    Value {
+        cycle: u64,
+        element: ElementId,
+        attribute: String,
+        value: Option<AttributeValue>,
+    },
+    Batch {
+        cycle: u64,
+        updates: UpdateMessage,
+    },
+}
+
+/// A time indexed trace of attribute values.
+pub(crate) struct Trace {
+    /// Values of the traced attributes in the system the trace is overlaid onto, [`None`] where
+    /// unset.
+    base: BTreeMap<(ElementId, String), Option<String>>,
+    /// Updates keyed by cycle.
+    frames: BTreeMap<u64, Vec<AttributeUpdate>>,
+}
+
+impl Trace {
+    /// Parses a trace file. CSV rows are `cycle,element,attribute,value`, an empty value
+    /// removes the attribute and a header row is optional. JSON lines hold a [`TraceLine`] each.
+    /// Traced elements must exist in `system`, which provides the values before they are traced.
+    fn parse(content: &str, system: &SystemSnapshot) -> Result<Self, String> {
+        let mut frames: BTreeMap<u64, Vec<AttributeUpdate>> = BTreeMap::new();
+        let json = content.trim_start().starts_with('{');
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (cycle, updates) = if json {
+                Self::parse_json_line(line)
+            } else {
+                match Self::parse_csv_line(line) {
+                    // Header row
+                    Err(_) if i == 0 => continue,
+                    res => res,
+                }
+            }
+            .map_err(|e| format!("Line {}: {e}", i + 1))?;
+
+            frames.entry(cycle).or_default().extend(updates);
+        }
+
+        if frames.is_empty() {
+            return Err(String::from("Trace file contains no values."));
+        }
+
+        let mut base = BTreeMap::new();
+        for update in frames.values().flatten() {
+            let attributes = system
+                .attributes(&update.element)
+                .ok_or_else(|| format!("Element \"{}\" does not exist.", update.element))?;
+
+            base.entry((update.element, update.attribute.clone()))
+                .or_insert_with(|| attributes.get(&update.attribute).cloned());
+        }
+
+        Ok(Self { base, frames })
+    }
+
+    fn parse_csv_line(line: &str) -> Result<(u64, Vec<AttributeUpdate>), String> {
+        let mut fields = line.splitn(4, ',').map(str::trim);
+
+        let (Some(cycle), Some(element), Some(attribute)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(String::from("Expected cycle,element,attribute,value."));
+        };
+
+        let cycle = cycle
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid cycle \"{cycle}\"."))?;
+        let value = fields
+            .next()
+            .map(|value| value.trim_matches('"'))
+            .filter(|value| !value.is_empty())
+            .map(String::from);
+
+        Ok((
+            cycle,
+            vec![AttributeUpdate {
+                element: element.parse()?,
+                attribute: attribute.to_string(),
+                value,
+            }],
+        ))
+    }
+
+    fn parse_json_line(line: &str) -> Result<(u64, Vec<AttributeUpdate>), String> {
+        match serde_json::from_str::<TraceLine>(line).map_err(|e| e.to_string())? {
+            TraceLine::Value {
+                cycle,
+                element,
+                attribute,
+                value,
+            } => Ok((
+                cycle,
+                vec![AttributeUpdate {
+                    element,
+                    attribute,
+                    value: value.map(String::from),
+                }],
+            )),
+            TraceLine::Batch { cycle, updates } => Ok((cycle, updates.into_updates())),
+        }
+    }
+
+    fn start(&self) -> u64 {
+        self.frames.keys().next().copied().unwrap_or_default()
+    }
+
+    fn end(&self) -> u64 {
+        self.frames.keys().next_back().copied().unwrap_or_default()
+    }
+
+    /// Latest value of every traced attribute at `cycle`, the base value for those not traced yet.
+    fn updates_at(&self, cycle: u64) -> Vec<AttributeUpdate> {
+        let mut latest = self
+            .base
+            .iter()
+            .map(|((element, attribute), value)| ((*element, attribute), value))
+            .collect::<BTreeMap<(ElementId, &String), &Option<String>>>();
+
+        for updates in self.frames.range(..=cycle).map(|(_, updates)| updates) {
+            for update in updates {
+                latest.insert((update.element, &update.attribute), &update.value);
+            }
+        }
+
+        latest
+            .into_iter()
+            .map(|((element, attribute), value)| AttributeUpdate {
+                element,
+                attribute: attribute.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    /// Cycles rendered when playing from `from`. Without a step, every traced cycle is a frame.
+    fn timeline(&self, from: u64, cycles_per_frame: Option<u64>) -> Vec<u64> {
+        match cycles_per_frame.filter(|step| *step > 0) {
+            Some(step) => {
+                let mut ret = Vec::new();
+                let mut cycle = from.saturating_add(step);
+                while cycle <= self.end() {
+                    ret.push(cycle);
+                    cycle = cycle.saturating_add(step);
+                }
+                ret
+            }
+            None => self
+                .frames
+                .range((Bound::Excluded(from), Bound::Unbounded))
+                .map(|(cycle, _)| *cycle)
+                .collect(),
+        }
+    }
+}
+
+/// A loaded trace and its playback position.
+pub struct Playback {
+    trace: Arc<Trace>,
+    /// Load the trace belongs to.
+    generation: u64,
+    cycle: u64,
+    /// Set while the playback thread runs.
+    playing: Option<Arc<AtomicBool>>,
+}
+
+impl Playback {
+    fn pause(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            playing.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        self.pause();
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceInfo {
+    start: u64,
+    end: u64,
+    frame_count: usize,
+    cycle: u64,
+    playing: bool,
+}
+
+impl From<&Playback> for TraceInfo {
+    fn from(playback: &Playback) -> Self {
+        Self {
+            start: playback.trace.start(),
+            end: playback.trace.end(),
+            frame_count: playback.trace.frames.len(),
+            cycle: playback.cycle,
+            playing: playback.playing.is_some(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TraceResult {
+    status: ResultStatus,
+    message: String,
+    info: Option<TraceInfo>,
+}
+
+impl TraceResult {
+    fn error(message: String) -> Self {
+        Self {
+            status: ResultStatus::Error,
+            message,
+            info: None,
+        }
+    }
+}
+
+/// A rendered trace frame.
+#[derive(Serialize)]
+pub struct TraceFrame {
+    cycle: u64,
+    #[serde(flatten)]
+    result: UpdateApplyResult,
+}
+
+/// Sets the traced attributes of the loaded system to their values at `cycle`.
+/// `is_current` is checked once the document is locked, the frame is dropped if playback was
+/// paused or the system replaced while waiting for it.
+/// Locks the document, so must not be called while holding the playback lock.
+fn render_frame(
+    state: &tauri::State<State>,
+    trace: &Trace,
+    cycle: u64,
+    is_current: impl Fn() -> bool,
+) -> TraceFrame {
+    let mut document = state.write();
+
+    TraceFrame {
+        cycle,
+        result: match is_current() {
+            true => apply_updates(&mut document, &trace.updates_at(cycle)),
+            false => UpdateApplyResult::error(String::from(STALE_FRAME_MESSAGE)),
+        },
+    }
+}
+
+fn playback_loop(
+    handle: AppHandle,
+    trace: Arc<Trace>,
+    generation: u64,
+    timeline: Vec<u64>,
+    interval: Duration,
+    playing: Arc<AtomicBool>,
+) {
+    let state = handle.state::<State>();
+    let is_current =
+        || playing.load(Ordering::Relaxed) && state.load_generation.current() == generation;
+
+    for cycle in timeline {
+        if !playing.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let frame_start = Instant::now();
+        let frame = render_frame(&state, &trace, cycle, is_current);
+
+        if let Ok(mut playback_mutex) = state.playback.lock() {
+            match &mut *playback_mutex {
+                // Paused, or replaced, while rendering
+                Some(playback)
+                    if playback
+                        .playing
+                        .as_ref()
+                        .is_some_and(|current| Arc::ptr_eq(current, &playing)) =>
+                {
+                    playback.cycle = cycle;
+                }
+                _ => return,
+            }
+        }
+
+        // Event payloads must be Clone, go through a Value
+        if let Ok(payload) = serde_json::to_value(&frame) {
+            let _ = handle.emit_all(TRACE_FRAME_EVENT, payload);
+        }
+
+        if let ResultStatus::Error = frame.result.status {
+            playing.store(false, Ordering::Relaxed);
+            let _ = handle.emit_all(
+                TRACE_STATUS_EVENT,
+                format!("Playback stopped: {}", frame.result.message),
+            );
+            return;
+        }
+
+        thread::sleep(interval.saturating_sub(frame_start.elapsed()));
+    }
+
+    if let Ok(mut playback_mutex) = state.playback.lock() {
+        if let Some(playback) = &mut *playback_mutex {
+            if playback
+                .playing
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(current, &playing))
+            {
+                playback.pause();
+                let _ = handle.emit_all(TRACE_STATUS_EVENT, String::from("Playback finished."));
+            }
+        }
+    }
+}
+
+/// Loads a trace file to overlay onto the loaded system.
+/// The system as currently loaded is the base every frame is applied to.
+#[tauri::command]
+pub fn load_trace(file_path: &str, state: tauri::State<State>) -> TraceResult {
+    let trace = fs::read_to_string(file_path)
+        .map_err(|e| format!("Could not open trace file: {e}"))
+        .and_then(|content| match &state.read().snapshot {
+            // Loads bump the generation while holding the document
+            Some(snapshot) => Ok((
+                Trace::parse(content.as_str(), snapshot)?,
+                state.load_generation.current(),
+            )),
+            None => Err(String::from("Load a system before loading a trace.")),
+        });
+
+    match (trace, state.playback.lock()) {
+        (Ok((trace, generation)), Ok(mut playback_mutex)) => {
+            let playback = playback_mutex.insert(Playback {
+                generation,
+                cycle: trace.start(),
+                trace: Arc::new(trace),
+                playing: None,
+            });
+
+            TraceResult {
+                status: ResultStatus::Ok,
+                message: String::from("Trace loaded."),
+                info: Some(TraceInfo::from(&*playback)),
+            }
+        }
+        (Err(e), _) => TraceResult::error(e),
+        (_, Err(_)) => TraceResult::error(String::from("Could not store trace.")),
+    }
+}
+
+/// Renders the trace at `cycle`. Stops playback, if running.
+#[tauri::command]
+pub fn seek_trace(cycle: u64, state: tauri::State<State>) -> TraceFrame {
+    let trace = match state.playback.lock() {
+        Ok(mut playback_mutex) => match &mut *playback_mutex {
+            Some(playback) => {
+                playback.pause();
+                playback.cycle = cycle;
+                Ok((Arc::clone(&playback.trace), playback.generation))
+            }
+            None => Err(String::from("Load a trace before seeking.")),
+        },
+        Err(_) => Err(String::from("Something went wrong, please try again.")),
+    };
+
+    match trace {
+        Ok((trace, generation)) => render_frame(&state, &trace, cycle, || {
+            state.load_generation.current() == generation
+        }),
+        Err(e) => TraceFrame {
+            cycle,
+            result: UpdateApplyResult::error(e),
+        },
+    }
+}
+
+/// Plays the trace from the current position at `frame_rate` frames per second.
+/// Each frame advances to the next traced cycle, or by `cycles_per_frame` if provided.
+/// Frames are delivered through `trace_frame` events.
+#[tauri::command]
+pub fn play_trace(
+    frame_rate: f32,
+    cycles_per_frame: Option<u64>,
+    handle: AppHandle,
+    state: tauri::State<State>,
+) -> TraceResult {
+    if !frame_rate.is_finite() || frame_rate <= 0.0 {
+        return TraceResult::error(String::from("Frame rate must be greater than zero."));
+    }
+    let interval = Duration::from_secs_f32(1.0 / frame_rate.min(MAX_FRAME_RATE));
+
+    let Ok(mut playback_mutex) = state.playback.lock() else {
+        return TraceResult::error(String::from("Something went wrong, please try again."));
+    };
+
+    let Some(playback) = &mut *playback_mutex else {
+        return TraceResult::error(String::from("Load a trace before playing it."));
+    };
+
+    playback.pause();
+
+    let timeline = playback.trace.timeline(playback.cycle, cycles_per_frame);
+    if timeline.is_empty() {
+        return TraceResult {
+            status: ResultStatus::Ok,
+            message: String::from("Already at the end of the trace."),
+            info: Some(TraceInfo::from(&*playback)),
+        };
+    }
+
+    let playing = Arc::new(AtomicBool::new(true));
+    let playing_clone = Arc::clone(&playing);
+    let (trace, generation) = (Arc::clone(&playback.trace), playback.generation);
+    thread::spawn(move || {
+        playback_loop(handle, trace, generation, timeline, interval, playing_clone)
+    });
+
+    playback.playing = Some(playing);
+
+    TraceResult {
+        status: ResultStatus::Ok,
+        message: String::from("Playing trace."),
+        info: Some(TraceInfo::from(&*playback)),
+    }
+}
+
+/// Pauses playback at the last rendered frame.
+#[tauri::command]
+pub fn pause_trace(state: tauri::State<State>) -> TraceResult {
+    match state.playback.lock() {
+        Ok(mut playback_mutex) => match &mut *playback_mutex {
+            Some(playback) => {
+                playback.pause();
+
+                TraceResult {
+                    status: ResultStatus::Ok,
+                    message: String::from("Playback paused."),
+                    info: Some(TraceInfo::from(&*playback)),
+                }
+            }
+            None => TraceResult::error(String::from("No trace loaded.")),
+        },
+        Err(_) => TraceResult::error(String::from("Something went wrong, please try again.")),
+    }
+}
@@ -0,0 +1,243 @@
+use std::path::Path;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{api::dialog::FileDialogBuilder, AppHandle, Manager, Window};
+
+use crate::{
+    document::Document,
+    export::{serialise_render, ClipPathInput, RenderMode, ERROR_EVENT, OK_EVENT},
+    result_status::ResultStatus,
+    theme::Appearance,
+    State,
+};
+
+static DEFAULT_FILE_NAME: &'static str = "{system}_{preset}";
+
+/// A named export, run again with one click.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportPreset {
+    pub name: String,
+    pub render_mode: RenderMode,
+    pub scale: f32,
+    /// Exported area, the whole render if not set.
+    #[serde(default)]
+    pub clip_path: Option<ClipPathInput>,
+    /// Background and theme, the in-app ones if not set.
+    #[serde(default)]
+    pub appearance: Option<Appearance>,
+    /// Output file name without extension.
+    /// `{preset}`, `{system}` and `{timestamp}` are replaced by the preset name, the loaded
+    /// file name and the export time.
+    #[serde(default)]
+    pub file_name: String,
+}
+
+impl ExportPreset {
+    /// Output file name, with extension.
+    fn file_name(&self, system: &str, timestamp: &str) -> String {
+        let pattern = match self.file_name.trim() {
+            "" => DEFAULT_FILE_NAME,
+            pattern => pattern,
+        };
+        let (_, extension, _) = self.render_mode.file_details();
+
+        let file_name = pattern
+            .replace("{preset}", self.name.as_str())
+            .replace("{system}", system)
+            .replace("{timestamp}", timestamp)
+            // Presets write to the picked folder only
+            .replace(['/', '\\'], "_");
+
+        format!("{file_name}.{extension}")
+    }
+
+    /// Serialises the render as this preset exports it.
+    fn render(&self, document: &mut Document) -> Result<String, String> {
+        let (region, clip_path) = self
+            .clip_path
+            .clone()
+            .map(ClipPathInput::into_parts)
+            .unzip();
+        serialise_render(document, None, region, clip_path, self.appearance.as_ref())
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportPresetsResult {
+    status: ResultStatus,
+    message: String,
+    presets: Vec<ExportPreset>,
+}
+
+impl ExportPresetsResult {
+    fn ok(message: &str, document: &Document) -> Self {
+        Self {
+            status: ResultStatus::Ok,
+            message: String::from(message),
+            presets: document.export_presets.clone(),
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            status: ResultStatus::Error,
+            message,
+            presets: Vec::new(),
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_export_presets(state: tauri::State<State>) -> ExportPresetsResult {
+    ExportPresetsResult::ok("Ok", &state.read())
+}
+
+/// Stores a preset, replacing the one with the same name if any.
+#[tauri::command]
+pub(crate) fn set_export_preset(
+    preset: ExportPreset,
+    state: tauri::State<State>,
+) -> ExportPresetsResult {
+    if preset.name.trim().is_empty() {
+        return ExportPresetsResult::error(String::from("Please name the preset."));
+    }
+    if !preset.scale.is_finite() || preset.scale <= 0.0 {
+        return ExportPresetsResult::error(String::from(
+            "Please provide a scale greater than zero.",
+        ));
+    }
+
+    let mut document = state.write();
+    match document
+        .export_presets
+        .iter_mut()
+        .find(|existing| existing.name == preset.name)
+    {
+        Some(existing) => *existing = preset,
+        None => document.export_presets.push(preset),
+    }
+
+    ExportPresetsResult::ok("Preset saved.", &document)
+}
+
+#[tauri::command]
+pub(crate) fn remove_export_preset(
+    name: String,
+    state: tauri::State<State>,
+) -> ExportPresetsResult {
+    let mut document = state.write();
+    document.export_presets.retain(|preset| preset.name != name);
+
+    ExportPresetsResult::ok("Preset removed.", &document)
+}
+
+/// Renders `presets` and writes them to a folder picked by the user.
+fn run_presets(
+    presets: Vec<ExportPreset>,
+    window: Window,
+    handle: AppHandle,
+    state: tauri::State<'_, State>,
+) -> Result<(), String> {
+    let mut document = state.write();
+    if document.svg.is_none() {
+        return Err("You must load a system first.".to_string());
+    }
+
+    let system = document
+        .source_path
+        .as_ref()
+        .and_then(|source| Path::new(source).file_stem())
+        .map_or(String::from("system"), |stem| {
+            stem.to_string_lossy().into_owned()
+        });
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+
+    let exports = presets
+        .into_iter()
+        .map(|preset| match preset.render(&mut document) {
+            Ok(svg_string) => Ok((
+                preset.file_name(system.as_str(), timestamp.as_str()),
+                svg_string,
+                preset,
+            )),
+            Err(e) => Err(format!("Could not render {}: {e}", preset.name)),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    drop(document);
+
+    let font_database = state.font_database().clone();
+    let window_label = window.label().to_owned();
+
+    // ASYNC CONTEXT - CAN'T RETURN TO JS PROMISE
+    FileDialogBuilder::new().pick_folder(move |folder| {
+        let (Some(folder), Some(window)) = (folder, handle.get_window(window_label.as_str()))
+        else {
+            return;
+        };
+
+        let count = exports.len();
+        let export_res = exports
+            .into_iter()
+            .try_for_each(|(file_name, svg_string, preset)| {
+                preset
+                    .render_mode
+                    .write(
+                        folder.join(file_name),
+                        svg_string,
+                        &font_database,
+                        preset.scale,
+                        &[],
+                    )
+                    .map(|_| ())
+                    .map_err(|e| format!("Could not export {}: {e}", preset.name))
+            });
+
+        match export_res {
+            Ok(_) => {
+                let _ = window.emit(OK_EVENT, format!("Successfully exported {count} presets."));
+            }
+            Err(e) => {
+                let _ = window.emit(ERROR_EVENT, e);
+            }
+        }
+    });
+    // ASYNC CONTEXT ENDS
+    Ok(())
+}
+
+/// Runs the preset named `name`.
+#[tauri::command]
+pub(crate) fn run_export_preset(
+    name: String,
+    window: Window,
+    handle: AppHandle,
+    state: tauri::State<'_, State>,
+) -> Result<(), String> {
+    let preset = state
+        .read()
+        .export_presets
+        .iter()
+        .find(|preset| preset.name == name)
+        .cloned()
+        .ok_or(format!("There is no preset named {name}."))?;
+
+    run_presets(vec![preset], window, handle, state)
+}
+
+/// Runs every preset, writing all files to the same folder.
+#[tauri::command]
+pub(crate) fn run_export_presets(
+    window: Window,
+    handle: AppHandle,
+    state: tauri::State<'_, State>,
+) -> Result<(), String> {
+    let presets = state.read().export_presets.clone();
+    if presets.is_empty() {
+        return Err(String::from("There are no export presets."));
+    }
+
+    run_presets(presets, window, handle, state)
+}
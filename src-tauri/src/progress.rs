@@ -0,0 +1,102 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use serde::Serialize;
+use tauri::Window;
+
+static PROGRESS_EVENT: &'static str = "progress";
+static CANCELLED_MESSAGE: &'static str = "Cancelled, another system was loaded.";
+
+/// Step of a long running command.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Parsing,
+    BuildingSvg,
+    Serialising,
+    Done,
+    Cancelled,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Progress {
+    /// Load the work belongs to.
+    generation: u64,
+    stage: Stage,
+}
+
+/// Counts system loads, so work started for a previous system can notice it is stale.
+#[derive(Default)]
+pub(crate) struct LoadGeneration(AtomicU64);
+
+impl LoadGeneration {
+    /// Starts a new load, making all running work stale.
+    pub fn next(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn current(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Reports the stages of a command to the window that invoked it.
+pub(crate) struct ProgressReporter {
+    window: Window,
+    generations: Arc<LoadGeneration>,
+    generation: u64,
+}
+
+impl ProgressReporter {
+    /// Reports work belonging to the current load.
+    pub fn new(window: Window, generations: &Arc<LoadGeneration>) -> Self {
+        Self {
+            window,
+            generation: generations.current(),
+            generations: generations.clone(),
+        }
+    }
+
+    /// Reports work for a new load, cancelling work for previous ones.
+    pub fn new_load(window: Window, generations: &Arc<LoadGeneration>) -> Self {
+        Self {
+            window,
+            generation: generations.next(),
+            generations: generations.clone(),
+        }
+    }
+
+    fn emit(&self, stage: Stage) {
+        let _ = self.window.emit(
+            PROGRESS_EVENT,
+            Progress {
+                generation: self.generation,
+                stage,
+            },
+        );
+    }
+
+    /// Fails with a message for the user if another system was loaded since the work started.
+    pub fn check(&self) -> Result<(), String> {
+        if self.generations.current() != self.generation {
+            self.emit(Stage::Cancelled);
+            return Err(String::from(CANCELLED_MESSAGE));
+        }
+
+        Ok(())
+    }
+
+    /// Announces the next stage, unless the work is stale.
+    pub fn stage(&self, stage: Stage) -> Result<(), String> {
+        self.check()?;
+
+        self.emit(stage);
+        Ok(())
+    }
+
+    pub fn done(&self) {
+        self.emit(Stage::Done);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{fmt::Write, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::Document,
+    overlay::{push_text, GridGeometry},
+    result_status::ResultStatus,
+    svg::{display_render, SVGObject, SVGResult},
+    system::{SystemSnapshot, TaskEdge},
+    topology::{Channel, Direction, Topology, TopologyKind},
+    State,
+};
+
+static ROUTE_TRACE_LAYER: &'static str = "route_trace";
+static ROUTE_TRACE_COLOUR: &'static str = "#ff00ff";
+
+/// Routing algorithms we can compute paths for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RoutingAlgorithm {
+    /// Travel along the source row first (East/West), then along the destination column.
+    RowFirst,
+    /// Travel along the source column first (North/South), then along the destination row.
+    ColumnFirst,
+}
+
+impl FromStr for RoutingAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "RowFirst" => Ok(RoutingAlgorithm::RowFirst),
+            "ColumnFirst" => Ok(RoutingAlgorithm::ColumnFirst),
+            "Observed" => Err(String::from(
+                "Observed routing has no path information, please select a routing algorithm.",
+            )),
+            _ => Err(format!("Unsupported routing algorithm \"{s}\".")),
+        }
+    }
+}
+
+/// A single step of a route.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub(crate) struct Hop {
+    pub channel: Channel,
+    pub to: usize,
+}
+
+/// Direction along one dimension, `None` if already aligned.
+/// On a torus the shorter way around is taken, ties go the positive way.
+fn along(wraps: bool, size: usize, from: usize, to: usize) -> Option<bool> {
+    if from == to {
+        return None;
+    }
+
+    if wraps {
+        let forward = (to + size - from) % size;
+        Some(forward <= size - forward)
+    } else {
+        Some(from < to)
+    }
+}
+
+fn horizontal(topology: &Topology, from: usize, to: usize) -> Option<Direction> {
+    let (_, from_column) = topology.position(from);
+    let (_, to_column) = topology.position(to);
+    let wraps = topology.kind == TopologyKind::Torus;
+
+    along(wraps, topology.columns, from_column, to_column).map(|forward| {
+        if forward {
+            Direction::East
+        } else {
+            Direction::West
+        }
+    })
+}
+
+fn vertical(topology: &Topology, from: usize, to: usize) -> Option<Direction> {
+    let (_, from_row, _) = topology.layer_position(from);
+    let (_, to_row, _) = topology.layer_position(to);
+    let wraps = topology.kind == TopologyKind::Torus;
+
+    along(wraps, topology.layer_rows(), from_row, to_row).map(|forward| {
+        if forward {
+            Direction::South
+        } else {
+            Direction::North
+        }
+    })
+}
+
+/// Between layers of a stacked mesh, only once aligned within the layer.
+fn between_layers(topology: &Topology, from: usize, to: usize) -> Option<Direction> {
+    let (from_layer, _, _) = topology.layer_position(from);
+    let (to_layer, _, _) = topology.layer_position(to);
+
+    along(false, topology.layers(), from_layer, to_layer).map(|up| {
+        if up {
+            Direction::Up
+        } else {
+            Direction::Down
+        }
+    })
+}
+
+/// Computes the channels a flow from `source` to `destination` traverses.
+/// Meshes, tori and stacked meshes use dimension order routing, changing layer last. Rings take the shorter way around and
+/// irregular topologies the shortest path, regardless of the algorithm.
+pub(crate) fn route(
+    topology: &Topology,
+    algorithm: RoutingAlgorithm,
+    source: usize,
+    destination: usize,
+) -> Result<Vec<Hop>, String> {
+    let core_count = topology.core_count();
+    if source >= core_count || destination >= core_count {
+        return Err(format!(
+            "Cores must be in range 0-{}.",
+            core_count.saturating_sub(1)
+        ));
+    }
+
+    if let TopologyKind::Irregular(_) = topology.kind {
+        return topology
+            .shortest_path(source, destination)
+            .map(|path| {
+                path.into_iter()
+                    .map(|(channel, to)| Hop { channel, to })
+                    .collect()
+            })
+            .ok_or(format!(
+                "Core {destination} cannot be reached from core {source}."
+            ));
+    }
+
+    let mut ret = Vec::new();
+    let mut current = source;
+
+    while current != destination {
+        let direction = match topology.kind {
+            TopologyKind::Ring => along(true, core_count, current, destination).map(|forward| {
+                if forward {
+                    Direction::East
+                } else {
+                    Direction::West
+                }
+            }),
+            _ => match algorithm {
+                RoutingAlgorithm::RowFirst => horizontal(topology, current, destination)
+                    .or(vertical(topology, current, destination)),
+                RoutingAlgorithm::ColumnFirst => vertical(topology, current, destination)
+                    .or(horizontal(topology, current, destination)),
+            }
+            .or(between_layers(topology, current, destination)),
+        }
+        // current != destination guarantees a direction
+        .ok_or("Could not compute route.")?;
+
+        let to = topology
+            .neighbour(current, direction)
+            .ok_or("Route left the system.")?;
+
+        ret.push(Hop {
+            channel: Channel {
+                core: current,
+                direction,
+            },
+            to,
+        });
+        current = to;
+    }
+
+    Ok(ret)
+}
+
+/// Route taken by a task graph edge.
+#[derive(Clone, Debug)]
+pub(crate) struct Flow {
+    pub edge: TaskEdge,
+    pub hops: Vec<Hop>,
+}
+
+/// Routes every task graph edge whose tasks are allocated to cores.
+/// Edges with unallocated tasks don't generate traffic and are skipped.
+pub(crate) fn route_task_graph(
+    snapshot: &SystemSnapshot,
+    topology: &Topology,
+    algorithm: RoutingAlgorithm,
+) -> Result<Vec<Flow>, String> {
+    let mut ret = Vec::with_capacity(snapshot.edges.len());
+
+    for edge in &snapshot.edges {
+        if let (Some(source), Some(destination)) =
+            (snapshot.task_core(edge.from), snapshot.task_core(edge.to))
+        {
+            ret.push(Flow {
+                edge: edge.clone(),
+                hops: route(topology, algorithm, source, destination)?,
+            });
+        }
+    }
+
+    Ok(ret)
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "type", content = "id")]
+pub enum RouteEndpoint {
+    Task(u16),
+    Core(usize),
+}
+
+impl RouteEndpoint {
+    fn core(&self, snapshot: &SystemSnapshot) -> Result<usize, String> {
+        match self {
+            RouteEndpoint::Task(task) => snapshot
+                .task_core(*task)
+                .ok_or(format!("Task {task} is not allocated to any core.")),
+            RouteEndpoint::Core(core) => Ok(*core),
+        }
+    }
+}
+
+/// Polyline through the route and per-hop annotations.
+fn route_markup(topology: &Topology, geometry: &GridGeometry, hops: &[Hop]) -> String {
+    let stroke_width = geometry.stroke_width() * 2.0;
+    let font_size = geometry.font_size();
+    let mut points = String::new();
+    let mut annotations = String::new();
+
+    for (i, hop) in hops.iter().enumerate() {
+        let ((x1, y1), (x2, y2)) = geometry.channel_segment(topology, &hop.channel, hop.to);
+        if i == 0 {
+            let _ = write!(points, "{x1},{y1} ");
+        }
+        let _ = write!(points, "{x2},{y2} ");
+
+        push_text(
+            &mut annotations,
+            (x1 + x2) / 2.0 + stroke_width,
+            (y1 + y2) / 2.0 - stroke_width,
+            font_size,
+            ROUTE_TRACE_COLOUR,
+            format!("{}: {}", i + 1, hop.channel.direction).as_str(),
+        );
+    }
+
+    format!(
+        "<g id=\"routeTrace\"><polyline points=\"{}\" fill=\"none\" stroke=\"{ROUTE_TRACE_COLOUR}\" stroke-width=\"{stroke_width}\" stroke-linejoin=\"round\" stroke-opacity=\"0.8\"/>{annotations}</g>",
+        points.trim_end()
+    )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteTrace {
+    source_core: usize,
+    destination_core: usize,
+    hops: Vec<Hop>,
+    overlay: String,
+}
+
+#[derive(Serialize)]
+pub struct RouteTraceResult {
+    status: ResultStatus,
+    message: String,
+    trace: Option<RouteTrace>,
+    svg: Option<SVGObject>,
+}
+
+/// Computes the route between two tasks or cores and stores it as an overlay.
+/// Returns the render with the route drawn on it.
+#[tauri::command]
+pub fn trace_route(
+    source: RouteEndpoint,
+    destination: RouteEndpoint,
+    algorithm: String,
+    state: tauri::State<State>,
+) -> RouteTraceResult {
+    let mut ret = RouteTraceResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        trace: None,
+        svg: None,
+    };
+
+    let mut document = state.write();
+    let Document {
+        snapshot, overlays, ..
+    } = &mut *document;
+    let (Some(snapshot), Some(geometry)) = (&*snapshot, overlays.geometry) else {
+        ret.message = String::from("Load a system before tracing a route.");
+        return ret;
+    };
+
+    let topology = Topology::from_snapshot(snapshot);
+    let trace = algorithm.parse::<RoutingAlgorithm>().and_then(|algorithm| {
+        let source_core = source.core(snapshot)?;
+        let destination_core = destination.core(snapshot)?;
+        let hops = route(&topology, algorithm, source_core, destination_core)?;
+
+        Ok((source_core, destination_core, hops))
+    });
+
+    match trace {
+        Ok((source_core, destination_core, hops)) => {
+            let overlay = route_markup(&topology, &geometry, &hops);
+            overlays.set(ROUTE_TRACE_LAYER, overlay.clone());
+
+            ret.status = ResultStatus::Ok;
+            ret.message = format!("Route is {} hops long.", hops.len());
+            ret.trace = Some(RouteTrace {
+                source_core,
+                destination_core,
+                hops,
+                overlay,
+            });
+            ret.svg = display_render(&mut document).ok();
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
+
+/// Removes the route trace overlay. Returns the render without it.
+#[tauri::command]
+pub fn clear_route_trace(state: tauri::State<State>) -> SVGResult {
+    let mut ret = SVGResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        svg: None,
+    };
+
+    let mut document = state.write();
+    document.overlays.remove(ROUTE_TRACE_LAYER);
+
+    match display_render(&mut document) {
+        Ok(svg) => {
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Route trace cleared.");
+            ret.svg = Some(svg);
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
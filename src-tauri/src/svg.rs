@@ -1,17 +1,43 @@
 use chrono::Utc;
 use manycore_parser::ManycoreSystem;
-use manycore_svg::{BaseConfiguration, Configuration, UpdateResult, SVG};
+use manycore_svg::{BaseConfiguration, Configuration, FieldConfiguration, UpdateResult, SVG};
 
 use serde::Serialize;
+use tauri::Window;
 
-use crate::{result_status::ResultStatus, State};
+use crate::{
+    document::Document,
+    export::WholeConfiguration,
+    faults::refresh_faults,
+    fonts::{family_font_faces, has_family, with_font_faces, with_font_family},
+    legend::refresh_legend,
+    migration::CONFIGURATION_VERSION,
+    overlay::{topology_markup, GridGeometry},
+    progress::{ProgressReporter, Stage},
+    result_status::ResultStatus,
+    system::SystemSnapshot,
+    topology::{Topology, TopologyKind},
+    State,
+};
+
+static TOPOLOGY_LAYER: &'static str = "topology";
 
 #[derive(Serialize)]
 pub struct SVGObject {
-    content: String,
+    pub content: String,
     timestamp: String,
 }
 
+impl SVGObject {
+    /// Wraps serialised SVG content, timestamped now.
+    pub(crate) fn new(content: String) -> Self {
+        Self {
+            content,
+            timestamp: Utc::now().to_string(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct SVGResult {
     pub status: ResultStatus,
@@ -24,91 +50,338 @@ pub struct SVGUpdateResult {
     status: ResultStatus,
     message: String,
     update: Option<UpdateResult>,
+    /// Whole render, when decorations around it changed.
+    svg: Option<SVGObject>,
 }
 
-pub fn generate_svg(ret: &mut SVGResult, state: &tauri::State<State>, manycore: &ManycoreSystem) {
-    let svg = SVG::try_from(manycore);
+/// Refreshes the [`SystemSnapshot`] after the loaded system changed.
+pub(crate) fn refresh_snapshot(document: &mut Document, manycore: &ManycoreSystem) {
+    document.snapshot = SystemSnapshot::from_manycore(manycore)
+        .ok()
+        .map(|snapshot| SystemSnapshot {
+            topology: document.topology.clone(),
+            ..snapshot
+        });
+}
 
-    if let Ok(svg) = svg {
-        if let Ok(mut svg_mutex) = state.svg.lock() {
-            match String::try_from(&svg) {
-                Ok(svg_string) => {
-                    let _ = svg_mutex.insert(svg);
+/// Refreshes the [`SystemSnapshot`] and drops overlays belonging to a previous render.
+fn refresh_derived_state(document: &mut Document, manycore: &ManycoreSystem, svg_string: &str) {
+    refresh_snapshot(document, manycore);
 
-                    ret.status = ResultStatus::Ok;
-                    ret.message = String::from("Successfully generated SVG");
-                    ret.svg = Some(SVGObject {
-                        content: svg_string,
-                        timestamp: Utc::now().to_string(),
-                    });
-                }
-                Err(e) => {
-                    ret.message = e.to_string();
-                }
-            }
+    let geometry = document.snapshot.as_ref().and_then(|snapshot| {
+        GridGeometry::from_svg_str(svg_string, snapshot.rows, snapshot.columns)
+    });
+    document.overlays.reset(geometry);
 
-            return;
+    // Channels the base render can't draw
+    if let (Some(snapshot), Some(geometry)) = (&document.snapshot, geometry) {
+        if let Some(markup) = topology_markup(&Topology::from_snapshot(snapshot), &geometry) {
+            document.overlays.set(TOPOLOGY_LAYER, markup);
         }
-        // Couldn't lock mutex
-        ret.message = String::from("Could not update render, please try again.");
-    } else if let Err(svg_error) = svg {
-        // Couldn't convert svg
-        ret.message = svg_error.to_string();
     }
+
+    refresh_legend(document);
+    // Faults outlive re-renders of the same system
+    let _ = refresh_faults(document);
 }
 
-#[tauri::command]
-pub fn get_svg(state: tauri::State<State>) -> SVGResult {
-    let mut ret = SVGResult {
-        status: ResultStatus::Error,
-        message: String::from("Something went wrong, please try again."),
-        svg: None,
+/// Adds the overlays, the configured font faces and the [`Appearance`](crate::theme::Appearance)
+/// to a serialised render, as shown in the app.
+pub(crate) fn display_string(document: &Document, svg_string: String) -> String {
+    let svg_string = document.overlays.splice(svg_string);
+
+    let svg_string = with_font_family(svg_string, document.configuration.as_ref());
+    let svg_string = match &document.font_faces {
+        Some(font_faces) => with_font_faces(svg_string, font_faces.as_str()),
+        None => svg_string,
     };
 
-    if let Ok(manycore_mutex) = state.manycore.lock() {
-        // Reference inner value of mutex without taking ownership.
-        // Value in option is preserved.
-        match &*manycore_mutex {
-            Some(manycore) => {
-                generate_svg(&mut ret, &state, manycore);
-            }
-            None => {
-                ret.message = String::from("Load a system before generating a render.");
-            }
+    document.appearance.apply(svg_string)
+}
+
+/// The stored render as shown in the app, e.g. after its overlays changed.
+pub(crate) fn display_render(document: &mut Document) -> Result<SVGObject, String> {
+    let svg_string = match &document.svg {
+        Some(svg) => document.cache.serialise(svg)?,
+        None => return Err(String::from("Load a system before generating a render.")),
+    };
+
+    Ok(SVGObject::new(display_string(document, svg_string)))
+}
+
+pub fn generate_svg(ret: &mut SVGResult, document: &mut Document, manycore: &ManycoreSystem) {
+    match build_svg(manycore, None) {
+        Ok((svg, svg_string)) => store_svg(ret, document, manycore, svg, svg_string),
+        Err(e) => ret.message = e,
+    }
+}
+
+/// Builds and serialises the render of `manycore`, reporting each stage.
+fn build_svg(
+    manycore: &ManycoreSystem,
+    progress: Option<&ProgressReporter>,
+) -> Result<(SVG, String), String> {
+    let stage = |stage: Stage| progress.map_or(Ok(()), |progress| progress.stage(stage));
+
+    stage(Stage::BuildingSvg)?;
+    let svg = SVG::try_from(manycore).map_err(|e| e.to_string())?;
+
+    stage(Stage::Serialising)?;
+    let svg_string = String::try_from(&svg).map_err(|e| e.to_string())?;
+
+    Ok((svg, svg_string))
+}
+
+/// Stores the render of `manycore` and what is derived from it.
+fn store_svg(
+    ret: &mut SVGResult,
+    document: &mut Document,
+    manycore: &ManycoreSystem,
+    svg: SVG,
+    svg_string: String,
+) {
+    let _ = document.svg.insert(svg);
+    document.cache.invalidate();
+    document.cache.store_svg_string(svg_string.clone());
+    refresh_derived_state(document, manycore, svg_string.as_str());
+
+    ret.status = ResultStatus::Ok;
+    ret.message = String::from("Successfully generated SVG");
+    ret.svg = Some(SVGObject::new(display_string(document, svg_string)));
+}
+
+/// Removes the routing options of `configuration` if the system is not a mesh: the render computes
+/// routing loads over a mesh only. Returns a message naming the removed options, if any.
+pub(crate) fn without_unsupported_routing(
+    configuration: &mut Configuration,
+    topology: &TopologyKind,
+) -> Option<String> {
+    if *topology == TopologyKind::Mesh {
+        return None;
+    }
+
+    let mut removed = Vec::new();
+    configuration.channel_config_mut().retain(|key, option| {
+        let routing = matches!(option, FieldConfiguration::Routing(_));
+        if routing {
+            removed.push(key.clone());
         }
+
+        !routing
+    });
+
+    match removed.is_empty() {
+        true => None,
+        false => Some(format!(
+            "Routing loads are only computed for meshes, {} topologies can't display {}.",
+            topology.name(),
+            removed.join(", ")
+        )),
     }
+}
 
-    ret
+/// Re-applies the last configuration to the stored [`SVG`] after the loaded system changed.
+/// Only the configurable groups of the render are regenerated.
+/// Returns [`None`] if no configuration has been applied yet.
+pub(crate) fn reapply_configuration(
+    document: &mut Document,
+    manycore: &mut ManycoreSystem,
+) -> Option<Result<UpdateResult, String>> {
+    match (&mut document.svg, &mut document.configuration) {
+        (Some(svg), Some(whole_configuration)) => {
+            // Both the system and the render change
+            document.cache.invalidate();
+            let _ = without_unsupported_routing(
+                &mut whole_configuration.configuration,
+                &document.topology,
+            );
+
+            Some(
+                svg.update_configurable_information(
+                    manycore,
+                    &mut whole_configuration.configuration,
+                    &whole_configuration.base_configuration,
+                )
+                .map_err(|e| e.to_string()),
+            )
+        }
+        _ => None,
+    }
 }
 
+/// Renders the loaded system, reporting progress to `window`.
+/// Runs on a blocking thread, the render is dropped if another system is loaded meanwhile.
 #[tauri::command]
-pub fn update_svg(
+pub async fn get_svg(window: Window, state: tauri::State<'_, State>) -> Result<SVGResult, ()> {
+    let state = state.inner().clone();
+    let progress = ProgressReporter::new(window, &state.load_generation);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut ret = SVGResult {
+            status: ResultStatus::Error,
+            message: String::from("Something went wrong, please try again."),
+            svg: None,
+        };
+
+        display_svg(&mut ret, &state, Some(&progress));
+
+        if let ResultStatus::Ok = ret.status {
+            progress.done();
+        }
+
+        ret
+    })
+    .await
+    .map_err(|_| ())
+}
+
+/// Serves the render of the loaded system as shown in the app, rendering it only if it changed
+/// since the last time. The render is built under a read lock and only stored if the system
+/// didn't change meanwhile.
+pub(crate) fn display_svg(ret: &mut SVGResult, state: &State, progress: Option<&ProgressReporter>) {
+    let generation = state.load_generation.current();
+    let (revision, built) = {
+        let document = state.read();
+        let Some(manycore) = &document.manycore else {
+            ret.message = String::from("Load a system before generating a render.");
+            return;
+        };
+
+        // Nothing changed since the last render
+        if let Some(svg_string) = document.cache.svg_string() {
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Successfully generated SVG");
+            ret.svg = Some(SVGObject::new(display_string(
+                &document,
+                svg_string.clone(),
+            )));
+            return;
+        }
+
+        (document.cache.revision(), build_svg(manycore, progress))
+    };
+    let (svg, svg_string) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            ret.message = e;
+            return;
+        }
+    };
+
+    let mut document = state.write();
+    if let Err(e) = progress.map_or(Ok(()), ProgressReporter::check) {
+        ret.message = e;
+        return;
+    }
+    // Loads bump the generation, other changes to the system the cache revision
+    if state.load_generation.current() != generation || document.cache.revision() != revision {
+        ret.message = String::from("The system changed while it was rendered, please try again.");
+        return;
+    }
+
+    // Take the system out while the rest of the document is updated
+    let document = &mut *document;
+    if let Some(manycore) = document.manycore.take() {
+        store_svg(ret, document, &manycore, svg, svg_string);
+        let _ = document.manycore.insert(manycore);
+    }
+}
+
+/// Applies a configuration to the render, reporting progress to `window`.
+/// Runs on a blocking thread. The font family applies to the whole render, which must be fetched
+/// again with [`get_svg`] when it changes.
+#[tauri::command]
+pub async fn update_svg(
+    configuration: Configuration,
+    base_configuration: BaseConfiguration,
+    font_family: Option<String>,
+    window: Window,
+    state: tauri::State<'_, State>,
+) -> Result<SVGUpdateResult, ()> {
+    let state = state.inner().clone();
+    let progress = ProgressReporter::new(window, &state.load_generation);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        apply_configuration(
+            configuration,
+            base_configuration,
+            font_family,
+            &state,
+            &progress,
+        )
+    })
+    .await
+    .map_err(|_| ())
+}
+
+/// Applies a configuration to the stored render, unless another system was loaded since
+/// `progress` started.
+fn apply_configuration(
     mut configuration: Configuration,
     base_configuration: BaseConfiguration,
-    state: tauri::State<State>,
+    font_family: Option<String>,
+    state: &State,
+    progress: &ProgressReporter,
 ) -> SVGUpdateResult {
     let mut ret = SVGUpdateResult {
         status: ResultStatus::Error,
         message: String::from("Something went wrong, please try again."),
         update: None,
+        svg: None,
+    };
+
+    // Faces are read before locking the document
+    let font_faces = match &font_family {
+        Some(family) => {
+            let font_database = state.font_database();
+            if !has_family(&font_database, family) {
+                ret.message = format!("Font {family} is not loaded.");
+                return ret;
+            }
+
+            Some(family_font_faces(&font_database, family))
+        }
+        None => None,
     };
 
-    if let (Ok(mut manycore_mutex), Ok(mut svg_mutex)) = (state.manycore.lock(), state.svg.lock()) {
-        if let (Some(manycore), Some(svg)) = (&mut *manycore_mutex, &mut *svg_mutex) {
-            match svg.update_configurable_information(
-                manycore,
-                &mut configuration,
-                &base_configuration,
-            ) {
-                Ok(update) => {
-                    ret.status = ResultStatus::Ok;
-                    ret.message = String::from("Successfully generated SVG");
-                    ret.update = Some(update);
-
-                    return ret;
+    let mut document = state.write();
+    let document = &mut *document;
+    if let (Some(manycore), Some(svg)) = (&mut document.manycore, &mut document.svg) {
+        // The render may belong to a system loaded after this update was requested
+        if let Err(e) = progress.stage(Stage::BuildingSvg) {
+            ret.message = e;
+            return ret;
+        }
+        document.cache.invalidate_svg();
+        let unsupported = without_unsupported_routing(&mut configuration, &document.topology);
+
+        match svg.update_configurable_information(manycore, &mut configuration, &base_configuration)
+        {
+            Ok(update) => {
+                ret.status = ResultStatus::Ok;
+                ret.message = unsupported.unwrap_or(String::from("Successfully generated SVG"));
+                ret.update = Some(update);
+
+                // Remember what was applied, the legend is generated from it
+                let _ = document.configuration.insert(WholeConfiguration {
+                    version: CONFIGURATION_VERSION,
+                    base_configuration,
+                    configuration,
+                    font_family,
+                    // Presets are kept in the document
+                    export_presets: Vec::new(),
+                });
+                document.font_faces = font_faces;
+                refresh_legend(document);
+                // Partial updates would drop the room made for decorations
+                if document.overlays.has_decorations() {
+                    ret.svg = display_render(document).ok();
                 }
-                Err(e) => ret.message = e.to_string(),
+                progress.done();
+
+                return ret;
             }
+            Err(e) => ret.message = e.to_string(),
         }
     }
 
@@ -0,0 +1,712 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::Display,
+    fs,
+    str::FromStr,
+};
+
+use manycore_parser::ManycoreSystem;
+use quick_xml::{
+    events::{BytesEnd, BytesStart, Event},
+    Reader, Writer,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::topology::{Channel, Direction, TopologyKind, VERTICAL_DIRECTIONS};
+
+// XML element and attribute names used by the ManyCore XML format.
+static CORE_ELEMENT: &'static [u8] = b"Core";
+static ROUTER_ELEMENT: &'static [u8] = b"Router";
+static CHANNEL_ELEMENT: &'static [u8] = b"Channel";
+static TASK_ELEMENT: &'static [u8] = b"Task";
+static EDGE_ELEMENT: &'static [u8] = b"Edge";
+static ROWS_ATTRIBUTE: &'static str = "rows";
+static COLUMNS_ATTRIBUTE: &'static str = "columns";
+static ROUTING_ATTRIBUTES: &[&str] = &["routingAlgorithm", "routingAlgo"];
+static ID_ATTRIBUTE: &'static str = "id";
+pub(crate) static ALLOCATED_TASK_ATTRIBUTE: &'static str = "allocatedTask";
+static DIRECTION_ATTRIBUTE: &'static str = "direction";
+static FROM_ATTRIBUTE: &'static str = "from";
+static TO_ATTRIBUTE: &'static str = "to";
+static COMMUNICATION_COST_ATTRIBUTE: &'static str = "communicationCost";
+// Topology extension, not part of the ManyCore schema so the parser drops it
+static TOPOLOGY_ATTRIBUTE: &'static str = "topology";
+static LAYERS_ATTRIBUTE: &'static str = "layers";
+static LINKS_ELEMENT: &'static str = "Links";
+static LINK_ELEMENT: &'static str = "Link";
+
+/// Kind of system element.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementKind {
+    Core,
+    Router,
+    Channel,
+}
+
+/// Identifies a core, router or channel.
+/// Textual form is `core:<id>`, `router:<id>` or `channel:<id>:<direction>`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum ElementId {
+    Core(usize),
+    Router(usize),
+    Channel(Channel),
+}
+
+impl FromStr for ElementId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid element id \"{s}\".");
+        let mut parts = s.split(':');
+        let kind = parts.next().ok_or_else(invalid)?;
+        let core = parts
+            .next()
+            .ok_or_else(invalid)?
+            .parse::<usize>()
+            .map_err(|_| invalid())?;
+
+        let ret = match kind {
+            "core" => ElementId::Core(core),
+            "router" => ElementId::Router(core),
+            "channel" => ElementId::Channel(Channel {
+                core,
+                direction: parts.next().ok_or_else(invalid)?.parse::<Direction>()?,
+            }),
+            _ => return Err(invalid()),
+        };
+
+        match parts.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(ret),
+        }
+    }
+}
+
+impl Display for ElementId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementId::Core(core) => write!(f, "core:{core}"),
+            ElementId::Router(core) => write!(f, "router:{core}"),
+            ElementId::Channel(channel) => {
+                write!(f, "channel:{}:{}", channel.core, channel.direction)
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for ElementId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ElementId> for String {
+    fn from(value: ElementId) -> Self {
+        value.to_string()
+    }
+}
+
+/// Sets an attribute of a system element. A missing value removes the attribute.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AttributeUpdate {
+    pub element: ElementId,
+    pub attribute: String,
+    pub value: Option<String>,
+}
+
+/// A task graph edge as read from the system XML.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskEdge {
+    pub from: u16,
+    pub to: u16,
+    pub communication_cost: u64,
+}
+
+/// A core, its router and its channels as read from the system XML.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CoreSnapshot {
+    pub id: usize,
+    pub allocated_task: Option<u16>,
+    pub attributes: BTreeMap<String, String>,
+    pub router: BTreeMap<String, String>,
+    pub channels: BTreeMap<Direction, BTreeMap<String, String>>,
+}
+
+/// Plain view of a [`ManycoreSystem`] used by the analyses implemented in this crate.
+/// Built from the serialised XML so it only depends on the ManyCore XML format.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SystemSnapshot {
+    pub rows: usize,
+    pub columns: usize,
+    pub routing_algorithm: Option<String>,
+    /// Cores, indexed by id.
+    pub cores: Vec<CoreSnapshot>,
+    /// Task ids, in document order.
+    pub tasks: Vec<u16>,
+    pub edges: Vec<TaskEdge>,
+    /// Not part of the serialised system, see [`read_topology`].
+    pub topology: TopologyKind,
+}
+
+impl SystemSnapshot {
+    /// Core the given task is allocated to, if any.
+    pub fn task_core(&self, task: u16) -> Option<usize> {
+        self.cores
+            .iter()
+            .find(|core| core.allocated_task == Some(task))
+            .map(|core| core.id)
+    }
+
+    /// Attributes of the given element, if it exists.
+    pub fn attributes(&self, element: &ElementId) -> Option<&BTreeMap<String, String>> {
+        match element {
+            ElementId::Core(core) => self.cores.get(*core).map(|core| &core.attributes),
+            ElementId::Router(core) => self.cores.get(*core).map(|core| &core.router),
+            ElementId::Channel(channel) => self
+                .cores
+                .get(channel.core)
+                .and_then(|core| core.channels.get(&channel.direction)),
+        }
+    }
+
+    fn attributes_mut(&mut self, element: &ElementId) -> Option<&mut BTreeMap<String, String>> {
+        match element {
+            ElementId::Core(core) => self.cores.get_mut(*core).map(|core| &mut core.attributes),
+            ElementId::Router(core) => self.cores.get_mut(*core).map(|core| &mut core.router),
+            ElementId::Channel(channel) => self
+                .cores
+                .get_mut(channel.core)
+                .and_then(|core| core.channels.get_mut(&channel.direction)),
+        }
+    }
+
+    /// Applies attribute updates, keeping the snapshot in step with a system patched by
+    /// [`patch_in_place`]. Updates to missing elements are skipped.
+    pub fn apply(&mut self, updates: &[AttributeUpdate]) {
+        for update in updates {
+            let Some(attributes) = self.attributes_mut(&update.element) else {
+                continue;
+            };
+
+            match &update.value {
+                Some(value) => {
+                    attributes.insert(update.attribute.clone(), value.clone());
+                }
+                None => {
+                    attributes.remove(&update.attribute);
+                }
+            }
+
+            if let ElementId::Core(core) = update.element {
+                if update.attribute == ALLOCATED_TASK_ATTRIBUTE {
+                    self.cores[core].allocated_task = update
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.parse::<u16>().ok());
+                }
+            }
+        }
+    }
+
+    /// Every element of the given kind paired with the numeric value of `attribute`.
+    /// Elements without the attribute, or with a non numeric value, are skipped.
+    pub fn numeric_values(&self, kind: ElementKind, attribute: &str) -> Vec<(ElementId, f64)> {
+        let mut ret = Vec::new();
+
+        for core in &self.cores {
+            let elements = match kind {
+                ElementKind::Core => vec![ElementId::Core(core.id)],
+                ElementKind::Router => vec![ElementId::Router(core.id)],
+                ElementKind::Channel => core
+                    .channels
+                    .keys()
+                    .map(|direction| {
+                        ElementId::Channel(Channel {
+                            core: core.id,
+                            direction: *direction,
+                        })
+                    })
+                    .collect(),
+            };
+
+            for element in elements {
+                if let Some(value) = self
+                    .attributes(&element)
+                    .and_then(|attributes| attributes.get(attribute))
+                    .and_then(|value| value.parse::<f64>().ok())
+                {
+                    ret.push((element, value));
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Parses a [`SystemSnapshot`] out of a ManyCore XML string.
+    pub fn from_xml(xml: &str) -> Result<Self, String> {
+        let mut reader = Reader::from_str(xml);
+        let mut snapshot = SystemSnapshot::default();
+        // Core whose children we are currently reading
+        let mut current_core: Option<CoreSnapshot> = None;
+        // Task whose edges we are currently reading (older task graph layout)
+        let mut current_task: Option<u16> = None;
+        let mut root_read = false;
+
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                    let attributes = read_attributes(&e)?;
+                    let name = e.name();
+
+                    if !root_read {
+                        root_read = true;
+                        snapshot.rows = parse_attribute(&attributes, ROWS_ATTRIBUTE)?;
+                        snapshot.columns = parse_attribute(&attributes, COLUMNS_ATTRIBUTE)?;
+                        snapshot.routing_algorithm = ROUTING_ATTRIBUTES
+                            .iter()
+                            .find_map(|key| attributes.get(*key).cloned());
+                    } else if name.as_ref() == CORE_ELEMENT {
+                        if let Some(core) = current_core.take() {
+                            snapshot.cores.push(core);
+                        }
+
+                        let mut core = CoreSnapshot {
+                            id: parse_attribute(&attributes, ID_ATTRIBUTE)?,
+                            ..Default::default()
+                        };
+                        if attributes.contains_key(ALLOCATED_TASK_ATTRIBUTE) {
+                            core.allocated_task =
+                                Some(parse_attribute(&attributes, ALLOCATED_TASK_ATTRIBUTE)?);
+                        }
+                        core.attributes = attributes;
+
+                        current_core = Some(core);
+                    } else if name.as_ref() == ROUTER_ELEMENT {
+                        if let Some(core) = current_core.as_mut() {
+                            core.router = attributes;
+                        }
+                    } else if name.as_ref() == CHANNEL_ELEMENT {
+                        if let Some(core) = current_core.as_mut() {
+                            let direction = attributes
+                                .get(DIRECTION_ATTRIBUTE)
+                                .ok_or("Found a channel without a direction.")?
+                                .parse::<Direction>()?;
+                            core.channels.insert(direction, attributes);
+                        }
+                    } else if name.as_ref() == TASK_ELEMENT {
+                        let task = parse_attribute(&attributes, ID_ATTRIBUTE)?;
+                        snapshot.tasks.push(task);
+                        current_task = Some(task);
+                    } else if name.as_ref() == EDGE_ELEMENT {
+                        let from = match current_task {
+                            Some(task) if !attributes.contains_key(FROM_ATTRIBUTE) => task,
+                            _ => parse_attribute(&attributes, FROM_ATTRIBUTE)?,
+                        };
+
+                        snapshot.edges.push(TaskEdge {
+                            from,
+                            to: parse_attribute(&attributes, TO_ATTRIBUTE)?,
+                            communication_cost: parse_attribute(
+                                &attributes,
+                                COMMUNICATION_COST_ATTRIBUTE,
+                            )?,
+                        });
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    if e.name().as_ref() == TASK_ELEMENT {
+                        current_task = None;
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(format!("Could not read system XML: {e}")),
+                _ => {}
+            }
+        }
+
+        if let Some(core) = current_core.take() {
+            snapshot.cores.push(core);
+        }
+        snapshot.cores.sort_by_key(|core| core.id);
+
+        Ok(snapshot)
+    }
+
+    /// Serialises the provided [`ManycoreSystem`] and reads it back as a [`SystemSnapshot`].
+    pub fn from_manycore(manycore: &ManycoreSystem) -> Result<Self, String> {
+        Self::from_xml(serialise(manycore)?.as_str())
+    }
+}
+
+fn read_attributes(element: &BytesStart) -> Result<BTreeMap<String, String>, String> {
+    let mut ret = BTreeMap::new();
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+
+        ret.insert(key, value.into_owned());
+    }
+
+    Ok(ret)
+}
+
+fn parse_attribute<T: std::str::FromStr>(
+    attributes: &BTreeMap<String, String>,
+    key: &str,
+) -> Result<T, String> {
+    attributes
+        .get(key)
+        .ok_or(format!("Missing \"{key}\" attribute."))?
+        .parse::<T>()
+        .map_err(|_| format!("Invalid \"{key}\" attribute."))
+}
+
+/// Reads the topology of a ManyCore XML system from the root `topology` attribute.
+/// Irregular topologies list their channels as `<Link from="0" direction="East" to="5"/>`
+/// inside a `<Links>` element. Stacked meshes give their number of layers in the root `layers` attribute,
+/// which must divide the number of rows. Systems without the attribute are meshes.
+/// Links must join existing cores, irregular topologies have no `Up` or `Down` channels.
+pub(crate) fn read_topology(xml: &str) -> Result<TopologyKind, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut kind: Option<String> = None;
+    let mut rows = 0usize;
+    let mut columns = 0usize;
+    let mut layers: Option<usize> = None;
+    let mut links = BTreeMap::new();
+    let mut root_read = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let attributes = read_attributes(&e)?;
+
+                if !root_read {
+                    root_read = true;
+                    kind = attributes.get(TOPOLOGY_ATTRIBUTE).cloned();
+                    rows = parse_attribute(&attributes, ROWS_ATTRIBUTE)?;
+                    columns = parse_attribute(&attributes, COLUMNS_ATTRIBUTE)?;
+                    if attributes.contains_key(LAYERS_ATTRIBUTE) {
+                        layers = Some(parse_attribute(&attributes, LAYERS_ATTRIBUTE)?);
+                    }
+                } else if e.name().as_ref() == LINK_ELEMENT.as_bytes() {
+                    let channel = Channel {
+                        core: parse_attribute(&attributes, FROM_ATTRIBUTE)?,
+                        direction: parse_attribute(&attributes, DIRECTION_ATTRIBUTE)?,
+                    };
+                    let to = parse_attribute::<usize>(&attributes, TO_ATTRIBUTE)?;
+
+                    for core in [channel.core, to] {
+                        if core >= rows * columns {
+                            return Err(format!(
+                                "Link from {} {} to {to} refers to core {core}, the system has {} cores.",
+                                channel.core,
+                                channel.direction,
+                                rows * columns
+                            ));
+                        }
+                    }
+                    if VERTICAL_DIRECTIONS.contains(&channel.direction) {
+                        return Err(format!(
+                            "Link from {} {} to {to}: irregular topologies can't have {} channels.",
+                            channel.core, channel.direction, channel.direction
+                        ));
+                    }
+
+                    links.insert(channel, to);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("Could not read system XML: {e}")),
+            _ => {}
+        }
+    }
+
+    match kind.as_deref() {
+        None | Some("Mesh") => Ok(TopologyKind::Mesh),
+        Some("Torus") => Ok(TopologyKind::Torus),
+        Some("Ring") => Ok(TopologyKind::Ring),
+        Some("Irregular") => Ok(TopologyKind::Irregular(links)),
+        Some("Stacked") => match layers {
+            Some(layers) if layers > 0 && rows % layers == 0 => Ok(TopologyKind::Stacked(layers)),
+            Some(layers) => Err(format!(
+                "{rows} rows cannot be split in {layers} layers of equal size."
+            )),
+            None => Err(String::from(
+                "Stacked systems must provide their number of layers.",
+            )),
+        },
+        Some(kind) => Err(format!("Unsupported topology \"{kind}\".")),
+    }
+}
+
+/// Adds the topology attribute, and links of irregular topologies, to a ManyCore XML string.
+pub(crate) fn write_topology(xml: &str, topology: &TopologyKind) -> Result<String, String> {
+    if *topology == TopologyKind::Mesh {
+        return Ok(xml.to_string());
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Vec::with_capacity(xml.len()));
+    let mut depth = 0usize;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(mut e)) => {
+                if depth == 0 {
+                    e.push_attribute((TOPOLOGY_ATTRIBUTE, topology.name()));
+                    if let TopologyKind::Stacked(layers) = topology {
+                        e.push_attribute((LAYERS_ATTRIBUTE, layers.to_string().as_str()));
+                    }
+                }
+                depth += 1;
+                Event::Start(e)
+            }
+            Ok(Event::End(e)) => {
+                depth = depth.saturating_sub(1);
+
+                if let (0, TopologyKind::Irregular(links)) = (depth, topology) {
+                    let write_err =
+                        |e: quick_xml::Error| format!("Could not write system XML: {e}");
+                    writer
+                        .write_event(Event::Start(BytesStart::new(LINKS_ELEMENT)))
+                        .map_err(write_err)?;
+                    for (channel, to) in links {
+                        let mut link = BytesStart::new(LINK_ELEMENT);
+                        link.push_attribute((FROM_ATTRIBUTE, channel.core.to_string().as_str()));
+                        link.push_attribute((
+                            DIRECTION_ATTRIBUTE,
+                            channel.direction.to_string().as_str(),
+                        ));
+                        link.push_attribute((TO_ATTRIBUTE, to.to_string().as_str()));
+                        writer.write_event(Event::Empty(link)).map_err(write_err)?;
+                    }
+                    writer
+                        .write_event(Event::End(BytesEnd::new(LINKS_ELEMENT)))
+                        .map_err(write_err)?;
+                }
+
+                Event::End(e)
+            }
+            Ok(event) => event,
+            Err(e) => return Err(format!("Could not read system XML: {e}")),
+        };
+
+        writer
+            .write_event(event)
+            .map_err(|e| format!("Could not write system XML: {e}"))?;
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+}
+
+/// Serialises a [`ManycoreSystem`] to a pretty printed XML string.
+pub(crate) fn serialise(manycore: &ManycoreSystem) -> Result<String, String> {
+    let mut xml_string = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml_string);
+    serializer.indent(' ', 4);
+    manycore.serialize(serializer).map_err(|e| e.to_string())?;
+
+    Ok(xml_string)
+}
+
+/// Parses a [`ManycoreSystem`] from an XML string, going through a temporary file.
+pub(crate) fn reparse(xml: &str) -> Result<ManycoreSystem, String> {
+    let file_path = env::temp_dir().join(Uuid::new_v4().to_string());
+    fs::write(&file_path, xml).map_err(|e| format!("Could not write temporary file: {e}"))?;
+
+    let manycore_parse_res =
+        ManycoreSystem::parse_file(file_path.to_string_lossy().to_string().as_str())
+            .map_err(|e| e.to_string());
+
+    // Delete temporary file, doesn't particularly matter if we fail tho
+    let _ = fs::remove_file(file_path);
+
+    manycore_parse_res
+}
+
+/// Text attributes of an element of `manycore`. Attributes the parser reads into typed fields,
+/// such as ids or allocated tasks, are not in there.
+fn manycore_attributes_mut<'a>(
+    manycore: &'a mut ManycoreSystem,
+    element: &ElementId,
+) -> Option<&'a mut BTreeMap<String, String>> {
+    let id = match element {
+        ElementId::Core(core) | ElementId::Router(core) => *core,
+        ElementId::Channel(channel) => channel.core,
+    };
+    let core = manycore
+        .cores_mut()
+        .list_mut()
+        .iter_mut()
+        .find(|core| *core.id() as usize == id)?;
+
+    match element {
+        ElementId::Core(_) => core.other_attributes_mut().as_mut(),
+        ElementId::Router(_) => core.router_mut().other_attributes_mut().as_mut(),
+        ElementId::Channel(channel) => {
+            let direction = channel.direction.to_string();
+            core.channels_mut()
+                .channel_mut()
+                .iter_mut()
+                // The parser serialises directions by name
+                .find(|(key, _)| {
+                    serde_json::to_value(key)
+                        .is_ok_and(|key| key.as_str() == Some(direction.as_str()))
+                })
+                .and_then(|(_, channel)| channel.other_attributes_mut().as_mut())
+        }
+    }
+}
+
+/// Applies attribute updates to the loaded [`ManycoreSystem`] without reparsing it.
+/// Only value changes of existing text attributes that keep their type (number or text) can be
+/// applied like this, anything else changes what the parser derives from the system.
+/// Returns `false`, leaving `manycore` untouched, unless every update can be applied; those
+/// must go through [`patch`] and [`reparse`].
+pub(crate) fn patch_in_place(manycore: &mut ManycoreSystem, updates: &[AttributeUpdate]) -> bool {
+    let is_number = |value: &str| value.parse::<f64>().is_ok();
+
+    let in_place = updates.iter().all(|update| {
+        let Some(value) = &update.value else {
+            return false;
+        };
+
+        manycore_attributes_mut(manycore, &update.element)
+            .and_then(|attributes| attributes.get(&update.attribute))
+            .is_some_and(|current| is_number(current) == is_number(value))
+    });
+    if !in_place {
+        return false;
+    }
+
+    for update in updates {
+        if let (Some(attributes), Some(value)) = (
+            manycore_attributes_mut(manycore, &update.element),
+            &update.value,
+        ) {
+            attributes.insert(update.attribute.clone(), value.clone());
+        }
+    }
+
+    true
+}
+
+/// Applies attribute updates to a ManyCore XML string.
+/// Fails if an update targets an element that does not exist.
+pub(crate) fn patch(xml: &str, updates: &[AttributeUpdate]) -> Result<String, String> {
+    // Group updates by element, later updates win
+    let mut pending: BTreeMap<ElementId, Vec<&AttributeUpdate>> = BTreeMap::new();
+    for update in updates {
+        pending.entry(update.element).or_default().push(update);
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Vec::with_capacity(xml.len()));
+    let mut current_core: Option<usize> = None;
+    let mut applied = BTreeSet::new();
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(e)) => {
+                Event::Start(patch_element(e, &pending, &mut current_core, &mut applied)?)
+            }
+            Ok(Event::Empty(e)) => {
+                Event::Empty(patch_element(e, &pending, &mut current_core, &mut applied)?)
+            }
+            Ok(event) => event,
+            Err(e) => return Err(format!("Could not read system XML: {e}")),
+        };
+
+        writer
+            .write_event(event)
+            .map_err(|e| format!("Could not write system XML: {e}"))?;
+    }
+
+    if let Some(missing) = pending.keys().find(|element| !applied.contains(*element)) {
+        return Err(format!("Element \"{missing}\" does not exist."));
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+}
+
+/// Rewrites the attributes of a single element if it has pending updates.
+fn patch_element<'a>(
+    element: BytesStart<'a>,
+    pending: &BTreeMap<ElementId, Vec<&AttributeUpdate>>,
+    current_core: &mut Option<usize>,
+    applied: &mut BTreeSet<ElementId>,
+) -> Result<BytesStart<'a>, String> {
+    let name = element.name();
+    let id = if name.as_ref() == CORE_ELEMENT {
+        let attributes = read_attributes(&element)?;
+        let core = parse_attribute(&attributes, ID_ATTRIBUTE)?;
+        *current_core = Some(core);
+
+        Some(ElementId::Core(core))
+    } else if name.as_ref() == ROUTER_ELEMENT {
+        current_core.map(ElementId::Router)
+    } else if name.as_ref() == CHANNEL_ELEMENT {
+        let attributes = read_attributes(&element)?;
+        match (current_core, attributes.get(DIRECTION_ATTRIBUTE)) {
+            (Some(core), Some(direction)) => Some(ElementId::Channel(Channel {
+                core: *core,
+                direction: direction.parse()?,
+            })),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let Some((id, updates)) = id.and_then(|id| pending.get(&id).map(|updates| (id, updates)))
+    else {
+        return Ok(element);
+    };
+
+    // Keep attribute order stable, append new attributes at the end
+    let mut attributes = Vec::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        attributes.push((
+            String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            attribute
+                .unescape_value()
+                .map_err(|e| e.to_string())?
+                .into_owned(),
+        ));
+    }
+
+    for update in updates {
+        let position = attributes
+            .iter()
+            .position(|(key, _)| *key == update.attribute);
+
+        match (&update.value, position) {
+            (Some(value), Some(position)) => attributes[position].1 = value.clone(),
+            (Some(value), None) => attributes.push((update.attribute.clone(), value.clone())),
+            (None, Some(position)) => {
+                attributes.remove(position);
+            }
+            (None, None) => {}
+        }
+    }
+    applied.insert(id);
+
+    let mut ret = BytesStart::new(String::from_utf8_lossy(name.as_ref()).into_owned());
+    for (key, value) in &attributes {
+        ret.push_attribute((key.as_str(), value.as_str()));
+    }
+
+    Ok(ret)
+}
@@ -0,0 +1,168 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    overlay::{insert_background, insert_first, insert_root_attribute},
+    result_status::ResultStatus,
+    svg::{display_string, SVGObject, SVGResult},
+    State,
+};
+
+static THEME_ATTRIBUTE: &'static str = "data-theme";
+static DARK_INK: &'static str = "#e0e0e0";
+static DARK_PAPER: &'static str = "#1e1e1e";
+/// Spellings of black and white used by the render and overlays.
+static LIGHT_INKS: [&'static str; 3] = ["black", "#000", "#000000"];
+static LIGHT_PAPERS: [&'static str; 3] = ["white", "#fff", "#ffffff"];
+
+/// Colours of strokes and text.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    #[default]
+    Light,
+    /// Light strokes and text on dark fills.
+    Dark,
+}
+
+impl Theme {
+    fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    /// Stylesheet swapping the colours of the render, scoped to roots with this theme.
+    /// Only attribute and descendant selectors are used, so resvg applies it as well.
+    /// Colours picked in the configuration are left alone.
+    fn stylesheet(&self) -> Option<String> {
+        let Theme::Dark = self else {
+            return None;
+        };
+
+        let scope = format!("[{THEME_ATTRIBUTE}=\"{}\"]", self.name());
+        let selectors = |attribute: &str, values: &[&str]| {
+            values
+                .iter()
+                .map(|value| format!("{scope} [{attribute}=\"{value}\"]"))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        let mut stylesheet = String::new();
+        // Text and shapes without a fill inherit it
+        let _ = write!(stylesheet, "svg{scope}{{fill:{DARK_INK}}}");
+        let _ = write!(
+            stylesheet,
+            "{}{{stroke:{DARK_INK}}}",
+            selectors("stroke", &LIGHT_INKS)
+        );
+        let _ = write!(
+            stylesheet,
+            "{}{{fill:{DARK_INK}}}",
+            selectors("fill", &LIGHT_INKS)
+        );
+        let _ = write!(
+            stylesheet,
+            "{}{{fill:{DARK_PAPER}}}",
+            selectors("fill", &LIGHT_PAPERS)
+        );
+
+        Some(stylesheet)
+    }
+}
+
+fn opaque() -> f32 {
+    1.0
+}
+
+/// How the render looks, in the app and in exports.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Appearance {
+    /// Fill behind the render, transparent if not set.
+    #[serde(default)]
+    pub background: Option<String>,
+    /// Opacity of the background, from 0 to 1.
+    #[serde(default = "opaque")]
+    pub background_opacity: f32,
+    #[serde(default)]
+    pub theme: Theme,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            background: None,
+            background_opacity: opaque(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Appearance {
+    /// Colour of strokes and text drawn over the render.
+    pub fn ink(&self) -> &'static str {
+        match self.theme {
+            Theme::Light => LIGHT_INKS[0],
+            Theme::Dark => DARK_INK,
+        }
+    }
+
+    /// Adds the background and theme stylesheet to a serialised render.
+    /// Must be applied after overlays are spliced, so the background covers decorations.
+    pub fn apply(&self, mut svg_string: String) -> String {
+        if let Some(stylesheet) = self.theme.stylesheet() {
+            svg_string = insert_first(svg_string, format!("<style>{stylesheet}</style>").as_str());
+            svg_string = insert_root_attribute(svg_string, THEME_ATTRIBUTE, self.theme.name());
+        }
+
+        match &self.background {
+            Some(background) => {
+                insert_background(svg_string, background.as_str(), self.background_opacity)
+            }
+            None => svg_string,
+        }
+    }
+}
+
+/// Sets the appearance of the render in the app and of exports that don't override it.
+/// Returns the render with the new appearance.
+#[tauri::command]
+pub(crate) fn set_appearance(appearance: Appearance, state: tauri::State<State>) -> SVGResult {
+    let mut ret = SVGResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        svg: None,
+    };
+
+    if !appearance.background_opacity.is_finite()
+        || !(0.0..=1.0).contains(&appearance.background_opacity)
+    {
+        ret.message = String::from("Background opacity must be between 0 and 1.");
+        return ret;
+    }
+
+    let mut document = state.write();
+    document.appearance = appearance;
+
+    let document = &mut *document;
+    match &document.svg {
+        Some(svg) => match document.cache.serialise(svg) {
+            Ok(svg_string) => {
+                ret.status = ResultStatus::Ok;
+                ret.message = String::from("Successfully generated SVG");
+                ret.svg = Some(SVGObject::new(display_string(document, svg_string)));
+            }
+            Err(e) => ret.message = e,
+        },
+        // Applied to the next render
+        None => {
+            ret.status = ResultStatus::Ok;
+            ret.message = String::from("Appearance saved.");
+        }
+    }
+
+    ret
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::system::SystemSnapshot;
+
+/// Output direction of a router channel.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Direction {
+    North,
+    East,
+    South,
+    West,
+    /// Through-silicon via to the same position of the layer above, stacked meshes only.
+    Up,
+    /// Through-silicon via to the same position of the layer below, stacked meshes only.
+    Down,
+}
+
+impl Direction {
+    /// Whether the channel leaves the layer.
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "North" => Ok(Direction::North),
+            "East" => Ok(Direction::East),
+            "South" => Ok(Direction::South),
+            "West" => Ok(Direction::West),
+            "Up" => Ok(Direction::Up),
+            "Down" => Ok(Direction::Down),
+            _ => Err(format!("Unknown channel direction \"{s}\".")),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self {
+            Direction::North => "North",
+            Direction::East => "East",
+            Direction::South => "South",
+            Direction::West => "West",
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+        };
+
+        write!(f, "{direction}")
+    }
+}
+
+pub(crate) static DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+pub(crate) static VERTICAL_DIRECTIONS: [Direction; 2] = [Direction::Up, Direction::Down];
+
+/// A directed channel, identified by the core whose router it leaves and its direction.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Channel {
+    pub core: usize,
+    pub direction: Direction,
+}
+
+/// How cores are connected.
+/// Systems are always laid out on a rows x columns grid, the kind decides which routers each channel reaches.
+/// The render only computes routing loads for meshes, routing options are removed for other kinds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum TopologyKind {
+    #[default]
+    Mesh,
+    /// Mesh with wraparound channels along every row and column.
+    Torus,
+    /// Cores connected in id order through their East and West channels, last to first.
+    Ring,
+    /// Channels listed explicitly, unlisted channels are not connected.
+    Irregular(BTreeMap<Channel, usize>),
+    /// 3D mesh of the given number of layers. Each layer is a band of `rows / layers` rows,
+    /// [`Direction::Up`] and [`Direction::Down`] channels connect routers at the same position of adjacent layers.
+    Stacked(usize),
+}
+
+impl TopologyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TopologyKind::Mesh => "Mesh",
+            TopologyKind::Torus => "Torus",
+            TopologyKind::Ring => "Ring",
+            TopologyKind::Irregular(_) => "Irregular",
+            TopologyKind::Stacked(_) => "Stacked",
+        }
+    }
+}
+
+/// Connectivity of the loaded system.
+#[derive(Clone, Debug)]
+pub(crate) struct Topology {
+    pub rows: usize,
+    pub columns: usize,
+    pub kind: TopologyKind,
+    /// Hop counts between every pair of cores, for irregular topologies.
+    distances: Option<Arc<Vec<Vec<usize>>>>,
+}
+
+impl Topology {
+    pub fn from_snapshot(snapshot: &SystemSnapshot) -> Self {
+        let mut ret = Self {
+            rows: snapshot.rows,
+            columns: snapshot.columns,
+            kind: snapshot.topology.clone(),
+            distances: None,
+        };
+
+        if let TopologyKind::Irregular(_) = ret.kind {
+            let distances = (0..ret.core_count())
+                .map(|core| ret.hop_counts(core))
+                .collect();
+            ret.distances = Some(Arc::new(distances));
+        }
+
+        ret
+    }
+
+    pub fn core_count(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// (row, column) of the given core. Cores are numbered row by row.
+    pub fn position(&self, core: usize) -> (usize, usize) {
+        (core / self.columns, core % self.columns)
+    }
+
+    pub fn core_at(&self, row: usize, column: usize) -> usize {
+        row * self.columns + column
+    }
+
+    /// Number of stacked layers, 1 unless the system is a 3D mesh.
+    pub fn layers(&self) -> usize {
+        match self.kind {
+            TopologyKind::Stacked(layers) => layers.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Number of rows of each layer.
+    pub fn layer_rows(&self) -> usize {
+        self.rows / self.layers()
+    }
+
+    /// (layer, row within the layer, column) of the given core.
+    pub fn layer_position(&self, core: usize) -> (usize, usize, usize) {
+        let (row, column) = self.position(core);
+        let layer_rows = self.layer_rows().max(1);
+
+        (row / layer_rows, row % layer_rows, column)
+    }
+
+    /// Directions channels can leave a router through.
+    pub fn directions(&self) -> impl Iterator<Item = Direction> + '_ {
+        let vertical: &[Direction] = match self.kind {
+            TopologyKind::Stacked(_) => &VERTICAL_DIRECTIONS,
+            _ => &[],
+        };
+
+        DIRECTIONS.iter().chain(vertical.iter()).copied()
+    }
+
+    /// Neighbour on the grid in the given direction, ignoring the topology.
+    pub fn grid_neighbour(&self, core: usize, direction: Direction) -> Option<usize> {
+        let (row, column) = self.position(core);
+
+        match direction {
+            Direction::North if row > 0 => Some(self.core_at(row - 1, column)),
+            Direction::South if row + 1 < self.rows => Some(self.core_at(row + 1, column)),
+            Direction::West if column > 0 => Some(self.core_at(row, column - 1)),
+            Direction::East if column + 1 < self.columns => Some(self.core_at(row, column + 1)),
+            // Vertical channels never reach a grid neighbour
+            _ => None,
+        }
+    }
+
+    /// Core reached by leaving `core` through `direction`, if any.
+    pub fn neighbour(&self, core: usize, direction: Direction) -> Option<usize> {
+        let (row, column) = self.position(core);
+        let core_count = self.core_count();
+
+        match &self.kind {
+            TopologyKind::Mesh => self.grid_neighbour(core, direction),
+            TopologyKind::Torus => {
+                let ret = match direction {
+                    Direction::North => self.core_at((row + self.rows - 1) % self.rows, column),
+                    Direction::South => self.core_at((row + 1) % self.rows, column),
+                    Direction::West => {
+                        self.core_at(row, (column + self.columns - 1) % self.columns)
+                    }
+                    Direction::East => self.core_at(row, (column + 1) % self.columns),
+                    Direction::Up | Direction::Down => return None,
+                };
+
+                // Single row or column meshes have no wraparound
+                Some(ret).filter(|neighbour| *neighbour != core)
+            }
+            TopologyKind::Ring if core_count > 1 => match direction {
+                Direction::East => Some((core + 1) % core_count),
+                Direction::West => Some((core + core_count - 1) % core_count),
+                _ => None,
+            },
+            TopologyKind::Ring => None,
+            TopologyKind::Irregular(links) => links.get(&Channel { core, direction }).copied(),
+            TopologyKind::Stacked(_) => {
+                let (layer, _, _) = self.layer_position(core);
+                let layer_size = self.layer_rows() * self.columns;
+
+                match direction {
+                    Direction::Up if layer + 1 < self.layers() => Some(core + layer_size),
+                    Direction::Down if layer > 0 => Some(core - layer_size),
+                    Direction::Up | Direction::Down => None,
+                    // Planar channels stay within the layer
+                    _ => self
+                        .grid_neighbour(core, direction)
+                        .filter(|neighbour| self.layer_position(*neighbour).0 == layer),
+                }
+            }
+        }
+    }
+
+    /// Whether a channel connects routers that are not next to each other on the grid.
+    /// Vertical channels are not wraparound channels.
+    pub fn is_wraparound(&self, channel: &Channel) -> bool {
+        if channel.direction.is_vertical() {
+            return false;
+        }
+
+        match self.neighbour(channel.core, channel.direction) {
+            Some(neighbour) => {
+                self.grid_neighbour(channel.core, channel.direction) != Some(neighbour)
+            }
+            None => false,
+        }
+    }
+
+    /// Every connected channel with the core it reaches.
+    pub fn links(&self) -> Vec<(Channel, usize)> {
+        (0..self.core_count())
+            .flat_map(|core| {
+                self.directions().filter_map(move |direction| {
+                    let channel = Channel { core, direction };
+                    self.neighbour(core, direction).map(|to| (channel, to))
+                })
+            })
+            .collect()
+    }
+
+    /// Breadth first hop counts from `source`. Unreachable cores get `core_count`.
+    fn hop_counts(&self, source: usize) -> Vec<usize> {
+        let mut ret = vec![self.core_count(); self.core_count()];
+        let mut queue = VecDeque::from([source]);
+        ret[source] = 0;
+
+        while let Some(core) = queue.pop_front() {
+            for direction in self.directions() {
+                if let Some(neighbour) = self.neighbour(core, direction) {
+                    if ret[neighbour] == self.core_count() && neighbour != source {
+                        ret[neighbour] = ret[core] + 1;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Shortest path between two cores, trying directions in [`Topology::directions`] order.
+    pub fn shortest_path(
+        &self,
+        source: usize,
+        destination: usize,
+    ) -> Option<Vec<(Channel, usize)>> {
+        let mut previous: BTreeMap<usize, (Channel, usize)> = BTreeMap::new();
+        let mut queue = VecDeque::from([source]);
+
+        while let Some(core) = queue.pop_front() {
+            if core == destination {
+                let mut ret = Vec::new();
+                let mut current = destination;
+                while let Some((channel, from)) = previous.get(&current) {
+                    ret.push((*channel, current));
+                    current = *from;
+                }
+                ret.reverse();
+
+                return Some(ret);
+            }
+
+            for direction in self.directions() {
+                if let Some(neighbour) = self.neighbour(core, direction) {
+                    if neighbour != source && !previous.contains_key(&neighbour) {
+                        previous.insert(neighbour, (Channel { core, direction }, core));
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Minimum number of hops between two cores.
+    /// Unreachable pairs of an irregular topology are `core_count` hops apart.
+    pub fn distance(&self, from: usize, to: usize) -> usize {
+        let (from_row, from_column) = self.position(from);
+        let (to_row, to_column) = self.position(to);
+        let rows = from_row.abs_diff(to_row);
+        let columns = from_column.abs_diff(to_column);
+
+        match &self.kind {
+            TopologyKind::Mesh => rows + columns,
+            TopologyKind::Torus => rows.min(self.rows - rows) + columns.min(self.columns - columns),
+            TopologyKind::Ring => {
+                let forward = from.abs_diff(to);
+                forward.min(self.core_count() - forward)
+            }
+            TopologyKind::Irregular(_) => self
+                .distances
+                .as_ref()
+                .and_then(|distances| distances.get(from)?.get(to).copied())
+                .unwrap_or(self.core_count()),
+            TopologyKind::Stacked(_) => {
+                let (from_layer, from_row, _) = self.layer_position(from);
+                let (to_layer, to_row, _) = self.layer_position(to);
+
+                from_layer.abs_diff(to_layer) + from_row.abs_diff(to_row) + columns
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::{collections::BTreeMap, fs};
+
+use manycore_parser::ManycoreSystem;
+use manycore_svg::{Configuration, UpdateResult};
+use serde::{Deserialize, Serialize};
+use tauri::{api::dialog::FileDialogBuilder, AppHandle, Manager, Window};
+
+use crate::{
+    document::Document,
+    export::ERROR_EVENT,
+    faults::refresh_faults,
+    parse::forget_system_state,
+    result_status::ResultStatus,
+    svg::{generate_svg, reapply_configuration, refresh_snapshot, SVGObject, SVGResult},
+    system::{
+        patch, patch_in_place, read_topology, reparse, serialise, AttributeUpdate, ElementId,
+    },
+    topology::TopologyKind,
+    State,
+};
+
+static UPDATE_APPLIED_EVENT: &'static str = "update_applied";
+
+/// Attribute value in an update message. Numbers and booleans are stored as text.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Text(String),
+    Number(serde_json::Number),
+    Boolean(bool),
+}
+
+impl From<AttributeValue> for String {
+    fn from(value: AttributeValue) -> Self {
+        match value {
+            AttributeValue::Text(text) => text,
+            AttributeValue::Number(number) => number.to_string(),
+            AttributeValue::Boolean(boolean) => boolean.to_string(),
+        }
+    }
+}
+
+/// A batch of attribute updates.
+///
+/// The compact form maps element ids to attribute/value pairs, `null` removes an attribute:
+/// `{"core:3": {"temperature": 50}, "channel:3:North": {"actualComCost": 12}}`.
+/// The list form is an array of [`AttributeUpdate`].
+/// Update files contain a single message in either form.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum UpdateMessage {
+    Compact(BTreeMap<ElementId, BTreeMap<String, Option<AttributeValue>>>),
+    List(Vec<AttributeUpdate>),
+}
+
+impl UpdateMessage {
+    pub fn into_updates(self) -> Vec<AttributeUpdate> {
+        match self {
+            UpdateMessage::Compact(elements) => elements
+                .into_iter()
+                .flat_map(|(element, attributes)| {
+                    attributes
+                        .into_iter()
+                        .map(move |(attribute, value)| AttributeUpdate {
+                            element,
+                            attribute,
+                            value: value.map(String::from),
+                        })
+                })
+                .collect(),
+            UpdateMessage::List(updates) => updates,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UpdateApplyResult {
+    pub status: ResultStatus,
+    pub message: String,
+    /// Elements whose attributes changed.
+    changed: Vec<ElementId>,
+    /// Refreshed configurable groups, when a configuration has been applied.
+    update: Option<UpdateResult>,
+    /// Whole render, when no configuration has been applied yet.
+    svg: Option<SVGObject>,
+}
+
+impl UpdateApplyResult {
+    pub(crate) fn error(message: String) -> Self {
+        Self {
+            status: ResultStatus::Error,
+            message,
+            changed: Vec::new(),
+            update: None,
+            svg: None,
+        }
+    }
+}
+
+/// Stores a new system and renders it.
+pub(crate) fn replace_system(document: &mut Document, manycore: ManycoreSystem) -> SVGResult {
+    let mut ret = SVGResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        svg: None,
+    };
+
+    generate_svg(&mut ret, document, &manycore);
+    let _ = document.manycore.insert(manycore);
+
+    ret
+}
+
+/// Stores a new system and renders it, like loading a file does.
+/// Work started for the previous system is cancelled and state tied to it dropped.
+/// `source_path` is the file the system was read from, if any.
+pub(crate) fn load_system(
+    state: &State,
+    document: &mut Document,
+    manycore: ManycoreSystem,
+    topology: TopologyKind,
+    source_path: Option<String>,
+) -> SVGResult {
+    // Work started for the previous system is stale
+    state.load_generation.next();
+    forget_system_state(state, document);
+    // Render with the new system's topology
+    document.topology = topology;
+    document.source_path = source_path;
+
+    replace_system(document, manycore)
+}
+
+/// Parses a system from an XML string and loads it. Pushed systems are not backed by a file.
+pub(crate) fn replace_system_xml(state: &State, document: &mut Document, xml: &str) -> SVGResult {
+    match reparse(xml).and_then(|manycore| Ok((manycore, read_topology(xml)?))) {
+        Ok((manycore, topology)) => load_system(state, document, manycore, topology, None),
+        Err(e) => SVGResult {
+            status: ResultStatus::Error,
+            message: e,
+            svg: None,
+        },
+    }
+}
+
+/// Elements targeted by `updates`, each once.
+fn changed_elements(updates: &[AttributeUpdate]) -> Vec<ElementId> {
+    let mut changed = updates
+        .iter()
+        .map(|update| update.element)
+        .collect::<Vec<ElementId>>();
+    changed.sort();
+    changed.dedup();
+
+    changed
+}
+
+/// Whether `configuration` shows the attribute an update changes.
+fn displays(configuration: &Configuration, update: &AttributeUpdate) -> bool {
+    match update.element {
+        ElementId::Core(_) => configuration.core_config().contains_key(&update.attribute),
+        ElementId::Router(_) => configuration
+            .router_config()
+            .contains_key(&update.attribute),
+        // Routing loads can be read from any channel attribute
+        ElementId::Channel(_) => !configuration.channel_config().is_empty(),
+    }
+}
+
+/// Refreshes what is derived from the loaded system after it was patched in place.
+/// The configurable groups of the render are only regenerated if the applied configuration
+/// shows an updated attribute, the rest of the render can't depend on them.
+fn refresh_patched_system(
+    document: &mut Document,
+    updates: &[AttributeUpdate],
+) -> UpdateApplyResult {
+    let changed = changed_elements(updates);
+
+    if let Some(snapshot) = &mut document.snapshot {
+        snapshot.apply(updates);
+    }
+    let _ = refresh_faults(document);
+
+    let displayed = document
+        .configuration
+        .as_ref()
+        .is_some_and(|whole_configuration| {
+            updates
+                .iter()
+                .any(|update| displays(&whole_configuration.configuration, update))
+        });
+    let update = match document.manycore.take() {
+        Some(mut manycore) if displayed => {
+            let update = reapply_configuration(document, &mut manycore);
+            let _ = document.manycore.insert(manycore);
+
+            match update {
+                Some(Ok(update)) => Some(update),
+                // The system is patched already, say what changed
+                Some(Err(e)) => {
+                    return UpdateApplyResult {
+                        status: ResultStatus::Error,
+                        message: format!(
+                            "Updated {} elements, but the render could not be refreshed: {e}",
+                            changed.len()
+                        ),
+                        changed,
+                        update: None,
+                        svg: None,
+                    };
+                }
+                None => None,
+            }
+        }
+        manycore => {
+            document.manycore = manycore;
+            None
+        }
+    };
+
+    UpdateApplyResult {
+        status: ResultStatus::Ok,
+        message: format!("Updated {} elements.", changed.len()),
+        changed,
+        update,
+        svg: None,
+    }
+}
+
+/// Stores a system reparsed with updates applied.
+/// Only the configurable groups of the render are refreshed, unless no configuration
+/// has been applied yet, in which case the whole render is generated.
+fn store_updated_system(
+    document: &mut Document,
+    updated: Result<ManycoreSystem, String>,
+    updates: &[AttributeUpdate],
+) -> UpdateApplyResult {
+    let mut manycore = match updated {
+        Ok(manycore) => manycore,
+        Err(e) => return UpdateApplyResult::error(e),
+    };
+
+    let changed = changed_elements(updates);
+
+    let mut ret = match reapply_configuration(document, &mut manycore) {
+        Some(Ok(update)) => {
+            refresh_snapshot(document, &manycore);
+            let _ = refresh_faults(document);
+
+            UpdateApplyResult {
+                status: ResultStatus::Ok,
+                message: format!("Updated {} elements.", changed.len()),
+                changed,
+                update: Some(update),
+                svg: None,
+            }
+        }
+        Some(Err(e)) => return UpdateApplyResult::error(e),
+        None => {
+            let mut svg_result = SVGResult {
+                status: ResultStatus::Error,
+                message: String::from("Something went wrong, please try again."),
+                svg: None,
+            };
+            generate_svg(&mut svg_result, document, &manycore);
+
+            UpdateApplyResult {
+                status: svg_result.status,
+                message: svg_result.message,
+                changed,
+                update: None,
+                svg: svg_result.svg,
+            }
+        }
+    };
+
+    if let ResultStatus::Ok = ret.status {
+        let _ = document.manycore.insert(manycore);
+    } else {
+        ret.changed.clear();
+    }
+
+    ret
+}
+
+/// Applies attribute updates to the loaded system.
+/// Value changes are patched into the loaded system, see [`patch_in_place`]. Other updates
+/// reparse it.
+pub(crate) fn apply_updates(
+    document: &mut Document,
+    updates: &[AttributeUpdate],
+) -> UpdateApplyResult {
+    let Some(manycore) = &mut document.manycore else {
+        return UpdateApplyResult::error(String::from("Load a system before applying updates."));
+    };
+
+    if patch_in_place(manycore, updates) {
+        return refresh_patched_system(document, updates);
+    }
+
+    let updated = serialise(manycore)
+        .and_then(|xml| patch(xml.as_str(), updates))
+        .and_then(|xml| reparse(xml.as_str()));
+
+    store_updated_system(document, updated, updates)
+}
+
+/// Applies an [`UpdateMessage`] to the loaded system.
+#[tauri::command]
+pub fn apply_update_message(
+    message: UpdateMessage,
+    state: tauri::State<State>,
+) -> UpdateApplyResult {
+    apply_updates(&mut state.write(), &message.into_updates())
+}
+
+/// Picks an update file from disk and applies it to the loaded system.
+pub(crate) fn load_updates(window: Window, handle: AppHandle) {
+    FileDialogBuilder::new()
+        .add_filter("ManyCore Visualiser Updates", &["json"])
+        .pick_file(move |file_path| {
+            if let Some(file_path) = file_path {
+                let message = fs::read_to_string(file_path)
+                    .map_err(|e| format!("Could not open update file: {e}"))
+                    .and_then(|content| {
+                        serde_json::from_str::<UpdateMessage>(content.as_str())
+                            .map_err(|e| format!("Could not process provided updates: {e}"))
+                    });
+
+                match message {
+                    Ok(message) => {
+                        let state = handle.state::<State>();
+                        let result = apply_updates(&mut state.write(), &message.into_updates());
+
+                        // Event payloads must be Clone, go through a Value
+                        match serde_json::to_value(&result) {
+                            Ok(payload) => {
+                                let _ = window.emit(UPDATE_APPLIED_EVENT, payload);
+                            }
+                            Err(e) => {
+                                let _ = window.emit(ERROR_EVENT, e.to_string());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = window.emit(ERROR_EVENT, e);
+                    }
+                }
+            }
+        });
+}
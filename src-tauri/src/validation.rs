@@ -0,0 +1,258 @@
+use manycore_parser::{AttributeType, ProcessedAttribute, RoutingAlgorithms};
+use manycore_svg::{Configuration, FieldConfiguration};
+use serde::Serialize;
+
+use crate::{
+    document::Document, export::WholeConfiguration, result_status::ResultStatus,
+    topology::TopologyKind, State,
+};
+
+// Configuration groups, named as in configuration files
+static CORE_CONFIG: &'static str = "coreConfig";
+static ROUTER_CONFIG: &'static str = "routerConfig";
+static CHANNEL_CONFIG: &'static str = "channelConfig";
+static CORE_FILLS: &'static str = "coreFills";
+static ROUTER_FILLS: &'static str = "routerFills";
+/// Mismatches listed in dialogs, the rest are counted.
+static DESCRIBED_MISMATCHES: usize = 10;
+
+/// A configuration option that does not fit the loaded system.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Mismatch {
+    /// Configuration group of the option, e.g. `coreConfig`.
+    pub group: &'static str,
+    /// Attribute name or fill override id.
+    pub key: String,
+    pub reason: String,
+}
+
+impl Mismatch {
+    fn new(group: &'static str, key: impl ToString, reason: String) -> Self {
+        Self {
+            group,
+            key: key.to_string(),
+            reason,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ValidationResult {
+    status: ResultStatus,
+    message: String,
+    mismatches: Vec<Mismatch>,
+}
+
+/// Name of a configuration option's kind, as shown to the user.
+fn option_name(option: &FieldConfiguration) -> &'static str {
+    match option {
+        FieldConfiguration::Boolean(..) => "Boolean",
+        FieldConfiguration::Coordinates(..) => "Coordinates",
+        FieldConfiguration::Routing(..) => "Routing",
+        FieldConfiguration::Fill(..) => "Fill",
+        FieldConfiguration::ColouredText(..) => "ColouredText",
+        _ => "Text",
+    }
+}
+
+/// Whether `option` can display `attribute`.
+fn compatible(option: &FieldConfiguration, attribute: &ProcessedAttribute) -> bool {
+    match option {
+        FieldConfiguration::Boolean(..) => matches!(attribute._type(), AttributeType::Boolean),
+        FieldConfiguration::Coordinates(..) => {
+            matches!(attribute._type(), AttributeType::Coordinates)
+        }
+        FieldConfiguration::Routing(..) => matches!(attribute._type(), AttributeType::Routing),
+        FieldConfiguration::Fill(..) | FieldConfiguration::ColouredText(..) => {
+            matches!(attribute._type(), AttributeType::Number)
+        }
+        _ => true,
+    }
+}
+
+/// Checks the attributes, routing algorithms and fill override ids `configuration` refers to
+/// exist in the loaded system.
+pub(crate) fn validate(
+    document: &Document,
+    configuration: &Configuration,
+) -> Result<Vec<Mismatch>, String> {
+    let (Some(manycore), Some(snapshot)) = (&document.manycore, &document.snapshot) else {
+        return Err(String::from("You must load a system first."));
+    };
+    let attributes = manycore.configurable_attributes();
+    let core_count = snapshot.cores.len();
+
+    let mut mismatches = Vec::new();
+    let groups = [
+        (
+            CORE_CONFIG,
+            "core",
+            configuration.core_config(),
+            attributes.core(),
+        ),
+        (
+            ROUTER_CONFIG,
+            "router",
+            configuration.router_config(),
+            attributes.router(),
+        ),
+        (
+            CHANNEL_CONFIG,
+            "channel",
+            configuration.channel_config(),
+            attributes.channel(),
+        ),
+    ];
+
+    for (group, element, options, system_attributes) in groups {
+        for (key, option) in options {
+            let Some(attribute) = system_attributes.get(key) else {
+                mismatches.push(Mismatch::new(
+                    group,
+                    key,
+                    format!("The system has no {element} attribute named {key}."),
+                ));
+                continue;
+            };
+
+            if !compatible(option, attribute) {
+                let attribute_type = format!("{:?}", attribute._type()).to_lowercase();
+                mismatches.push(Mismatch::new(
+                    group,
+                    key,
+                    format!(
+                        "{} can't display {key}, which is a {attribute_type}.",
+                        option_name(option)
+                    ),
+                ));
+                continue;
+            }
+
+            let FieldConfiguration::Routing(routing) = option else {
+                continue;
+            };
+            if document.topology != TopologyKind::Mesh {
+                mismatches.push(Mismatch::new(
+                    group,
+                    key,
+                    format!(
+                        "Routing loads are only computed for meshes, the system is a {}.",
+                        document.topology.name()
+                    ),
+                ));
+            } else if !attributes.algorithms().contains(routing.algorithm())
+                || (*routing.algorithm() == RoutingAlgorithms::Observed
+                    && attributes.observed_algorithm().is_none())
+            {
+                mismatches.push(Mismatch::new(
+                    group,
+                    key,
+                    format!(
+                        "The system does not support the {:?} routing algorithm.",
+                        routing.algorithm()
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Every core has a router with its id
+    for (group, fills) in [
+        (CORE_FILLS, configuration.core_fills()),
+        (ROUTER_FILLS, configuration.router_fills()),
+    ] {
+        for id in fills.keys() {
+            if !id
+                .to_string()
+                .parse::<usize>()
+                .is_ok_and(|id| id < core_count)
+            {
+                mismatches.push(Mismatch::new(
+                    group,
+                    id,
+                    format!("The system has no element with id {id}."),
+                ));
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Removes the mismatched options from a configuration, to apply the rest.
+pub(crate) fn without_mismatches(configuration: &mut Configuration, mismatches: &[Mismatch]) {
+    for mismatch in mismatches {
+        let key = mismatch.key.as_str();
+
+        if mismatch.group == CORE_CONFIG {
+            configuration.core_config_mut().remove(key);
+        } else if mismatch.group == ROUTER_CONFIG {
+            configuration.router_config_mut().remove(key);
+        } else if mismatch.group == CHANNEL_CONFIG {
+            configuration.channel_config_mut().remove(key);
+        } else if mismatch.group == CORE_FILLS {
+            configuration
+                .core_fills_mut()
+                .retain(|id, _| id.to_string() != key);
+        } else if mismatch.group == ROUTER_FILLS {
+            configuration
+                .router_fills_mut()
+                .retain(|id, _| id.to_string() != key);
+        }
+    }
+}
+
+/// Lists `mismatches` for the user, the first few of them if there are many.
+pub(crate) fn describe(mismatches: &[Mismatch]) -> String {
+    let mut lines = mismatches
+        .iter()
+        .take(DESCRIBED_MISMATCHES)
+        .map(|mismatch| format!("{} {}: {}", mismatch.group, mismatch.key, mismatch.reason))
+        .collect::<Vec<String>>();
+    if mismatches.len() > DESCRIBED_MISMATCHES {
+        lines.push(format!(
+            "...and {} more.",
+            mismatches.len() - DESCRIBED_MISMATCHES
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Checks a configuration (provided as [`String`] to avoid serde back and forth) fits the loaded
+/// system before applying it.
+#[tauri::command]
+pub fn validate_configuration(
+    whole_configuration: String,
+    state: tauri::State<State>,
+) -> ValidationResult {
+    let mut ret = ValidationResult {
+        status: ResultStatus::Error,
+        message: String::from("Something went wrong, please try again."),
+        mismatches: Vec::new(),
+    };
+
+    let whole_configuration =
+        match serde_json::from_str::<WholeConfiguration>(whole_configuration.as_str()) {
+            Ok(whole_configuration) => whole_configuration,
+            Err(e) => {
+                ret.message = format!("Could not process provided configuration: {e}");
+                return ret;
+            }
+        };
+
+    match validate(&state.read(), &whole_configuration.configuration) {
+        Ok(mismatches) => {
+            ret.status = ResultStatus::Ok;
+            ret.message = match mismatches.len() {
+                0 => String::from("The configuration fits the loaded system."),
+                count => format!("{count} options don't fit the loaded system."),
+            };
+            ret.mismatches = mismatches;
+        }
+        Err(e) => ret.message = e,
+    }
+
+    ret
+}
@@ -11,12 +11,14 @@ import React, {
   useState,
 } from "react";
 import RustEvents from "./components/RustEvents";
+import Analysis from "./components/Analysis";
 import Controls from "./components/Controls";
 import FileLoader from "./components/FileLoader";
 import Graph from "./components/Graph";
 import HoverInfo from "./components/HoverInfo";
 import Loading from "./components/Loading";
 import { ModalContext } from "./components/Modal";
+import NewSystemModal from "./components/NewSystemModal";
 import Settings from "./components/Settings";
 import {
   ConfigurableBaseConfigurationT,
@@ -169,6 +171,7 @@ function App() {
       }}
     >
       <RustEvents />
+      <NewSystemModal />
       <HoverInfo />
       {(processingInput || editing) && <Loading />}
       {!svg && <FileLoader />}
@@ -184,6 +187,7 @@ function App() {
             <Graph />
           </ModalContext.Provider>
           <Controls />
+          <Analysis />
         </>
       )}
     </AppStateContext.Provider>
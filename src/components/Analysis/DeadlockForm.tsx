@@ -0,0 +1,105 @@
+import { invoke } from "@tauri-apps/api";
+import { useState } from "react";
+import toast from "react-hot-toast";
+import { useAppContext } from "../../App";
+import { DeadlockResponseT } from "../../types/analysis";
+import { SVGResponseT } from "../../types/svg";
+import Modal, { useModalContext } from "../Modal";
+import { ROUTING_ALGORITHMS } from "./RouteTraceForm";
+import "../Settings/select.css";
+
+export const DEADLOCK_MODAL = "detect_deadlocks";
+// Falls back to the routing algorithm of the loaded system
+const SYSTEM_ALGORITHM = "System";
+
+const DeadlockForm: React.FunctionComponent = () => {
+  const ctx = useAppContext();
+  const { setDisplay } = useModalContext();
+  const [algorithm, setAlgorithm] = useState(SYSTEM_ALGORITHM);
+
+  function closeModal() {
+    setDisplay((prev) => (prev === DEADLOCK_MODAL ? null : prev));
+  }
+
+  function handleDetect() {
+    invoke<DeadlockResponseT>("detect_deadlocks", {
+      algorithm: algorithm === SYSTEM_ALGORITHM ? undefined : algorithm,
+    })
+      .then((res) => {
+        if (res.status === "ok") {
+          if (res.svg) ctx.setSVG(res.svg);
+          if (res.report?.cycles.length) {
+            toast(res.message, { duration: 10000 });
+          } else {
+            toast.success(res.message);
+          }
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  function handleClear() {
+    invoke<SVGResponseT>("clear_deadlock_overlay")
+      .then((res) => {
+        if (res.status === "ok") {
+          ctx.setSVG(res.svg!);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  return (
+    <Modal name={DEADLOCK_MODAL}>
+      <div className="flex flex-col md:w-[50vw] lg:w-[40vw] xl:w-[30vw] 2xl:w-[22vw]">
+        <h5 className="text-3xl text-indigo-500">Detect deadlocks</h5>
+        <div className="grid grid-cols-2 text-lg gap-4 py-4 items-center">
+          <label htmlFor="deadlockAlgorithm">Routing algorithm:</label>
+          <div className="content dropdown-wrapper">
+            <select
+              id="deadlockAlgorithm"
+              className="appearance-none dropdown"
+              value={algorithm}
+              onChange={(ev) => setAlgorithm(ev.target.value)}
+            >
+              <option value={SYSTEM_ALGORITHM}>System</option>
+              {ROUTING_ALGORITHMS.map((algorithm) => (
+                <option key={algorithm} value={algorithm}>
+                  {algorithm}
+                </option>
+              ))}
+            </select>
+          </div>
+        </div>
+        <div className="ml-auto pt-6 grid grid-cols-3 gap-4">
+          <button
+            onClick={handleDetect}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Detect
+          </button>
+          <button
+            onClick={handleClear}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Clear
+          </button>
+          <button
+            onClick={closeModal}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Cancel
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+export default DeadlockForm;
@@ -0,0 +1,57 @@
+import { RouteEndpointT } from "../../types/analysis";
+import "../Settings/number.css";
+import "../Settings/select.css";
+
+type EndpointInputProps = {
+  id: string;
+  label: string;
+  endpoint: RouteEndpointT;
+  setEndpoint: React.Dispatch<React.SetStateAction<RouteEndpointT>>;
+};
+
+// A task or core a route starts or ends at.
+const EndpointInput: React.FunctionComponent<EndpointInputProps> = ({
+  id,
+  label,
+  endpoint,
+  setEndpoint,
+}) => {
+  return (
+    <>
+      <label htmlFor={id}>{label}:</label>
+      <div className="flex items-center">
+        <div className="content dropdown-wrapper">
+          <select
+            className="appearance-none dropdown"
+            value={endpoint.type}
+            onChange={(ev) =>
+              setEndpoint((prev) => ({
+                ...prev,
+                type: ev.target.value as RouteEndpointT["type"],
+              }))
+            }
+          >
+            <option value="Task">Task</option>
+            <option value="Core">Core</option>
+          </select>
+        </div>
+        <input
+          id={id}
+          type="number"
+          className="number w-20 ml-4"
+          min={0}
+          value={endpoint.id}
+          onChange={(ev) => {
+            const value = parseInt(ev.target.value);
+            setEndpoint((prev) => ({
+              ...prev,
+              id: isNaN(value) || value < 0 ? 0 : value,
+            }));
+          }}
+        ></input>
+      </div>
+    </>
+  );
+};
+
+export default EndpointInput;
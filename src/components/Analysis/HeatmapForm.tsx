@@ -0,0 +1,191 @@
+import { invoke } from "@tauri-apps/api";
+import { useState } from "react";
+import toast from "react-hot-toast";
+import { useAppContext } from "../../App";
+import {
+  ColourMapT,
+  ElementKindT,
+  HeatmapResponseT,
+  PlacementT,
+} from "../../types/analysis";
+import { SVGResponseT } from "../../types/svg";
+import Modal, { useModalContext } from "../Modal";
+import PlacementSelect from "./PlacementSelect";
+import "../Settings/number.css";
+import "../Settings/select.css";
+
+export const HEATMAP_MODAL = "heatmap";
+const COLOUR_MAPS: ColourMapT[] = ["Viridis", "Magma", "Diverging"];
+const ATTRIBUTE_GROUPS = {
+  Core: "core",
+  Router: "router",
+  Channel: "channel",
+} as const;
+
+// Reads an optional scale bound, undefined if empty or invalid
+function parseBound(value: string) {
+  const bound = parseFloat(value);
+
+  return isNaN(bound) ? undefined : bound;
+}
+
+const HeatmapForm: React.FunctionComponent = () => {
+  const ctx = useAppContext();
+  const { setDisplay } = useModalContext();
+  const [element, setElement] = useState<ElementKindT>("Core");
+  const [attribute, setAttribute] = useState("");
+  const [colourMap, setColourMap] = useState<ColourMapT>("Viridis");
+  const [min, setMin] = useState<number>();
+  const [max, setMax] = useState<number>();
+  const [legendPlacement, setLegendPlacement] = useState<PlacementT>("Right");
+
+  // Only numeric attributes can be coloured
+  const attributes = Object.entries(
+    ctx.attributes?.[ATTRIBUTE_GROUPS[element]] ?? {}
+  ).filter(([, content]) => content.type === "number");
+  const selectedAttribute = attributes.some(([key]) => key === attribute)
+    ? attribute
+    : attributes[0]?.[0] ?? "";
+
+  function closeModal() {
+    setDisplay((prev) => (prev === HEATMAP_MODAL ? null : prev));
+  }
+
+  function handleShow() {
+    invoke<HeatmapResponseT>("set_heatmap", {
+      configuration: {
+        element,
+        attribute: selectedAttribute,
+        colourMap,
+        min,
+        max,
+        legendPlacement,
+      },
+    })
+      .then((res) => {
+        if (res.status === "ok") {
+          if (res.svg) ctx.setSVG(res.svg);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  function handleClear() {
+    invoke<SVGResponseT>("clear_heatmap")
+      .then((res) => {
+        if (res.status === "ok") {
+          ctx.setSVG(res.svg!);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  return (
+    <Modal name={HEATMAP_MODAL}>
+      <div className="flex flex-col md:w-[50vw] lg:w-[40vw] xl:w-[30vw] 2xl:w-[22vw]">
+        <h5 className="text-3xl text-indigo-500">Heatmap</h5>
+        <div className="grid grid-cols-2 text-lg gap-4 py-4 items-center">
+          <label htmlFor="heatmapElement">Element:</label>
+          <div className="content dropdown-wrapper">
+            <select
+              id="heatmapElement"
+              className="appearance-none dropdown"
+              value={element}
+              onChange={(ev) => setElement(ev.target.value as ElementKindT)}
+            >
+              {Object.keys(ATTRIBUTE_GROUPS).map((element) => (
+                <option key={element} value={element}>
+                  {element}
+                </option>
+              ))}
+            </select>
+          </div>
+          <label htmlFor="heatmapAttribute">Attribute:</label>
+          <div className="content dropdown-wrapper">
+            <select
+              id="heatmapAttribute"
+              className="appearance-none dropdown"
+              disabled={attributes.length === 0}
+              value={selectedAttribute}
+              onChange={(ev) => setAttribute(ev.target.value)}
+            >
+              {attributes.map(([key, content]) => (
+                <option key={key} value={key}>
+                  {content.display}
+                </option>
+              ))}
+            </select>
+          </div>
+          <label htmlFor="heatmapColourMap">Colour map:</label>
+          <div className="content dropdown-wrapper">
+            <select
+              id="heatmapColourMap"
+              className="appearance-none dropdown"
+              value={colourMap}
+              onChange={(ev) => setColourMap(ev.target.value as ColourMapT)}
+            >
+              {COLOUR_MAPS.map((colourMap) => (
+                <option key={colourMap} value={colourMap}>
+                  {colourMap}
+                </option>
+              ))}
+            </select>
+          </div>
+          <label htmlFor="heatmapMin">Minimum:</label>
+          <input
+            id="heatmapMin"
+            type="number"
+            className="number w-28"
+            placeholder="From data"
+            onChange={(ev) => setMin(parseBound(ev.target.value))}
+          ></input>
+          <label htmlFor="heatmapMax">Maximum:</label>
+          <input
+            id="heatmapMax"
+            type="number"
+            className="number w-28"
+            placeholder="From data"
+            onChange={(ev) => setMax(parseBound(ev.target.value))}
+          ></input>
+          <label htmlFor="heatmapLegendPlacement">Legend:</label>
+          <PlacementSelect
+            id="heatmapLegendPlacement"
+            placement={legendPlacement}
+            setPlacement={setLegendPlacement}
+          />
+        </div>
+        <div className="ml-auto pt-6 grid grid-cols-3 gap-4">
+          <button
+            onClick={handleShow}
+            disabled={!selectedAttribute}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Show
+          </button>
+          <button
+            onClick={handleClear}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Clear
+          </button>
+          <button
+            onClick={closeModal}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Cancel
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+export default HeatmapForm;
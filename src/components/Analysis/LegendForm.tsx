@@ -0,0 +1,81 @@
+import { invoke } from "@tauri-apps/api";
+import { useState } from "react";
+import toast from "react-hot-toast";
+import { useAppContext } from "../../App";
+import { LegendResponseT, PlacementT } from "../../types/analysis";
+import Modal, { useModalContext } from "../Modal";
+import PlacementSelect from "./PlacementSelect";
+import "../Settings/checkbox.css";
+
+export const LEGEND_MODAL = "legend";
+
+const LegendForm: React.FunctionComponent = () => {
+  const ctx = useAppContext();
+  const { setDisplay } = useModalContext();
+  const [enabled, setEnabled] = useState(false);
+  const [placement, setPlacement] = useState<PlacementT>("Right");
+
+  function closeModal() {
+    setDisplay((prev) => (prev === LEGEND_MODAL ? null : prev));
+  }
+
+  function handleApply() {
+    invoke<LegendResponseT>("set_legend", {
+      placement: enabled ? placement : undefined,
+    })
+      .then((res) => {
+        if (res.status === "ok") {
+          if (res.svg) ctx.setSVG(res.svg);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  return (
+    <Modal name={LEGEND_MODAL}>
+      <div className="flex flex-col md:w-[50vw] lg:w-[40vw] xl:w-[30vw] 2xl:w-[22vw]">
+        <h5 className="text-3xl text-indigo-500">Legend</h5>
+        <div className="grid grid-cols-2 text-lg gap-4 py-4 items-center">
+          <div className="flex items-center checkbox-container col-span-2">
+            <input
+              type="checkbox"
+              className="checkbox"
+              id="legendEnabled"
+              checked={enabled}
+              onChange={(ev) => setEnabled(ev.target.checked)}
+            ></input>
+            <label htmlFor="legendEnabled">
+              Explain the configured colours
+            </label>
+          </div>
+          <label htmlFor="legendPlacement">Position:</label>
+          <PlacementSelect
+            id="legendPlacement"
+            placement={placement}
+            setPlacement={setPlacement}
+          />
+        </div>
+        <div className="ml-auto pt-6 grid grid-cols-2 gap-4">
+          <button
+            onClick={handleApply}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Apply
+          </button>
+          <button
+            onClick={closeModal}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Cancel
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+export default LegendForm;
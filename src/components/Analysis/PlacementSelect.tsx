@@ -0,0 +1,36 @@
+import { PlacementT } from "../../types/analysis";
+import "../Settings/select.css";
+
+const PLACEMENTS: PlacementT[] = ["Top", "Right", "Bottom", "Left"];
+
+type PlacementSelectProps = {
+  id: string;
+  placement: PlacementT;
+  setPlacement: (placement: PlacementT) => void;
+};
+
+// Side of the render a legend is drawn on.
+const PlacementSelect: React.FunctionComponent<PlacementSelectProps> = ({
+  id,
+  placement,
+  setPlacement,
+}) => {
+  return (
+    <div className="content dropdown-wrapper">
+      <select
+        id={id}
+        className="appearance-none dropdown"
+        value={placement}
+        onChange={(ev) => setPlacement(ev.target.value as PlacementT)}
+      >
+        {PLACEMENTS.map((placement) => (
+          <option key={placement} value={placement}>
+            {placement}
+          </option>
+        ))}
+      </select>
+    </div>
+  );
+};
+
+export default PlacementSelect;
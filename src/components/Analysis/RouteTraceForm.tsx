@@ -0,0 +1,122 @@
+import { invoke } from "@tauri-apps/api";
+import { useState } from "react";
+import toast from "react-hot-toast";
+import { useAppContext } from "../../App";
+import { RouteEndpointT, RouteTraceResponseT } from "../../types/analysis";
+import { SVGResponseT } from "../../types/svg";
+import Modal, { useModalContext } from "../Modal";
+import EndpointInput from "./EndpointInput";
+import "../Settings/select.css";
+
+export const ROUTE_TRACE_MODAL = "trace_route";
+// Algorithms routes can be computed for, observed routing records no paths
+export const ROUTING_ALGORITHMS = ["RowFirst", "ColumnFirst"];
+
+const RouteTraceForm: React.FunctionComponent = () => {
+  const ctx = useAppContext();
+  const { setDisplay } = useModalContext();
+  const [source, setSource] = useState<RouteEndpointT>({
+    type: "Task",
+    id: 0,
+  });
+  const [destination, setDestination] = useState<RouteEndpointT>({
+    type: "Task",
+    id: 1,
+  });
+  const [algorithm, setAlgorithm] = useState(ROUTING_ALGORITHMS[0]);
+
+  function closeModal() {
+    setDisplay((prev) => (prev === ROUTE_TRACE_MODAL ? null : prev));
+  }
+
+  function handleTrace() {
+    invoke<RouteTraceResponseT>("trace_route", {
+      source,
+      destination,
+      algorithm,
+    })
+      .then((res) => {
+        if (res.status === "ok") {
+          if (res.svg) ctx.setSVG(res.svg);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  function handleClear() {
+    invoke<SVGResponseT>("clear_route_trace")
+      .then((res) => {
+        if (res.status === "ok") {
+          ctx.setSVG(res.svg!);
+          toast.success(res.message);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }));
+  }
+
+  return (
+    <Modal name={ROUTE_TRACE_MODAL}>
+      <div className="flex flex-col md:w-[50vw] lg:w-[40vw] xl:w-[30vw] 2xl:w-[22vw]">
+        <h5 className="text-3xl text-indigo-500">Trace route</h5>
+        <div className="grid grid-cols-2 text-lg gap-4 py-4 items-center">
+          <EndpointInput
+            id="routeTraceSource"
+            label="From"
+            endpoint={source}
+            setEndpoint={setSource}
+          />
+          <EndpointInput
+            id="routeTraceDestination"
+            label="To"
+            endpoint={destination}
+            setEndpoint={setDestination}
+          />
+          <label htmlFor="routeTraceAlgorithm">Routing algorithm:</label>
+          <div className="content dropdown-wrapper">
+            <select
+              id="routeTraceAlgorithm"
+              className="appearance-none dropdown"
+              value={algorithm}
+              onChange={(ev) => setAlgorithm(ev.target.value)}
+            >
+              {ROUTING_ALGORITHMS.map((algorithm) => (
+                <option key={algorithm} value={algorithm}>
+                  {algorithm}
+                </option>
+              ))}
+            </select>
+          </div>
+        </div>
+        <div className="ml-auto pt-6 grid grid-cols-3 gap-4">
+          <button
+            onClick={handleTrace}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Trace
+          </button>
+          <button
+            onClick={handleClear}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Clear
+          </button>
+          <button
+            onClick={closeModal}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Cancel
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+export default RouteTraceForm;
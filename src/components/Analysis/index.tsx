@@ -0,0 +1,43 @@
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+import { ModalContext } from "../Modal";
+import DeadlockForm, { DEADLOCK_MODAL } from "./DeadlockForm";
+import HeatmapForm, { HEATMAP_MODAL } from "./HeatmapForm";
+import LegendForm, { LEGEND_MODAL } from "./LegendForm";
+import RouteTraceForm, { ROUTE_TRACE_MODAL } from "./RouteTraceForm";
+
+// Opened by the "Analysis" menu items, whose events are named as the modals
+const ANALYSIS_MODALS = [
+  ROUTE_TRACE_MODAL,
+  DEADLOCK_MODAL,
+  HEATMAP_MODAL,
+  LEGEND_MODAL,
+];
+
+// Overlays drawn on the loaded system.
+const Analysis: React.FunctionComponent = () => {
+  const [display, setDisplay] = useState<string | null>(null);
+
+  useEffect(() => {
+    const listeners = ANALYSIS_MODALS.map((modal) =>
+      listen(modal, () => setDisplay(modal))
+    );
+
+    return () => {
+      for (const listener of listeners) {
+        listener.then((unlisten) => unlisten());
+      }
+    };
+  }, []);
+
+  return (
+    <ModalContext.Provider value={{ display, setDisplay }}>
+      <RouteTraceForm />
+      <DeadlockForm />
+      <HeatmapForm />
+      <LegendForm />
+    </ModalContext.Provider>
+  );
+};
+
+export default Analysis;
@@ -0,0 +1,149 @@
+import { invoke } from "@tauri-apps/api";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+import toast from "react-hot-toast";
+import { useAppContext } from "../../App";
+import { NewSystemResponseT } from "../../types/svg";
+import { showNewRender } from "../../utils/loadUtils";
+import Modal, { ModalContext, useModalContext } from "../Modal";
+import "../Settings/checkbox.css";
+import "../Settings/number.css";
+
+const NEW_SYSTEM_MODAL = "newSystem";
+
+// Reads a positive integer input, undefined if empty or invalid
+function parseCount(value: string) {
+  const count = parseInt(value);
+
+  return isNaN(count) || count < 1 ? undefined : count;
+}
+
+const NewSystemForm: React.FunctionComponent = () => {
+  const ctx = useAppContext();
+  const { setDisplay } = useModalContext();
+  const [rows, setRows] = useState(4);
+  const [columns, setColumns] = useState(4);
+  const [taskCount, setTaskCount] = useState<number>();
+  const [randomMapping, setRandomMapping] = useState(true);
+  const [seed, setSeed] = useState<number>();
+
+  function closeModal() {
+    setDisplay((prev) => (prev === NEW_SYSTEM_MODAL ? null : prev));
+  }
+
+  function handleGenerate() {
+    ctx.setProcessingInput(true);
+    invoke<NewSystemResponseT>("new_system", {
+      options: { rows, columns, taskCount, randomMapping, seed },
+    })
+      .then((res) => {
+        if (res.status === "ok") {
+          showNewRender(res.svg!, ctx);
+          toast.success(`${res.message} Seed: ${res.seed}`);
+          closeModal();
+        } else {
+          toast.error(res.message, { duration: 10000 });
+        }
+      })
+      .catch((e) => toast.error(e, { duration: 10000 }))
+      .finally(() => ctx.setProcessingInput(false));
+  }
+
+  return (
+    <Modal name={NEW_SYSTEM_MODAL}>
+      <div className="flex flex-col md:w-[50vw] lg:w-[40vw] xl:w-[30vw] 2xl:w-[22vw]">
+        <h5 className="text-3xl text-indigo-500">New system</h5>
+        <div className="grid grid-cols-2 text-lg gap-4 py-4 items-center">
+          <label htmlFor="newSystemRows">Rows:</label>
+          <input
+            id="newSystemRows"
+            type="number"
+            className="number w-20"
+            min={1}
+            max={255}
+            defaultValue={rows}
+            onChange={(ev) => setRows(parseCount(ev.target.value) ?? 1)}
+          ></input>
+          <label htmlFor="newSystemColumns">Columns:</label>
+          <input
+            id="newSystemColumns"
+            type="number"
+            className="number w-20"
+            min={1}
+            max={255}
+            defaultValue={columns}
+            onChange={(ev) => setColumns(parseCount(ev.target.value) ?? 1)}
+          ></input>
+          <label htmlFor="newSystemTasks">Tasks:</label>
+          <input
+            id="newSystemTasks"
+            type="number"
+            className="number w-20"
+            min={2}
+            placeholder={`${rows * columns}`}
+            onChange={(ev) => setTaskCount(parseCount(ev.target.value))}
+          ></input>
+          <label htmlFor="newSystemSeed">Seed:</label>
+          <input
+            id="newSystemSeed"
+            type="number"
+            className="number w-40"
+            min={0}
+            placeholder="Random"
+            onChange={(ev) => {
+              const value = parseInt(ev.target.value);
+              setSeed(isNaN(value) || value < 0 ? undefined : value);
+            }}
+          ></input>
+          <div className="flex items-center checkbox-container col-span-2">
+            <input
+              type="checkbox"
+              className="checkbox"
+              id="newSystemRandomMapping"
+              checked={randomMapping}
+              onChange={(ev) => setRandomMapping(ev.target.checked)}
+            ></input>
+            <label htmlFor="newSystemRandomMapping">
+              Allocate tasks to random cores
+            </label>
+          </div>
+        </div>
+        <div className="ml-auto pt-6 grid grid-cols-2 gap-4">
+          <button
+            onClick={handleGenerate}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Generate
+          </button>
+          <button
+            onClick={closeModal}
+            className="bg-indigo-300 text-indigo-700 rounded-md px-4 py-2"
+          >
+            Cancel
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+// Wizard opened by the "New system" menu item.
+const NewSystemModal: React.FunctionComponent = () => {
+  const [display, setDisplay] = useState<string | null>(null);
+
+  useEffect(() => {
+    const listener = listen("new_system", () => setDisplay(NEW_SYSTEM_MODAL));
+
+    return () => {
+      listener.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  return (
+    <ModalContext.Provider value={{ display, setDisplay }}>
+      <NewSystemForm />
+    </ModalContext.Provider>
+  );
+};
+
+export default NewSystemModal;
@@ -2,7 +2,15 @@ import { UnlistenFn, listen } from "@tauri-apps/api/event";
 import { useEffect } from "react";
 import toast from "react-hot-toast";
 import { useAppContext } from "../App";
-import { loadNewSystem } from "../utils/loadUtils";
+import { ProgressT, TraceFrameT, UpdateAppliedResponseT } from "../types/svg";
+import { applyUpdatedSystem, loadNewSystem } from "../utils/loadUtils";
+
+// Shown while a system is parsed or rendered
+const STAGE_LABELS: { [stage in ProgressT["stage"]]?: string } = {
+  parsing: "Parsing system...",
+  buildingSvg: "Building render...",
+  serialising: "Preparing render...",
+};
 
 const RustEvents: React.FunctionComponent = () => {
   const ctx = useAppContext();
@@ -27,6 +35,58 @@ const RustEvents: React.FunctionComponent = () => {
       })
     );
 
+    // Progress of long running commands, one indicator per load
+    listeners.push(
+      listen<ProgressT>("progress", (ev) => {
+        const id = `progress${ev.payload.generation}`;
+        const label = STAGE_LABELS[ev.payload.stage];
+
+        if (label) {
+          toast.loading(label, { id });
+        } else {
+          // Done or cancelled, the command reports the outcome
+          toast.dismiss(id);
+        }
+      })
+    );
+
+    // Render events
+    listeners.push(
+      listen<UpdateAppliedResponseT>("update_applied", (ev) => {
+        applyUpdatedSystem(ev.payload, ctx);
+        if (ev.payload.status === "ok") {
+          toast.success(ev.payload.message);
+        } else {
+          showError(ev.payload.message);
+        }
+      })
+    );
+    listeners.push(
+      listen<UpdateAppliedResponseT>("live_render", (ev) => {
+        // Pushes can be frequent, only report failures
+        applyUpdatedSystem(ev.payload, ctx);
+        if (ev.payload.status !== "ok") {
+          showError(ev.payload.message);
+        }
+      })
+    );
+    listeners.push(
+      listen<TraceFrameT>("trace_frame", (ev) => {
+        // Playback stops on failure and reports it through trace_status
+        applyUpdatedSystem(ev.payload, ctx);
+      })
+    );
+    listeners.push(
+      listen<string>("live_status", (ev) => {
+        showError(ev.payload);
+      })
+    );
+    listeners.push(
+      listen<string>("trace_status", (ev) => {
+        toast(ev.payload);
+      })
+    );
+
     // Messaging events
     listeners.push(
       listen<string>("ok_message", (ev) => {
@@ -0,0 +1,49 @@
+import { SVGResponseT } from "./svg";
+
+export type RouteEndpointT = { type: "Task" | "Core"; id: number };
+
+export type RouteTraceT = {
+  sourceCore: number;
+  destinationCore: number;
+  hops: object[];
+  overlay: string;
+};
+
+export interface RouteTraceResponseT extends SVGResponseT {
+  trace?: RouteTraceT;
+}
+
+export type DeadlockReportT = {
+  algorithm: string;
+  channelCount: number;
+  dependencyCount: number;
+  cycles: object[][];
+  overlay: string;
+};
+
+export interface DeadlockResponseT extends SVGResponseT {
+  report?: DeadlockReportT;
+}
+
+export type PlacementT = "Top" | "Right" | "Bottom" | "Left";
+
+export type ElementKindT = "Core" | "Router" | "Channel";
+
+export type ColourMapT = "Viridis" | "Magma" | "Diverging";
+
+export type HeatmapConfigurationT = {
+  element: ElementKindT;
+  attribute: string;
+  colourMap: ColourMapT;
+  min?: number;
+  max?: number;
+  legendPlacement: PlacementT;
+};
+
+export interface HeatmapResponseT extends SVGResponseT {
+  heatmap?: { min: number; max: number; overlay: string; legend: string };
+}
+
+export interface LegendResponseT extends SVGResponseT {
+  legend?: string;
+}
@@ -107,6 +107,7 @@ export type BaseConfigurationT = {
 };
 
 export type WholeConfigurationT = {
+  version?: number;
   baseConfiguration: BaseConfigurationT;
   configuration: ConfigurationT;
 };
@@ -126,3 +127,13 @@ export type DispatchFillOverrideGroupT =
       type: "replace";
       map: Map<number, string>;
     };
+
+export type ConfigurationMismatchT = {
+  group: string;
+  key: string;
+  reason: string;
+};
+
+export interface ValidationResponseT extends BaseResponseT {
+  mismatches: ConfigurationMismatchT[];
+}
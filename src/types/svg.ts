@@ -9,18 +9,42 @@ export interface SVGResponseT extends BaseResponseT {
   svg?: SVGObject;
 }
 
+export interface NewSystemResponseT extends SVGResponseT {
+  // Generates the same system again
+  seed?: number;
+}
+
+export type SVGUpdateGroupsT = {
+  style: string;
+  informationGroup: string;
+  tasksGroup: string;
+  viewBox: string;
+  svg?: string;
+};
+
 export interface SVGUpdateResponseT extends BaseResponseT {
-  update?: {
-    style: string;
-    informationGroup: string;
-    tasksGroup: string;
-    viewBox: string;
-    svg?: string;
-  };
+  update?: SVGUpdateGroupsT;
+  // Whole render, when decorations around it changed
+  svg?: SVGObject;
+}
+
+// Attribute updates applied to the loaded system, from files, the live server or a trace.
+export interface UpdateAppliedResponseT extends SVGUpdateResponseT {
+  changed?: string[];
+}
+
+export interface TraceFrameT extends UpdateAppliedResponseT {
+  cycle: number;
 }
 
 export interface SVGRenderResponseT extends BaseResponseT { }
 
+// Stage of a long running command, reported through the progress event
+export type ProgressT = {
+  generation: number;
+  stage: "parsing" | "buildingSvg" | "serialising" | "done" | "cancelled";
+};
+
 export interface InfoResponseT extends BaseResponseT {
   info?: Object;
 }
@@ -12,7 +12,13 @@ import {
   ProcessedAttributesGroupT,
   ProcessedAttributesT,
 } from "../types/configuration";
-import { SVGResponseT, SVGT, SVGUpdateResponseT } from "../types/svg";
+import {
+  SVGResponseT,
+  SVGT,
+  SVGUpdateGroupsT,
+  SVGUpdateResponseT,
+  UpdateAppliedResponseT,
+} from "../types/svg";
 
 async function loadNewSystem(ctx: AppState) {
   open({
@@ -75,25 +81,10 @@ function updateSVG(
     baseConfiguration,
   }).then((res) => {
     if (res.status === "ok") {
-      if (res.update) {
-        // Was this a full update?
-        if (res.update.svg) {
-          // If it was, replace whole SVG
-          ctx.setSVG({
-            content: res.update.svg,
-            timestamp: new Date().toUTCString(),
-          });
-        } else {
-          // If it wasn't just plug in the new data
-          const timestamp = new Date().toISOString();
-          ctx.setSVGStyle({ timestamp, content: res.update.style });
-          ctx.setSVGInformation({
-            timestamp,
-            content: res.update.informationGroup,
-          });
-          ctx.setSVGTasks({ timestamp, content: res.update.tasksGroup })
-          ctx.setSVGViewbox({ timestamp, content: res.update.viewBox });
-        }
+      if (res.svg) {
+        ctx.setSVG(res.svg);
+      } else if (res.update) {
+        applySVGUpdate(res.update, ctx);
       }
 
       toast.success(res.message);
@@ -103,6 +94,54 @@ function updateSVG(
   });
 }
 
+function applySVGUpdate(update: SVGUpdateGroupsT, ctx: AppState) {
+  // Was this a full update?
+  if (update.svg) {
+    // If it was, replace whole SVG
+    ctx.setSVG({
+      content: update.svg,
+      timestamp: new Date().toUTCString(),
+    });
+  } else {
+    // If it wasn't just plug in the new data
+    const timestamp = new Date().toISOString();
+    ctx.setSVGStyle({ timestamp, content: update.style });
+    ctx.setSVGInformation({
+      timestamp,
+      content: update.informationGroup,
+    });
+    ctx.setSVGTasks({ timestamp, content: update.tasksGroup });
+    ctx.setSVGViewbox({ timestamp, content: update.viewBox });
+  }
+}
+
+// Replaces the render with one of a new or rebuilt system.
+function showNewRender(svg: SVGT, ctx: AppState) {
+  // Reset all customisations
+  ctx.setSVGViewbox(null);
+  ctx.setSVGStyle(null);
+  ctx.setSVGInformation(null);
+
+  ctx.setSVG(svg);
+  // Attributes may have changed with the system
+  getAttributes(ctx.setAttributes);
+}
+
+// Shows the result of updates applied to the loaded system.
+// Only refreshed groups are sent back, or a whole render when the system was
+// replaced or not configured yet.
+function applyUpdatedSystem(res: UpdateAppliedResponseT, ctx: AppState) {
+  if (res.status !== "ok") {
+    return;
+  }
+
+  if (res.svg) {
+    showNewRender(res.svg, ctx);
+  } else if (res.update) {
+    applySVGUpdate(res.update, ctx);
+  }
+}
+
 function getSortedUpdatedAttributes(
   prev: ProcessedAttributesGroupT,
   curr: ProcessedAttributesGroupT
@@ -197,9 +236,11 @@ function editSystem(ctx: AppState) {
 }
 
 export {
+  applyUpdatedSystem,
   editSystem,
   getSVG,
   loadNewSystem,
+  showNewRender,
   startProcessing,
   updateSVG,
   getBaseConfiguration,